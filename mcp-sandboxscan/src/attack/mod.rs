@@ -0,0 +1,9 @@
+pub mod oracle;
+pub mod runner;
+pub mod scenario;
+pub mod verdict;
+
+pub use oracle::judge;
+pub use runner::{AttackRun, run_attack};
+pub use scenario::{AttackScenario, PlantedFile, ScenarioKind};
+pub use verdict::{Confusion, Label, Verdict};
@@ -0,0 +1,204 @@
+use crate::attack::verdict::Verdict;
+use crate::scan::report::ScanReport;
+use crate::taint::source::TaintSource;
+
+use super::scenario::{AttackScenario, INJECTION_CANARY, ScenarioKind};
+
+/*
+decide whether the attack described by a scenario is visible in the scan report
+*/
+
+pub fn judge(scenario: &AttackScenario, report: &ScanReport) -> (Verdict, String) {
+    if let Some(failure) = report.exec.termination.failure() {
+        return (Verdict::Error, failure);
+    }
+    // reports saved before termination was recorded only carry exit_code
+    if let Some(code) = report.exec.exit_code
+        && code != 0
+    {
+        return (Verdict::Error, format!("non-zero exit_code={code}"));
+    }
+
+    match scenario.kind {
+        ScenarioKind::Benign => {
+            if report.summary.has_flow() {
+                (
+                    Verdict::Detected,
+                    "unexpected external-to-sink flow in benign subject".to_string(),
+                )
+            } else {
+                (Verdict::NotDetected, "no flows (expected)".to_string())
+            }
+        }
+        ScenarioKind::EnvLeak | ScenarioKind::FileExfil => judge_source_flow(scenario, report),
+        ScenarioKind::C2Beacon => judge_c2_beacon(report),
+        ScenarioKind::PromptInjection => judge_prompt_injection(scenario, report),
+    }
+}
+
+fn expected_sources(scenario: &AttackScenario) -> Vec<String> {
+    if !scenario.expected_sources.is_empty() {
+        return scenario.expected_sources.clone();
+    }
+    let mut keys: Vec<String> = scenario
+        .env
+        .keys()
+        .map(|key| format!("EnvVar: {key}"))
+        .collect();
+    keys.extend(scenario.files.iter().map(|file| file.path.clone()));
+    keys.sort();
+    keys
+}
+
+fn judge_source_flow(scenario: &AttackScenario, report: &ScanReport) -> (Verdict, String) {
+    let expected = expected_sources(scenario);
+    if !report.summary.has_flow() {
+        return (
+            Verdict::NotDetected,
+            format!("missing {} flow", expected.join(" | ")),
+        );
+    }
+
+    let sink_ok = |sink_type: &str| {
+        scenario.expected_sinks.is_empty()
+            || scenario.expected_sinks.iter().any(|sink| sink == sink_type)
+    };
+    let matched = expected.iter().find(|pattern| {
+        report
+            .flows
+            .iter()
+            .any(|flow| flow.source_id.contains(pattern.as_str()) && sink_ok(&flow.sink_type))
+    });
+    match matched {
+        Some(pattern) => (Verdict::Detected, format!("flow from {pattern}")),
+        None if scenario.expected_sinks.is_empty() => (
+            Verdict::NotDetected,
+            format!("missing {} flow", expected.join(" | ")),
+        ),
+        None => (
+            Verdict::NotDetected,
+            format!(
+                "missing {} -> {} flow",
+                expected.join(" | "),
+                scenario.expected_sinks.join(" | ")
+            ),
+        ),
+    }
+}
+
+fn judge_c2_beacon(report: &ScanReport) -> (Verdict, String) {
+    let has_network_src = report.sources.iter().any(|src| {
+        matches!(
+            src,
+            TaintSource::NetworkConnect { .. } | TaintSource::HttpFetch { .. }
+        )
+    });
+    let sink_mentions_beacon = report.sinks.iter().any(|sink| {
+        let text = sink.as_text();
+        text.contains("beacon") || text.contains("c2.evil") || text.contains("HTTP_FETCH:")
+    });
+    let ok = has_network_src && (report.summary.has_flow() || sink_mentions_beacon);
+    if ok {
+        (
+            Verdict::Detected,
+            "network source with beacon evidence in flows or sinks".to_string(),
+        )
+    } else {
+        (
+            Verdict::NotDetected,
+            "missing network/beacon evidence".to_string(),
+        )
+    }
+}
+
+fn judge_prompt_injection(scenario: &AttackScenario, report: &ScanReport) -> (Verdict, String) {
+    let marker = match scenario.injection_payload.as_deref() {
+        Some(payload) if !payload.contains(INJECTION_CANARY) => payload,
+        _ => INJECTION_CANARY,
+    };
+
+    if report
+        .sinks
+        .iter()
+        .any(|sink| sink.as_text().contains(marker))
+    {
+        (
+            Verdict::Detected,
+            format!("injected payload reached sink ({marker})"),
+        )
+    } else {
+        (
+            Verdict::NotDetected,
+            "injected payload did not reach any sink".to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::exec_evidence::{ExecutionBackend, ExecutionEvidence};
+    use crate::scan::prompt_sink::PromptSink;
+    use crate::taint::flow::{FlowClass, FlowMatch};
+
+    fn empty_report() -> ScanReport {
+        ScanReport {
+            exec: ExecutionEvidence {
+                backend: ExecutionBackend::NativeStdio,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_seeded_file_flow() {
+        let mut report = empty_report();
+        report.summary.has_external_to_prompt_flow = true;
+        report.flows.push(FlowMatch {
+            source_id: "FileRead: /tmp/data/secret.txt".to_string(),
+            sink_type: "McpToolResultText".to_string(),
+            snippet: "top-secret".to_string(),
+            confidence: "high".to_string(),
+            flow_class: FlowClass::ExternalToPrompt,
+        });
+
+        let (verdict, _) = judge(&AttackScenario::file_exfil(), &report);
+        assert_eq!(verdict, Verdict::Detected);
+    }
+
+    #[test]
+    fn requires_declared_sink_kind() {
+        let mut report = empty_report();
+        report.summary.has_external_to_prompt_flow = true;
+        report.flows.push(FlowMatch {
+            source_id: "EnvVar: DEMO_SECRET".to_string(),
+            sink_type: "StdoutPrompt".to_string(),
+            snippet: "SEKRET".to_string(),
+            confidence: "high".to_string(),
+            flow_class: FlowClass::ExternalToPrompt,
+        });
+
+        let mut scenario = AttackScenario::env_leak();
+        assert_eq!(judge(&scenario, &report).0, Verdict::Detected);
+
+        scenario.expected_sinks = vec!["McpToolResultText".to_string()];
+        assert_eq!(judge(&scenario, &report).0, Verdict::NotDetected);
+    }
+
+    #[test]
+    fn detects_injected_payload_in_sink() {
+        let scenario = AttackScenario::prompt_injection();
+        let mut report = empty_report();
+        report.sinks.push(PromptSink::McpToolResultText {
+            path: "$.content[0].text".to_string(),
+            value: scenario.injection_payload.clone().unwrap(),
+        });
+
+        let (verdict, _) = judge(&scenario, &report);
+        assert_eq!(verdict, Verdict::Detected);
+
+        let (verdict, _) = judge(&scenario, &empty_report());
+        assert_eq!(verdict, Verdict::NotDetected);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::attack::verdict::Verdict;
+use crate::pipeline::{ScanLimits, SubjectScanResult, scan_subject_with_limits};
+use crate::subject::SubjectManifest;
+
+use super::oracle::judge;
+use super::scenario::AttackScenario;
+
+pub struct AttackRun {
+    pub scenario: AttackScenario,
+    pub result: SubjectScanResult,
+    pub verdict: Verdict,
+    pub rationale: String,
+}
+
+/// seed the scenario into a subject, scan it and let the oracle judge the report
+pub fn run_attack(
+    subject: &SubjectManifest,
+    scenario: &AttackScenario,
+    env: &HashMap<String, String>,
+    data_dir: Option<&Path>,
+    max_output_bytes: usize,
+    limits: ScanLimits,
+) -> Result<AttackRun> {
+    let attacked = attacked_subject(subject, scenario);
+    let scan_env = scenario.scan_env(env);
+
+    let planted_dir = plant_files(subject, scenario, data_dir)?;
+    let effective_data_dir = planted_dir.as_deref().or(data_dir);
+
+    let result = scan_subject_with_limits(
+        &attacked,
+        &scan_env,
+        effective_data_dir,
+        max_output_bytes,
+        limits,
+    );
+
+    if let Some(dir) = &planted_dir {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    let result = result.with_context(|| {
+        format!(
+            "attack scenario {} failed against subject {}",
+            scenario.name, subject.name
+        )
+    })?;
+    let (verdict, rationale) = judge(scenario, &result.report);
+
+    Ok(AttackRun {
+        scenario: scenario.clone(),
+        result,
+        verdict,
+        rationale,
+    })
+}
+
+fn attacked_subject(subject: &SubjectManifest, scenario: &AttackScenario) -> SubjectManifest {
+    let mut out = subject.clone();
+    if let Some(mcp) = out.mcp.as_mut() {
+        mcp.arguments = scenario.apply_tool_arguments(&mcp.arguments);
+    }
+    out
+}
+
+// planted files go into a fresh dir seeded with a copy of the caller's data dir,
+// so the subject still sees everything it was given
+fn plant_files(
+    subject: &SubjectManifest,
+    scenario: &AttackScenario,
+    data_dir: Option<&Path>,
+) -> Result<Option<PathBuf>> {
+    if scenario.files.is_empty() {
+        return Ok(None);
+    }
+
+    let slug = format!("{}-{}", subject.name, scenario.name)
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    let dir = std::env::temp_dir().join(format!(
+        "mcp-sandboxscan-attack-{slug}-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    if let Some(data_dir) = data_dir {
+        copy_dir(data_dir, &dir)?;
+    }
+
+    for file in &scenario.files {
+        let relative = Path::new(&file.path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            bail!("planted file must stay inside the data dir: {}", file.path);
+        }
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, &file.content)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    Ok(Some(dir))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    let entries =
+        std::fs::read_dir(from).with_context(|| format!("failed to read {}", from.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("failed to read {}", from.display()))?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir_all(&target)
+                .with_context(|| format!("failed to create {}", target.display()))?;
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target).with_context(|| {
+                format!(
+                    "failed to copy {} to {}",
+                    entry.path().display(),
+                    target.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attack::scenario::ScenarioKind;
+
+    #[test]
+    fn rejects_planted_file_outside_data_dir() {
+        let raw = std::fs::read_to_string("case_studies/rust-env-leak/subject.toml")
+            .expect("read subject.toml");
+        let subject: SubjectManifest = toml::from_str(&raw).expect("parse subject.toml");
+
+        let mut scenario = AttackScenario::file_exfil();
+        scenario.files[0].path = "../escape.txt".to_string();
+        assert!(plant_files(&subject, &scenario, None).is_err());
+    }
+
+    #[test]
+    fn planted_dir_keeps_the_callers_data() {
+        let raw = std::fs::read_to_string("case_studies/rust-env-leak/subject.toml")
+            .expect("read subject.toml");
+        let mut subject: SubjectManifest = toml::from_str(&raw).expect("parse subject.toml");
+        subject.name = format!("{}-keeps-data", subject.name);
+        let data_dir = std::env::temp_dir().join(format!(
+            "mcp-sandboxscan-attack-data-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(data_dir.join("nested")).unwrap();
+        std::fs::write(data_dir.join("notes.txt"), "caller data").unwrap();
+        std::fs::write(data_dir.join("nested/more.txt"), "more").unwrap();
+
+        let scenario = AttackScenario::file_exfil();
+        let planted = plant_files(&subject, &scenario, Some(&data_dir))
+            .unwrap()
+            .expect("scenario plants files");
+        let notes = std::fs::read_to_string(planted.join("notes.txt"));
+        let more = std::fs::read_to_string(planted.join("nested/more.txt"));
+        let seeded = planted.join(&scenario.files[0].path).exists();
+        std::fs::remove_dir_all(&planted).unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(notes.unwrap(), "caller data");
+        assert_eq!(more.unwrap(), "more");
+        assert!(seeded);
+    }
+
+    #[test]
+    fn runs_env_leak_attack_against_rust_subject() {
+        let raw = std::fs::read_to_string("case_studies/rust-env-leak/subject.toml")
+            .expect("read subject.toml");
+        let mut subject: SubjectManifest = toml::from_str(&raw).expect("parse subject.toml");
+        // the build writes tool.wasm next to main.rs; keep it out of the checked-in fixture
+        let source_dir =
+            std::env::temp_dir().join(format!("mcp-sandboxscan-attack-src-{}", std::process::id()));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        std::fs::copy(
+            subject.source_dir.join("main.rs"),
+            source_dir.join("main.rs"),
+        )
+        .unwrap();
+        subject.source_dir = source_dir.clone();
+
+        let run = run_attack(
+            &subject,
+            &AttackScenario::for_kind(ScenarioKind::EnvLeak),
+            &HashMap::new(),
+            None,
+            4096,
+            ScanLimits::default(),
+        )
+        .expect("run attack");
+        std::fs::remove_dir_all(&source_dir).unwrap();
+
+        assert_eq!(run.verdict, Verdict::Detected);
+        assert!(run.result.report.summary.has_external_to_prompt_flow);
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::subject::expect::ScenarioKind;
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Confusion {
     pub tp: usize,
@@ -51,6 +53,18 @@ pub enum Label {
     Clean,
 }
 
+impl Label {
+    pub fn for_scenario(scenario: ScenarioKind) -> Self {
+        match scenario {
+            ScenarioKind::Benign => Label::Clean,
+            ScenarioKind::EnvLeak
+            | ScenarioKind::FileExfil
+            | ScenarioKind::C2Beacon
+            | ScenarioKind::PromptInjection => Label::Malicious,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Verdict {
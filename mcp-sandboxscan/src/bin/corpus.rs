@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fs;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result, bail};
@@ -12,8 +13,11 @@ use mcp_sandboxscan::corpus::{
     verify_suspicious_cases, write_corpus_file, write_corpus_report, write_semantic_corpus_report,
     write_semantic_cross_validation_report,
 };
+use mcp_sandboxscan::mcp::client::ClientCapabilities;
 use mcp_sandboxscan::mcp::explore::ExplorationConfig;
 use mcp_sandboxscan::pipeline::ScanLimits;
+use mcp_sandboxscan::sandbox::limits::{SandboxLimits, SandboxOptions};
+use mcp_sandboxscan::sandbox::network_policy::{NetworkPolicy, NetworkRule};
 
 #[derive(Parser)]
 #[command(
@@ -84,12 +88,53 @@ enum Cmd {
         /// Max seconds waiting for MCP initialize + tools/call
         #[arg(long, default_value_t = 60)]
         mcp_timeout_secs: u64,
-        /// Dynamic exploration depth: shallow | targeted
+        /// Dynamic exploration depth: shallow | targeted (also reads resources and prompts) |
+        /// fuzz (many argument sets per tool in one server session)
         #[arg(long, default_value = "shallow")]
         explore_depth: String,
         /// Maximum tools to call in targeted exploration mode
         #[arg(long, default_value_t = 8)]
         max_tool_calls: usize,
+        /// Argument sets tried per tool with --explore-depth fuzz
+        #[arg(long, default_value_t = 24)]
+        fuzz_cases: usize,
+        /// Wasmtime fuel per WASM execution (0 = unlimited)
+        #[arg(long, default_value_t = 50_000_000_000)]
+        wasm_fuel: u64,
+        /// Max wall-clock seconds per WASM execution (0 = unlimited)
+        #[arg(long, default_value_t = 120)]
+        wasm_timeout_secs: u64,
+        /// Max linear memory per WASM execution, in MiB (0 = unlimited)
+        #[arg(long, default_value_t = 1024)]
+        wasm_max_memory_mb: usize,
+        /// Allow outbound connections to host[:port] or CIDR[:port] (repeatable)
+        #[arg(long)]
+        allow_net: Vec<NetworkRule>,
+        /// Loopback host:port that answers for every allowed destination
+        #[arg(long)]
+        net_stand_in: Option<SocketAddr>,
+        /// TOML file of canned HTTP responses served by the egress proxy
+        #[arg(long)]
+        net_fixtures: Option<PathBuf>,
+        /// Decrypt HTTPS through the egress proxy with a throwaway CA
+        #[arg(long)]
+        intercept_tls: bool,
+        /// Resolve names through a logging stub DNS resolver
+        #[arg(long)]
+        capture_dns: bool,
+        /// Run native servers in Linux namespaces under a seccomp filter
+        #[arg(long)]
+        isolate: bool,
+        /// Trace native servers' process spawns, sensitive file opens and connects with ptrace
+        #[arg(long)]
+        trace_syscalls: bool,
+        /// Client capabilities to advertise to native servers: sampling,roots,elicitation or all
+        #[arg(long, default_value = "")]
+        client_capabilities: String,
+        /// Tool manifests recorded per repo by earlier scans; changes are flagged and the file
+        /// updated (default: tool_baselines.json next to --corpus)
+        #[arg(long)]
+        tool_baselines: Option<PathBuf>,
     },
     /// Assign tier1/tier2 labels to repos.json (no network)
     Tier {
@@ -214,8 +259,31 @@ fn main() -> Result<()> {
             mcp_timeout_secs,
             explore_depth,
             max_tool_calls,
+            fuzz_cases,
+            wasm_fuel,
+            wasm_timeout_secs,
+            wasm_max_memory_mb,
+            allow_net,
+            net_stand_in,
+            net_fixtures,
+            intercept_tls,
+            capture_dns,
+            isolate,
+            trace_syscalls,
+            client_capabilities,
+            tool_baselines,
         } => {
+            let network = NetworkPolicy {
+                allow: allow_net,
+                stand_in: net_stand_in,
+                fixtures: net_fixtures,
+                intercept_tls,
+                capture_dns,
+            };
+            network.validate()?;
             let mut file = load_corpus(&corpus)?;
+            let tool_baselines =
+                tool_baselines.unwrap_or_else(|| corpus.with_file_name("tool_baselines.json"));
             let out = out_dir.unwrap_or_else(|| {
                 PathBuf::from("reports").join(format!("corpus-{}", mcp_sandboxscan::eval::run_id()))
             });
@@ -226,10 +294,27 @@ fn main() -> Result<()> {
                     out_dir: out.clone(),
                     env: HashMap::new(),
                     max_output_bytes: max_output_size,
+                    tool_baselines: Some(tool_baselines),
                     limits: ScanLimits {
                         build_timeout: Some(std::time::Duration::from_secs(build_timeout_secs)),
                         mcp_timeout: Some(std::time::Duration::from_secs(mcp_timeout_secs)),
-                        exploration: exploration_from_args(&explore_depth, max_tool_calls)?,
+                        exploration: ExplorationConfig {
+                            client: ClientCapabilities::parse(&client_capabilities)?,
+                            ..exploration_from_args(&explore_depth, max_tool_calls, fuzz_cases)?
+                        },
+                        sandbox: SandboxLimits {
+                            fuel: (wasm_fuel > 0).then_some(wasm_fuel),
+                            wall_clock: (wasm_timeout_secs > 0)
+                                .then(|| std::time::Duration::from_secs(wasm_timeout_secs)),
+                            max_memory_bytes: (wasm_max_memory_mb > 0)
+                                .then_some(wasm_max_memory_mb << 20),
+                            ..SandboxLimits::corpus_defaults()
+                        },
+                        sandbox_options: SandboxOptions {
+                            network,
+                            isolate_native: isolate,
+                            trace_native: trace_syscalls,
+                        },
                     },
                 },
             )?;
@@ -330,16 +415,22 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn exploration_from_args(depth: &str, max_tool_calls: usize) -> Result<ExplorationConfig> {
+fn exploration_from_args(
+    depth: &str,
+    max_tool_calls: usize,
+    fuzz_cases: usize,
+) -> Result<ExplorationConfig> {
     match depth {
         "shallow" => Ok(ExplorationConfig::disabled()),
-        "targeted" => Ok(ExplorationConfig {
+        "targeted" | "fuzz" => Ok(ExplorationConfig {
             enabled: true,
             max_tools: max_tool_calls,
             env_canary: String::new(),
             input_canary: String::new(),
             file_canary_path: None,
+            fuzz_cases: if depth == "fuzz" { fuzz_cases } else { 0 },
+            client: ClientCapabilities::default(),
         }),
-        other => bail!("unknown --explore-depth `{other}` (expected shallow or targeted)"),
+        other => bail!("unknown --explore-depth `{other}` (expected shallow, targeted or fuzz)"),
     }
 }
@@ -1,15 +1,20 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::explore::ExplorationConfig;
 use crate::pipeline::case_study::{default_env_for_subject, resolve_data_dir};
-use crate::pipeline::scan_subject;
+use crate::pipeline::{ScanLimits, scan_subject_with_limits};
+use crate::sandbox::limits::{SandboxLimits, SandboxOptions};
+use crate::sandbox::network_policy::{NetworkPolicy, NetworkRule};
 use crate::study::run_subject_matrix;
 use crate::subject::SubjectManifest;
 use anyhow::{Context, Result, bail};
 use clap::Parser;
 
 // absolute path to the sandboxscan data directory
-use crate::scan::dynamic::run_dynamic_scan;
+use crate::scan::dynamic::run_dynamic_scan_with_limits;
 
 // define CLI arguments
 #[derive(Parser, Debug)]
@@ -39,6 +44,38 @@ pub struct Args {
     /// max bytes of stdout/stderr to keep
     #[arg(long, default_value_t = 512_000)]
     pub max_output_size: usize,
+
+    /// Allow outbound connections to host[:port] or CIDR[:port] (repeatable)
+    #[arg(long, conflicts_with = "study")]
+    pub allow_net: Vec<NetworkRule>,
+
+    /// Loopback host:port that answers for every allowed destination
+    #[arg(long, conflicts_with = "study")]
+    pub net_stand_in: Option<SocketAddr>,
+
+    /// TOML file of canned HTTP responses served by the egress proxy
+    #[arg(long, conflicts_with = "study")]
+    pub net_fixtures: Option<PathBuf>,
+
+    /// Decrypt HTTPS through the egress proxy with a throwaway CA (native servers)
+    #[arg(long, conflicts_with = "study")]
+    pub intercept_tls: bool,
+
+    /// Resolve names for native servers through a logging stub DNS resolver
+    #[arg(long, conflicts_with = "study")]
+    pub capture_dns: bool,
+
+    /// Run native servers in Linux namespaces under a seccomp filter
+    #[arg(long, conflicts_with = "study")]
+    pub isolate: bool,
+
+    /// Trace native servers' process spawns, sensitive file opens and connects with ptrace
+    #[arg(long, conflicts_with = "study")]
+    pub trace_syscalls: bool,
+
+    /// Client capabilities to advertise to native servers: sampling,roots,elicitation or all
+    #[arg(long, default_value = "", conflicts_with = "study")]
+    pub client_capabilities: String,
 }
 
 // result defines error from user input parsing
@@ -66,6 +103,19 @@ pub fn entry() -> Result<()> {
     }
 
     let env: HashMap<String, String> = args.env.into_iter().collect();
+    let network = NetworkPolicy {
+        allow: args.allow_net,
+        stand_in: args.net_stand_in,
+        fixtures: args.net_fixtures,
+        intercept_tls: args.intercept_tls,
+        capture_dns: args.capture_dns,
+    };
+    network.validate()?;
+    let sandbox_options = SandboxOptions {
+        network,
+        isolate_native: args.isolate,
+        trace_native: args.trace_syscalls,
+    };
     let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     let json = if !args.study.is_empty() {
@@ -102,11 +152,19 @@ pub fn entry() -> Result<()> {
             args.data_dir.as_ref().map(|v| v.as_path()),
         )?;
 
-        let result = scan_subject(
+        let result = scan_subject_with_limits(
             &subject,
             &scan_env,
             effective_data_dir.as_deref(),
             args.max_output_size,
+            ScanLimits {
+                exploration: ExplorationConfig {
+                    client: ClientCapabilities::parse(&args.client_capabilities)?,
+                    ..ExplorationConfig::disabled()
+                },
+                sandbox_options,
+                ..ScanLimits::none()
+            },
         )
         .with_context(|| format!("Failed to scan subject {}", subject.name))?;
 
@@ -117,12 +175,14 @@ pub fn entry() -> Result<()> {
             bail!("WASM not found: {}", wasm_path.display());
         }
 
-        let report = run_dynamic_scan(
+        let report = run_dynamic_scan_with_limits(
             wasm_path,
             args.data_dir.as_ref().map(|v| v.as_path()),
             &env,
             None,
             args.max_output_size,
+            &SandboxLimits::none(),
+            &sandbox_options,
         )
         .with_context(|| format!("Failed to run dynamic scan on {}", wasm_path.display()))?;
 
@@ -0,0 +1,142 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/*
+just enough DNS to act as a stub resolver: parse the first question of a
+query and answer A/AAAA with sinkhole addresses. nothing is forwarded, so a
+name that only exists to carry data (`<secret>.c2.example`) never leaves the host.
+*/
+
+// RFC 2544 benchmarking range and the RFC 6666 discard prefix: never routed
+pub const SINKHOLE_V4: Ipv4Addr = Ipv4Addr::new(198, 18, 0, 1);
+pub const SINKHOLE_V6: Ipv6Addr = Ipv6Addr::new(0x100, 0, 0, 0, 0, 0, 0, 1);
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const ANSWER_TTL: u32 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsQuery {
+    pub id: u16,
+    pub name: String,
+    pub qtype: u16,
+    // question section as received, echoed back in the answer
+    question: Vec<u8>,
+    recursion_desired: bool,
+}
+
+impl DnsQuery {
+    pub fn qtype_name(&self) -> String {
+        match self.qtype {
+            TYPE_A => "A".to_string(),
+            TYPE_AAAA => "AAAA".to_string(),
+            5 => "CNAME".to_string(),
+            15 => "MX".to_string(),
+            16 => "TXT".to_string(),
+            other => format!("TYPE{other}"),
+        }
+    }
+
+    /// sinkhole answer for A/AAAA, an empty NOERROR answer for anything else
+    pub fn answer(&self) -> Vec<u8> {
+        let rdata: Option<Vec<u8>> = match self.qtype {
+            TYPE_A => Some(SINKHOLE_V4.octets().to_vec()),
+            TYPE_AAAA => Some(SINKHOLE_V6.octets().to_vec()),
+            _ => None,
+        };
+
+        let mut out = Vec::with_capacity(12 + self.question.len() + 28);
+        out.extend_from_slice(&self.id.to_be_bytes());
+        // QR | RA, plus RD echoed from the query
+        let flags: u16 = 0x8080 | if self.recursion_desired { 0x0100 } else { 0 };
+        out.extend_from_slice(&flags.to_be_bytes());
+        out.extend_from_slice(&1u16.to_be_bytes());
+        out.extend_from_slice(&(rdata.is_some() as u16).to_be_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&self.question);
+        if let Some(rdata) = rdata {
+            // name: pointer to the question at offset 12
+            out.extend_from_slice(&[0xc0, 0x0c]);
+            out.extend_from_slice(&self.qtype.to_be_bytes());
+            out.extend_from_slice(&CLASS_IN.to_be_bytes());
+            out.extend_from_slice(&ANSWER_TTL.to_be_bytes());
+            out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            out.extend_from_slice(&rdata);
+        }
+        out
+    }
+}
+
+pub fn parse_query(packet: &[u8]) -> Option<DnsQuery> {
+    if packet.len() < 12 {
+        return None;
+    }
+    let id = u16::from_be_bytes([packet[0], packet[1]]);
+    let is_response = packet[2] & 0x80 != 0;
+    let qdcount = u16::from_be_bytes([packet[4], packet[5]]);
+    if is_response || qdcount == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut pos = 12;
+    loop {
+        let len = *packet.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        // queries never use compression pointers
+        if len > 63 {
+            return None;
+        }
+        let label = packet.get(pos..pos + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += len;
+    }
+    let qtype = u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]);
+    let question_end = pos + 4;
+    let question = packet.get(12..question_end)?.to_vec();
+
+    Some(DnsQuery {
+        id,
+        name: labels.join("."),
+        qtype,
+        question,
+        recursion_desired: packet[2] & 0x01 != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn answers_queries_with_sinkhole() {
+        let parsed = parse_query(&query("sekret0123.c2.evil.example", TYPE_A)).unwrap();
+        assert_eq!(parsed.name, "sekret0123.c2.evil.example");
+        assert_eq!(parsed.qtype_name(), "A");
+
+        let answer = parsed.answer();
+        assert_eq!(&answer[..2], &[0x12, 0x34]);
+        // one answer whose rdata is the sinkhole address
+        assert_eq!(&answer[6..8], &[0, 1]);
+        assert!(answer.ends_with(&SINKHOLE_V4.octets()));
+
+        let txt = parse_query(&query("x.example", 16)).unwrap().answer();
+        assert_eq!(&txt[6..8], &[0, 0]);
+        assert!(parse_query(&txt).is_none());
+    }
+}
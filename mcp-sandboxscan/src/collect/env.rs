@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvGrant {
+    pub key: String,
+    // guest address environ_get copied the value to; components get a list instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_addr: Option<u32>,
+    pub value_len: usize,
+}
+
+#[derive(Default)]
+struct EnvState {
+    // (count, buf_size) reported by each environ_sizes_get call
+    size_queries: Vec<(u32, u32)>,
+    grants: BTreeMap<String, EnvGrant>,
+}
+
+/// env vars the runtime handed to a WASI guest. wasi-libc copies the whole
+/// environment in at start-up whether or not the program calls getenv, so this
+/// only says what the guest was given; reads show up as tainted writes instead
+#[derive(Default)]
+pub struct EnvCollector {
+    state: Mutex<EnvState>,
+}
+
+impl EnvCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = EnvState::default();
+    }
+
+    pub fn sizes_queried(&self, count: u32, buf_size: u32) {
+        self.state
+            .lock()
+            .unwrap()
+            .size_queries
+            .push((count, buf_size));
+    }
+
+    pub fn granted(&self, key: &str, guest_addr: Option<u32>, value_len: usize) {
+        self.state.lock().unwrap().grants.insert(
+            key.to_string(),
+            EnvGrant {
+                key: key.to_string(),
+                guest_addr,
+                value_len,
+            },
+        );
+    }
+
+    pub fn granted_keys(&self) -> HashSet<String> {
+        self.state.lock().unwrap().grants.keys().cloned().collect()
+    }
+
+    pub fn as_monitor_events(&self) -> Vec<MonitorEvent> {
+        let state = self.state.lock().unwrap();
+        let mut events = vec![];
+        for (count, buf_size) in &state.size_queries {
+            events.push(MonitorEvent {
+                kind: MonitorEventKind::EnvRead,
+                actor: "wasi-guest".to_string(),
+                target: None,
+                evidence: json!({
+                    "call": "environ_sizes_get",
+                    "count": count,
+                    "buf_size": buf_size
+                }),
+            });
+        }
+        for grant in state.grants.values() {
+            events.push(MonitorEvent {
+                kind: MonitorEventKind::CapabilityGranted,
+                actor: "wasi-guest".to_string(),
+                target: Some(grant.key.clone()),
+                evidence: json!({
+                    "call": if grant.guest_addr.is_some() { "environ_get" } else { "get-environment" },
+                    "access": "granted",
+                    "guest_addr": grant.guest_addr,
+                    "value_len": grant.value_len
+                }),
+            });
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_sizes_query_and_grants() {
+        let collector = EnvCollector::new();
+        collector.sizes_queried(2, 40);
+        collector.granted("DEMO_SECRET", Some(0x1000), 12);
+
+        assert_eq!(
+            collector.granted_keys(),
+            HashSet::from(["DEMO_SECRET".to_string()])
+        );
+        let events = collector.as_monitor_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].evidence["call"], "environ_sizes_get");
+        assert_eq!(events[1].kind, MonitorEventKind::CapabilityGranted);
+        assert_eq!(events[1].target.as_deref(), Some("DEMO_SECRET"));
+        assert_eq!(events[1].evidence["access"], "granted");
+
+        collector.reset();
+        assert!(collector.as_monitor_events().is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+use crate::taint::source::TaintSource;
+
+// cap on bytes kept per read file for taint sources (matches collect_file_sources)
+const MAX_SOURCE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOp {
+    Read,
+    Write,
+    Unlink,
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAccess {
+    pub op: FileOp,
+    pub guest_path: String,
+    pub host_path: Option<String>,
+    pub fd: Option<u32>,
+    pub offset: u64,
+    pub bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<String>,
+}
+
+struct OpenFile {
+    guest_path: String,
+    host_path: Option<PathBuf>,
+    position: u64,
+    // reads from here become taint sources (files under the /data preopen)
+    is_source: bool,
+}
+
+/// file accesses made by a WASI guest, keyed by the fds it holds
+#[derive(Default)]
+pub struct FileCollector {
+    fds: Mutex<HashMap<u32, OpenFile>>,
+    accesses: Mutex<Vec<FileAccess>>,
+    // bytes the guest actually read from source files, by host path in read order
+    read_contents: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+impl FileCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&self) {
+        self.fds.lock().unwrap().clear();
+        self.accesses.lock().unwrap().clear();
+        self.read_contents.lock().unwrap().clear();
+    }
+
+    pub fn add_preopen(&self, fd: u32, guest_path: &str, host_path: &Path, is_source: bool) {
+        self.fds.lock().unwrap().insert(
+            fd,
+            OpenFile {
+                guest_path: guest_path.to_string(),
+                host_path: Some(host_path.to_path_buf()),
+                position: 0,
+                is_source,
+            },
+        );
+    }
+
+    pub fn opened(&self, dir_fd: u32, relative: &str, fd: u32) {
+        let (guest_path, host_path, is_source) = self.resolve(dir_fd, relative);
+        self.fds.lock().unwrap().insert(
+            fd,
+            OpenFile {
+                guest_path,
+                host_path,
+                position: 0,
+                is_source,
+            },
+        );
+    }
+
+    pub fn host_path(&self, fd: u32) -> Option<PathBuf> {
+        self.fds.lock().unwrap().get(&fd)?.host_path.clone()
+    }
+
+    pub fn closed(&self, fd: u32) {
+        self.fds.lock().unwrap().remove(&fd);
+    }
+
+    pub fn seeked(&self, fd: u32, position: u64) {
+        if let Some(file) = self.fds.lock().unwrap().get_mut(&fd) {
+            file.position = position;
+        }
+    }
+
+    /// record a read; returns (host path, is_source) when the fd is a tracked file
+    pub fn read(&self, fd: u32, bytes: &[u8]) -> Option<(String, bool)> {
+        let mut fds = self.fds.lock().unwrap();
+        let file = fds.get_mut(&fd)?;
+        let offset = file.position;
+        file.position += bytes.len() as u64;
+        let host = file
+            .host_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| file.guest_path.clone());
+        let is_source = file.is_source;
+        let guest_path = file.guest_path.clone();
+        drop(fds);
+
+        self.record_io(
+            FileOp::Read,
+            fd,
+            &guest_path,
+            &host,
+            offset,
+            bytes.len() as u64,
+        );
+        if !is_source {
+            return Some((host, false));
+        }
+
+        let mut contents = self.read_contents.lock().unwrap();
+        let entry = match contents.iter().position(|(path, _)| *path == host) {
+            Some(index) => &mut contents[index].1,
+            None => {
+                contents.push((host.clone(), Vec::new()));
+                &mut contents.last_mut().unwrap().1
+            }
+        };
+        let room = MAX_SOURCE_BYTES.saturating_sub(entry.len());
+        entry.extend_from_slice(&bytes[..bytes.len().min(room)]);
+
+        Some((host, true))
+    }
+
+    pub fn written(&self, fd: u32, len: u64) {
+        let mut fds = self.fds.lock().unwrap();
+        let Some(file) = fds.get_mut(&fd) else {
+            return;
+        };
+        let offset = file.position;
+        file.position += len;
+        let guest_path = file.guest_path.clone();
+        let host = file
+            .host_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| guest_path.clone());
+        drop(fds);
+
+        self.record_io(FileOp::Write, fd, &guest_path, &host, offset, len);
+    }
+
+    pub fn unlinked(&self, dir_fd: u32, relative: &str) {
+        let (guest_path, host_path, _) = self.resolve(dir_fd, relative);
+        self.accesses.lock().unwrap().push(FileAccess {
+            op: FileOp::Unlink,
+            guest_path,
+            host_path: host_path.map(|p| p.display().to_string()),
+            fd: None,
+            offset: 0,
+            bytes: 0,
+            renamed_to: None,
+        });
+    }
+
+    pub fn renamed(&self, old_dir_fd: u32, old: &str, new_dir_fd: u32, new: &str) {
+        let (guest_path, host_path, _) = self.resolve(old_dir_fd, old);
+        let (renamed_to, _, _) = self.resolve(new_dir_fd, new);
+        self.accesses.lock().unwrap().push(FileAccess {
+            op: FileOp::Rename,
+            guest_path,
+            host_path: host_path.map(|p| p.display().to_string()),
+            fd: None,
+            offset: 0,
+            bytes: 0,
+            renamed_to: Some(renamed_to),
+        });
+    }
+
+    pub fn accesses(&self) -> Vec<FileAccess> {
+        self.accesses.lock().unwrap().clone()
+    }
+
+    /// FileRead sources for files under a source preopen that the guest actually read
+    pub fn as_taint_sources(&self) -> Vec<TaintSource> {
+        self.read_contents
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, bytes)| !bytes.is_empty())
+            .map(|(path, bytes)| TaintSource::FileRead {
+                path: path.clone(),
+                content: String::from_utf8_lossy(bytes).into_owned(),
+            })
+            .collect()
+    }
+
+    pub fn as_monitor_events(&self) -> Vec<MonitorEvent> {
+        self.accesses()
+            .into_iter()
+            .map(|access| MonitorEvent {
+                kind: match access.op {
+                    FileOp::Read => MonitorEventKind::FileRead,
+                    FileOp::Write | FileOp::Unlink | FileOp::Rename => MonitorEventKind::FileWrite,
+                },
+                actor: "wasi-guest".to_string(),
+                target: Some(access.guest_path.clone()),
+                evidence: serde_json::json!(access),
+            })
+            .collect()
+    }
+
+    // consecutive reads/writes of the same fd are merged into one access
+    fn record_io(&self, op: FileOp, fd: u32, guest_path: &str, host: &str, offset: u64, len: u64) {
+        let mut accesses = self.accesses.lock().unwrap();
+        if let Some(last) = accesses.last_mut()
+            && last.op == op
+            && last.fd == Some(fd)
+            && last.guest_path == guest_path
+            && last.offset + last.bytes == offset
+        {
+            last.bytes += len;
+            return;
+        }
+        accesses.push(FileAccess {
+            op,
+            guest_path: guest_path.to_string(),
+            host_path: Some(host.to_string()),
+            fd: Some(fd),
+            offset,
+            bytes: len,
+            renamed_to: None,
+        });
+    }
+
+    fn resolve(&self, dir_fd: u32, relative: &str) -> (String, Option<PathBuf>, bool) {
+        let fds = self.fds.lock().unwrap();
+        let Some(dir) = fds.get(&dir_fd) else {
+            return (relative.to_string(), None, false);
+        };
+        let mut guest = PathBuf::from(&dir.guest_path);
+        let mut host = dir.host_path.clone();
+        for component in Path::new(relative).components() {
+            match component {
+                Component::Normal(part) => {
+                    guest.push(part);
+                    if let Some(host) = host.as_mut() {
+                        host.push(part);
+                    }
+                }
+                Component::ParentDir => {
+                    guest.pop();
+                    if let Some(host) = host.as_mut() {
+                        host.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+        (guest.display().to_string(), host, dir.is_source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_sequential_reads_and_keeps_source_bytes() {
+        let collector = FileCollector::new();
+        collector.add_preopen(3, "/data", Path::new("/host/data"), true);
+        collector.opened(3, "sub/../secret.txt", 4);
+
+        assert_eq!(
+            collector.read(4, b"abc"),
+            Some(("/host/data/secret.txt".to_string(), true))
+        );
+        collector.read(4, b"def");
+        collector.seeked(4, 100);
+        collector.read(4, b"g");
+
+        let accesses = collector.accesses();
+        assert_eq!(accesses.len(), 2);
+        assert_eq!(accesses[0].guest_path, "/data/secret.txt");
+        assert_eq!((accesses[0].offset, accesses[0].bytes), (0, 6));
+        assert_eq!((accesses[1].offset, accesses[1].bytes), (100, 1));
+
+        let sources = collector.as_taint_sources();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].content(), "abcdefg");
+    }
+
+    #[test]
+    fn writes_outside_source_preopens_are_events_only() {
+        let collector = FileCollector::new();
+        collector.add_preopen(3, "/work", Path::new("/host/work"), false);
+        collector.opened(3, "out.log", 5);
+        collector.written(5, 12);
+        collector.renamed(3, "out.log", 3, "out.old");
+        collector.unlinked(3, "out.old");
+        assert_eq!(
+            collector.read(5, b"x"),
+            Some(("/host/work/out.log".to_string(), false))
+        );
+
+        assert!(collector.as_taint_sources().is_empty());
+        let events = collector.as_monitor_events();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                MonitorEventKind::FileWrite,
+                MonitorEventKind::FileWrite,
+                MonitorEventKind::FileWrite,
+                MonitorEventKind::FileRead,
+            ]
+        );
+        assert_eq!(events[1].evidence["renamed_to"], "/work/out.old");
+        assert_eq!(events[3].evidence["offset"], 12);
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/*
+canned upstream responses for the egress proxy, loaded from a TOML file:
+
+    [[response]]
+    url = "http://api.example.com/v1/"   # prefix of the absolute request URL
+    method = "POST"                       # optional, any method when absent
+    status = 200
+    content_type = "application/json"
+    body = '{"items": []}'
+
+the longest matching url wins, so a catch-all `url = "http://"` can sit
+next to specific endpoints.
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpFixtures {
+    #[serde(default, rename = "response")]
+    pub responses: Vec<CannedResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CannedResponse {
+    pub url: String,
+    pub method: Option<String>,
+    #[serde(default = "default_status")]
+    pub status: u16,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn default_content_type() -> String {
+    "text/plain".to_string()
+}
+
+impl HttpFixtures {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read HTTP fixtures {}", path.display()))?;
+        toml::from_str(&raw)
+            .with_context(|| format!("failed to parse HTTP fixtures {}", path.display()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    pub fn find(&self, method: &str, url: &str) -> Option<&CannedResponse> {
+        self.responses
+            .iter()
+            .filter(|response| {
+                response
+                    .method
+                    .as_deref()
+                    .is_none_or(|m| m.eq_ignore_ascii_case(method))
+                    && url.starts_with(&response.url)
+            })
+            .max_by_key(|response| response.url.len())
+    }
+}
+
+impl CannedResponse {
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason_phrase(self.status),
+            self.content_type,
+            self.body.len()
+        );
+        out.write_all(head.as_bytes())?;
+        out.write_all(self.body.as_bytes())
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "Fixture",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_longest_matching_url() {
+        let fixtures: HttpFixtures = toml::from_str(
+            r#"
+            [[response]]
+            url = "http://"
+            body = "fallback"
+
+            [[response]]
+            url = "http://api.example.com/v1/"
+            method = "POST"
+            status = 201
+            body = "created"
+            "#,
+        )
+        .unwrap();
+
+        let hit = fixtures
+            .find("post", "http://api.example.com/v1/items")
+            .unwrap();
+        assert_eq!((hit.status, hit.body.as_str()), (201, "created"));
+        let hit = fixtures
+            .find("GET", "http://api.example.com/v1/items")
+            .unwrap();
+        assert_eq!(hit.body, "fallback");
+        assert!(fixtures.find("GET", "https://api.example.com/").is_none());
+
+        let mut out = Vec::new();
+        hit.write_to(&mut out).unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .starts_with("HTTP/1.1 200 OK\r\n")
+        );
+    }
+}
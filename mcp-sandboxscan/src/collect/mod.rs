@@ -1,5 +1,15 @@
+pub mod dns;
+pub mod env;
+pub mod file;
+pub mod http_fixtures;
 pub mod network;
+pub mod tls_intercept;
 
+pub use env::{EnvCollector, EnvGrant};
+pub use file::{FileAccess, FileCollector, FileOp};
+pub use http_fixtures::{CannedResponse, HttpFixtures};
 pub use network::{
-    NetworkCollector, NetworkDirection, NetworkObservation, observations_from_http_intents,
+    NetworkCollector, NetworkDirection, NetworkObservation, ProxiedRequest,
+    observations_from_http_intents,
 };
+pub use tls_intercept::InterceptCa;
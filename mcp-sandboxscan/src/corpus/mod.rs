@@ -1,3 +1,4 @@
+pub mod baseline;
 pub mod classify;
 pub mod collect;
 pub mod deps;
@@ -13,6 +14,7 @@ pub mod semantic;
 pub mod tier;
 pub mod verify;
 
+pub use baseline::ToolBaselineStore;
 pub use collect::{CollectOptions, CollectResult, collect_github, seed_corpus, write_corpus_file};
 pub use filter::{CollectFilterStats, apply_collect_filter, reject_reason, reject_reason_strict};
 pub use model::{CorpusFile, CorpusScanReport, RepoEntry};
@@ -47,6 +47,8 @@ pub struct CorpusScanCase {
     pub has_flow: bool,
     pub num_flows: usize,
     pub num_sinks: usize,
+    #[serde(default)]
+    pub num_findings: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -69,6 +71,9 @@ pub struct CorpusScanCase {
     pub total_ms: Option<u128>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_profile: Option<ToolSemanticProfile>,
+    // canonical digest of the first tools/list answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
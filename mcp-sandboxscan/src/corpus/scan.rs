@@ -6,11 +6,15 @@ use std::time::Instant;
 use anyhow::{Context, Result};
 
 use crate::eval::run_id;
+use crate::monitor::event::finding_events;
 use crate::pipeline::case_study::{default_env_for_subject, resolve_data_dir};
 use crate::pipeline::{ScanLimits, scan_subject_with_limits};
+use crate::sandbox::exec_evidence::termination_of;
 use crate::scan::report::ScanReport;
+use crate::scan::rug_pull::ToolManifest;
 use crate::subject::SubjectManifest;
 
+use super::baseline::ToolBaselineStore;
 use super::model::{
     ClassStats, CorpusFile, CorpusScanCase, CorpusScanReport, LatencyStats, RepoEntry, TierStats,
     ToolSemanticAggregate, ToolSemanticSummary,
@@ -24,6 +28,8 @@ pub struct ScanOptions {
     pub env: HashMap<String, String>,
     pub max_output_bytes: usize,
     pub limits: ScanLimits,
+    // tool manifests from earlier scans, compared against and then updated
+    pub tool_baselines: Option<PathBuf>,
 }
 
 pub fn run_corpus_scan(corpus: &mut CorpusFile, opts: &ScanOptions) -> Result<CorpusScanReport> {
@@ -31,6 +37,11 @@ pub fn run_corpus_scan(corpus: &mut CorpusFile, opts: &ScanOptions) -> Result<Co
     fs::create_dir_all(&opts.out_dir)?;
     let cases_dir = opts.out_dir.join("cases");
     fs::create_dir_all(&cases_dir)?;
+    let run_id = run_id();
+    let mut baselines = match &opts.tool_baselines {
+        Some(path) => Some(ToolBaselineStore::load(path)?),
+        None => None,
+    };
 
     let mut cases = Vec::new();
     let mut by_class: HashMap<String, ClassStats> = HashMap::new();
@@ -52,7 +63,8 @@ pub fn run_corpus_scan(corpus: &mut CorpusFile, opts: &ScanOptions) -> Result<Co
         position += 1;
         eprintln!("[{position}/{total}] scanning {} ...", repo.id);
 
-        let case = scan_one_repo(repo, &toml, opts, &cases_dir);
+        let baseline = baselines.as_mut().map(|store| (store, run_id.as_str()));
+        let case = scan_one_repo(repo, &toml, opts, &cases_dir, baseline);
         let scan_ok = case.scan_ok;
         let stats = by_class.entry(repo.wasm_class.clone()).or_default();
         stats.total += 1;
@@ -90,6 +102,10 @@ pub fn run_corpus_scan(corpus: &mut CorpusFile, opts: &ScanOptions) -> Result<Co
         eprintln!("[{position}/{total}] {} -> {status}{latency}", repo.id);
     }
 
+    if let (Some(store), Some(path)) = (&baselines, &opts.tool_baselines) {
+        store.save(path)?;
+    }
+
     let total_repos = corpus.repos.len();
     let resolved_repos = corpus.repos.iter().filter(|r| r.resolved).count();
     let scanned_repos = cases.iter().filter(|c| c.scan_ok).count();
@@ -98,7 +114,7 @@ pub fn run_corpus_scan(corpus: &mut CorpusFile, opts: &ScanOptions) -> Result<Co
     let semantic = compute_semantic_summary(&cases);
 
     Ok(CorpusScanReport {
-        run_id: run_id(),
+        run_id,
         total_repos,
         resolved_repos,
         scanned_repos,
@@ -127,6 +143,7 @@ fn scan_one_repo(
     toml_path: &str,
     opts: &ScanOptions,
     cases_dir: &Path,
+    baseline: Option<(&mut ToolBaselineStore, &str)>,
 ) -> CorpusScanCase {
     let mut base = CorpusScanCase {
         repo_id: repo.id.clone(),
@@ -138,6 +155,7 @@ fn scan_one_repo(
         has_flow: false,
         num_flows: 0,
         num_sinks: 0,
+        num_findings: 0,
         error: None,
         report_path: None,
         failure_category: None,
@@ -153,22 +171,23 @@ fn scan_one_repo(
         scan_ms: None,
         total_ms: None,
         tool_profile: None,
+        tool_fingerprint: None,
     };
 
     let path = PathBuf::from(toml_path);
     let raw = match fs::read_to_string(&path) {
         Ok(s) => s,
-        Err(e) => return fail(base, e.to_string()),
+        Err(e) => return fail(base, e.into()),
     };
     let subject: SubjectManifest = match toml::from_str(&raw) {
         Ok(s) => s,
-        Err(e) => return fail(base, e.to_string()),
+        Err(e) => return fail(base, e.into()),
     };
 
     let env = default_env_for_subject(&subject, &opts.env);
     let data_dir = match resolve_data_dir(&opts.manifest_dir, &subject, None) {
         Ok(d) => d,
-        Err(e) => return fail(base, e.to_string()),
+        Err(e) => return fail(base, e),
     };
 
     let started = Instant::now();
@@ -179,7 +198,20 @@ fn scan_one_repo(
         opts.max_output_bytes,
         opts.limits.clone(),
     ) {
-        Ok(result) => {
+        Ok(mut result) => {
+            let first_listing = result
+                .report
+                .mcp_transcript
+                .as_ref()
+                .and_then(ToolManifest::first_in);
+            if let Some(manifest) = first_listing {
+                base.tool_fingerprint = Some(manifest.fingerprint());
+                if let Some((store, run_id)) = baseline {
+                    let changes = store.observe(&repo.id, manifest, run_id);
+                    result.report.events.extend(finding_events(&changes));
+                    result.report.findings.extend(changes);
+                }
+            }
             base.total_ms = Some(started.elapsed().as_millis());
             base.build_ms = Some(result.timing.build_ms);
             base.scan_ms = Some(result.timing.scan_ms);
@@ -189,20 +221,26 @@ fn scan_one_repo(
                 &report_path,
                 serde_json::to_string_pretty(&result.report).unwrap_or_default(),
             ) {
-                return fail(base, e.to_string());
+                return fail(base, e.into());
             }
             base.wasm_status = format!("{:?}", result.adaptation_status);
             base.scan_ok = true;
-            base.has_flow = result.report.summary.has_external_to_prompt_flow;
+            base.has_flow = result.report.summary.has_flow();
             base.num_flows = result.report.summary.num_flows;
             base.num_sinks = result.report.summary.num_sinks;
+            base.num_findings = result.report.findings.len();
             base.tool_profile = profile_scan_report(&result.report);
+            // the scan produced a report, but note guests that were cut short
+            let termination = &result.report.exec.termination;
+            if termination.failure().is_some() {
+                base.failure_category = Some(termination.category().to_string());
+            }
             base.report_path = Some(report_path.to_string_lossy().into_owned());
             base
         }
         Err(e) => {
             base.total_ms = Some(started.elapsed().as_millis());
-            fail(base, format!("{e:#}"))
+            fail(base, e)
         }
     }
 }
@@ -254,14 +292,18 @@ fn resolve_case_report_path(case: &CorpusScanCase, out_dir: &Path) -> Option<Pat
     None
 }
 
-fn fail(mut case: CorpusScanCase, err: String) -> CorpusScanCase {
+fn fail(mut case: CorpusScanCase, err: anyhow::Error) -> CorpusScanCase {
     case.failure_category = Some(classify_failure(&err).to_string());
-    case.error = Some(err);
+    case.error = Some(format!("{err:#}"));
     case
 }
 
-fn classify_failure(err: &str) -> &'static str {
-    let lower = err.to_lowercase();
+fn classify_failure(err: &anyhow::Error) -> &'static str {
+    // runs that recorded how the subject ended need no message matching
+    if let Some(termination) = termination_of(err) {
+        return termination.category();
+    }
+    let lower = format!("{err:#}").to_lowercase();
     if lower.contains("build command exited") || lower.contains("command timed out") {
         "build"
     } else if lower.contains("timed out after") {
@@ -534,12 +576,15 @@ fn push_semantic_row(out: &mut String, label: &str, stats: &ToolSemanticAggregat
 
 #[cfg(test)]
 mod tests {
+    use anyhow::anyhow;
+
     use super::*;
+    use crate::sandbox::exec_evidence::{TerminationError, TerminationReason};
 
     #[test]
     fn classifies_build_failure() {
         assert_eq!(
-            classify_failure("build command exited with status exit status: 1"),
+            classify_failure(&anyhow!("build command exited with status exit status: 1")),
             "build"
         );
     }
@@ -547,11 +592,27 @@ mod tests {
     #[test]
     fn classifies_mcp_start_failure() {
         assert_eq!(
-            classify_failure("MCP server closed stdout before JSON response"),
+            classify_failure(&anyhow!("MCP server closed stdout before JSON response")),
             "mcp_start"
         );
     }
 
+    #[test]
+    fn classifies_typed_termination_before_message() {
+        let err = anyhow::Error::new(TerminationError {
+            termination: TerminationReason::Timeout { after_ms: 30_000 },
+            message: "MCP server timed out after 30s".to_string(),
+        })
+        .context("tools/call failed");
+        assert_eq!(classify_failure(&err), "timeout");
+
+        let err = anyhow::Error::new(TerminationError {
+            termination: TerminationReason::MissingStart,
+            message: "missing _start".to_string(),
+        });
+        assert_eq!(classify_failure(&err), "missing_start");
+    }
+
     #[test]
     fn percentile_picks_median() {
         assert_eq!(percentile(&[10, 20, 30], 50), 20);
@@ -570,6 +631,7 @@ mod tests {
                 has_flow: false,
                 num_flows: 0,
                 num_sinks: 0,
+                num_findings: 0,
                 error: None,
                 report_path: None,
                 failure_category: None,
@@ -586,6 +648,7 @@ mod tests {
                     sensitive_tools: 1,
                     by_capability: HashMap::from([("shell".to_string(), 1)]),
                 }),
+                tool_fingerprint: None,
             },
             CorpusScanCase {
                 repo_id: "c/d".into(),
@@ -597,6 +660,7 @@ mod tests {
                 has_flow: false,
                 num_flows: 0,
                 num_sinks: 0,
+                num_findings: 0,
                 error: None,
                 report_path: None,
                 failure_category: None,
@@ -608,6 +672,7 @@ mod tests {
                 scan_ms: None,
                 total_ms: None,
                 tool_profile: None,
+                tool_fingerprint: None,
             },
         ];
 
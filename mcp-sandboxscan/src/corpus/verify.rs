@@ -24,7 +24,7 @@ pub fn verify_packet(repo_id: &str, report_json: &Path) -> Result<VerifyRow> {
 
     Ok(VerifyRow {
         repo_id: repo_id.to_string(),
-        has_flow: report.summary.has_external_to_prompt_flow,
+        has_flow: report.summary.has_flow(),
         num_flows: report.summary.num_flows,
         flow_snippets: report.flows.iter().map(|f| f.snippet.clone()).collect(),
         sink_preview: report
@@ -6,14 +6,15 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::attack::verdict::update_confusion;
+use crate::attack::{AttackScenario, Confusion, Label, Verdict, run_attack};
+use crate::pipeline::ScanLimits;
 use crate::pipeline::case_study::{default_env_for_subject, resolve_data_dir};
-use crate::pipeline::scan_subject;
 use crate::scan::compare::compare_from_report;
 use crate::study::portability::WasmPortabilityStatus;
 use crate::subject::SubjectManifest;
 
-use super::metrics::{Confusion, Label, Verdict, update_confusion};
-use super::score::{ScenarioKind, label_for_scenario, scenario_from_name, score_case};
+use super::score::ScenarioKind;
 use super::suite::SuiteId;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,21 +180,25 @@ fn bench_one_case(
     let subject: SubjectManifest = toml::from_str(&raw)
         .with_context(|| format!("parse subject {}", subject_path.display()))?;
 
-    let scenario = scenario_from_name(&subject.name);
-    let label = label_for_scenario(scenario);
+    let attack = AttackScenario::for_subject(&subject);
+    let scenario = attack.kind;
+    let label = Label::for_scenario(scenario);
     let language = format!("{:?}", subject.language);
 
     let scan_env = default_env_for_subject(&subject, env);
     let effective_data_dir = resolve_data_dir(manifest_dir, &subject, data_dir)?;
 
-    match scan_subject(
+    match run_attack(
         &subject,
+        &attack,
         &scan_env,
         effective_data_dir.as_deref(),
         max_output_bytes,
+        ScanLimits::default(),
     ) {
-        Ok(result) => {
-            let (verdict, rationale) = score_case(&result.report, scenario);
+        Ok(run) => {
+            let result = run.result;
+            let (verdict, rationale) = (run.verdict, run.rationale);
             let protocol_wins = if run_compare {
                 let row = compare_from_report(&subject, subject_path, &result.report);
                 Some(row.protocol_wins)
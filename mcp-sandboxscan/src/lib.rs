@@ -1,4 +1,5 @@
 pub mod adapter;
+pub mod attack;
 pub mod cli;
 pub mod collect;
 pub mod corpus;
@@ -1,16 +1,3 @@
-mod adapter;
-mod cli;
-mod collect;
-mod mcp;
-mod monitor;
-mod pipeline;
-mod sandbox;
-mod scan;
-mod study;
-mod subject;
-mod taint;
-mod util;
-
 fn main() -> anyhow::Result<()> {
-    cli::main::entry()
+    mcp_sandboxscan::cli::main::entry()
 }
@@ -0,0 +1,149 @@
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+
+use crate::mcp::surface::PLACEHOLDER;
+
+/*
+what the scanner claims to support as an MCP client. a server may only send
+sampling, roots or elicitation requests once the client advertises them, so
+each one is opt-in; whatever arrives is answered with a canned reply.
+*/
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientCapabilities {
+    pub sampling: bool,
+    pub roots: bool,
+    pub elicitation: bool,
+}
+
+// completion handed back for every sampling/createMessage
+pub const SAMPLING_REPLY: &str = "Done.";
+
+impl ClientCapabilities {
+    pub fn all() -> Self {
+        Self {
+            sampling: true,
+            roots: true,
+            elicitation: true,
+        }
+    }
+
+    /// comma-separated list from the command line, e.g. `sampling,roots`
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut caps = Self::default();
+        for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match name {
+                "sampling" => caps.sampling = true,
+                "roots" => caps.roots = true,
+                "elicitation" => caps.elicitation = true,
+                "all" => caps = Self::all(),
+                other => bail!("unknown client capability `{other}`"),
+            }
+        }
+        Ok(caps)
+    }
+
+    /// the `capabilities` object sent in initialize
+    pub fn to_json(&self) -> Value {
+        let mut caps = serde_json::Map::new();
+        if self.sampling {
+            caps.insert("sampling".to_string(), json!({}));
+        }
+        if self.roots {
+            caps.insert("roots".to_string(), json!({"listChanged": false}));
+        }
+        if self.elicitation {
+            caps.insert("elicitation".to_string(), json!({}));
+        }
+        Value::Object(caps)
+    }
+
+    /// response to a server->client request; `root` is the file:// URI listed as
+    /// the only root. unadvertised methods get method-not-found
+    pub fn answer(&self, request: &Value, root: Option<&str>) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "ping" => Some(json!({})),
+            "sampling/createMessage" if self.sampling => Some(json!({
+                "role": "assistant",
+                "content": {"type": "text", "text": SAMPLING_REPLY},
+                "model": "mcp-sandboxscan",
+                "stopReason": "endTurn"
+            })),
+            "roots/list" if self.roots => Some(json!({
+                "roots": root
+                    .map(|uri| vec![json!({"uri": uri, "name": "workspace"})])
+                    .unwrap_or_default()
+            })),
+            "elicitation/create" if self.elicitation => Some(json!({
+                "action": "accept",
+                "content": elicited_content(&request["params"]["requestedSchema"])
+            })),
+            _ => None,
+        };
+        match result {
+            Some(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": "method not supported by client"}
+            }),
+        }
+    }
+}
+
+// elicitation schemas are flat objects of primitives; fill every property
+fn elicited_content(schema: &Value) -> Value {
+    let content = schema["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, prop)| {
+            let value = match (prop["enum"].get(0), prop["type"].as_str()) {
+                (Some(first), _) => first.clone(),
+                (None, Some("boolean")) => json!(false),
+                (None, Some("number" | "integer")) => json!(0),
+                _ => json!(PLACEHOLDER),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+    Value::Object(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_only_advertised_requests() {
+        let caps = ClientCapabilities::parse("sampling, elicitation").unwrap();
+        assert_eq!(caps.to_json(), json!({"sampling": {}, "elicitation": {}}));
+        assert!(ClientCapabilities::parse("telepathy").is_err());
+
+        let sampling = json!({"jsonrpc": "2.0", "id": 0, "method": "sampling/createMessage",
+            "params": {"messages": [], "maxTokens": 10}});
+        assert_eq!(
+            caps.answer(&sampling, None)["result"]["content"]["text"],
+            SAMPLING_REPLY
+        );
+
+        let roots = json!({"jsonrpc": "2.0", "id": "r1", "method": "roots/list"});
+        let reply = caps.answer(&roots, Some("file:///work"));
+        assert_eq!(reply["id"], "r1");
+        assert_eq!(reply["error"]["code"], -32601);
+
+        let elicit = json!({"jsonrpc": "2.0", "id": 1, "method": "elicitation/create", "params": {
+            "message": "who are you?",
+            "requestedSchema": {"type": "object", "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "tier": {"type": "string", "enum": ["free", "pro"]}
+            }}
+        }});
+        assert_eq!(
+            caps.answer(&elicit, None)["result"],
+            json!({"action": "accept", "content": {"name": "example", "age": 0, "tier": "free"}})
+        );
+    }
+}
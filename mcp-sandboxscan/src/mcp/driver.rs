@@ -4,6 +4,7 @@ use serde_json::Value;
 The interface to call and run real MCP
 */
 
+use super::explore::ExplorationConfig;
 use super::transcript::McpTranscript;
 use crate::sandbox::exec_evidence::ExecutionEvidence;
 
@@ -22,3 +23,12 @@ pub struct McpDriverResult {
 pub trait McpDriver {
     fn call_tool(&self, plan: &McpCallPlan) -> Result<McpDriverResult>;
 }
+
+/// a driver that can call every tool exploration selects in one session
+pub trait McpExplore {
+    fn call_tool_with_exploration(
+        &self,
+        plan: &McpCallPlan,
+        exploration: &ExplorationConfig,
+    ) -> Result<McpDriverResult>;
+}
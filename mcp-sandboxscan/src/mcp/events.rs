@@ -60,8 +60,8 @@ pub fn monitor_events_from_transcript(transcript: &McpTranscript) -> Vec<Monitor
                 }
             }
         }
-        // server response events
-        if matches!(event.direction, McpDirection::ServerToClient) {
+        // server response events; server->client requests carry their own ids
+        if matches!(event.direction, McpDirection::ServerToClient) && event.method.is_none() {
             if let Some(id) = id {
                 if request_methods.get(&id).map(String::as_str) == Some("tools/call") {
                     events.push(MonitorEvent {
@@ -1,5 +1,6 @@
 use serde_json::{Map, Value, json};
 
+use crate::mcp::client::ClientCapabilities;
 use crate::mcp::driver::McpCallPlan;
 
 #[derive(Debug, Clone)]
@@ -9,6 +10,10 @@ pub struct ExplorationConfig {
     pub env_canary: String,
     pub input_canary: String,
     pub file_canary_path: Option<String>,
+    // argument sets tried per tool in one persistent session; 0 calls each tool once
+    pub fuzz_cases: usize,
+    // advertised in initialize whether or not tools are explored
+    pub client: ClientCapabilities,
 }
 
 impl ExplorationConfig {
@@ -19,6 +24,8 @@ impl ExplorationConfig {
             env_canary: String::new(),
             input_canary: String::new(),
             file_canary_path: None,
+            fuzz_cases: 0,
+            client: ClientCapabilities::default(),
         }
     }
 }
@@ -40,6 +47,20 @@ pub fn build_exploration_plans(
     tools_response: &Value,
     config: &ExplorationConfig,
 ) -> Vec<McpCallPlan> {
+    select_tools(tools_response, config)
+        .into_iter()
+        .map(|(name, input_schema)| McpCallPlan {
+            tool_name: name,
+            arguments: synthesize_arguments(&input_schema, config),
+        })
+        .collect()
+}
+
+/// the tools worth calling, best first, with their input schemas
+pub(crate) fn select_tools(
+    tools_response: &Value,
+    config: &ExplorationConfig,
+) -> Vec<(String, Value)> {
     if !config.enabled || config.max_tools == 0 {
         return Vec::new();
     }
@@ -85,10 +106,7 @@ pub fn build_exploration_plans(
     candidates
         .into_iter()
         .take(config.max_tools)
-        .map(|tool| McpCallPlan {
-            tool_name: tool.name,
-            arguments: synthesize_arguments(&tool.input_schema, config),
-        })
+        .map(|tool| (tool.name, tool.input_schema))
         .collect()
 }
 
@@ -132,7 +150,7 @@ fn is_potentially_destructive(name: &str, description: &str) -> bool {
     .any(|keyword| text.contains(keyword))
 }
 
-fn synthesize_arguments(schema: &Value, config: &ExplorationConfig) -> Value {
+pub(crate) fn synthesize_arguments(schema: &Value, config: &ExplorationConfig) -> Value {
     if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
         return json!({});
     }
@@ -181,7 +199,7 @@ fn synthesize_value(name: &str, schema: &Value, config: &ExplorationConfig) -> V
     }
 }
 
-fn string_value_for_field(name: &str, config: &ExplorationConfig) -> String {
+pub(crate) fn string_value_for_field(name: &str, config: &ExplorationConfig) -> String {
     let lower = name.to_lowercase();
     if lower.contains("path")
         || lower.contains("file")
@@ -223,6 +241,8 @@ mod tests {
                 env_canary: "ENV_CANARY".into(),
                 input_canary: "INPUT_CANARY".into(),
                 file_canary_path: Some("/tmp/file-canary.txt".into()),
+                fuzz_cases: 0,
+                client: ClientCapabilities::default(),
             },
         );
 
@@ -0,0 +1,449 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::mcp::driver::{McpCallPlan, McpDriverResult};
+use crate::mcp::explore::{
+    ExplorationConfig, select_tools, string_value_for_field, synthesize_arguments,
+};
+use crate::mcp::session::{McpLaunch, McpSession};
+
+/*
+schema-driven argument fuzzing. each selected tool is called with the baseline
+exploration arguments (required fields only), then with every property filled,
+then with one property at a time swapped for a boundary, format, wrong-type or
+attack value. all calls share one session, so state one call leaves behind is
+seen by the next. string payloads carry the input canary, so a tool that
+reflects or forwards them shows up as a flow.
+*/
+
+// sizes and nesting come from an untrusted schema; these keep what it can make us build small
+const MAX_ITEMS: usize = 64;
+const MAX_STRING: usize = 8 * 1024;
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuzzCoverage {
+    pub tools: Vec<ToolCoverage>,
+    // the transport error that ended the run early, e.g. the server crashing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aborted: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolCoverage {
+    pub tool: String,
+    pub cases: usize,
+    pub successes: usize,
+    pub errors: usize,
+    // responses counted once per distinct body
+    pub distinct_successes: usize,
+    pub distinct_errors: usize,
+}
+
+/// open a session, fuzz every tool exploration would call, then close it. with
+/// no tool worth exploring the planned call is made once
+pub fn fuzz_tools(
+    driver: &impl McpLaunch,
+    plan: &McpCallPlan,
+    config: &ExplorationConfig,
+) -> Result<(McpDriverResult, FuzzCoverage)> {
+    let mut session = McpSession::open(driver)?;
+    let tools_response = session.list_tools()?;
+
+    let mut targets: Vec<(String, Vec<Value>)> = select_tools(&tools_response, config)
+        .into_iter()
+        .map(|(name, schema)| (name, fuzz_arguments(&schema, config)))
+        .collect();
+    if targets.is_empty() {
+        targets.push((plan.tool_name.clone(), vec![plan.arguments.clone()]));
+    }
+
+    let mut coverage = FuzzCoverage::default();
+    'tools: for (tool, cases) in targets {
+        let mut tool_coverage = ToolCoverage {
+            tool: tool.clone(),
+            ..ToolCoverage::default()
+        };
+        let (mut successes, mut errors) = (HashSet::new(), HashSet::new());
+        for arguments in cases {
+            let result = match session.call(&tool, arguments) {
+                Ok(result) => result,
+                Err(err) => {
+                    coverage.aborted = Some(format!("{err:#}"));
+                    coverage.tools.push(tool_coverage);
+                    break 'tools;
+                }
+            };
+            tool_coverage.cases += 1;
+            if result.get("isError").and_then(Value::as_bool) == Some(true) {
+                tool_coverage.errors += 1;
+                errors.insert(result.to_string());
+            } else {
+                tool_coverage.successes += 1;
+                successes.insert(result.to_string());
+            }
+            tool_coverage.distinct_successes = successes.len();
+            tool_coverage.distinct_errors = errors.len();
+        }
+        coverage.tools.push(tool_coverage);
+    }
+
+    // a server that changes its tools once they have been used shows up in a second listing
+    if coverage.aborted.is_none() {
+        let _ = session.list_tools();
+    }
+    Ok((session.close()?, coverage))
+}
+
+/// argument sets for one tool, at most `config.fuzz_cases` of them
+pub fn fuzz_arguments(schema: &Value, config: &ExplorationConfig) -> Vec<Value> {
+    let mut cases = vec![synthesize_arguments(schema, config)];
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+
+    let full: Map<String, Value> = properties
+        .iter()
+        .map(|(name, prop)| (name.clone(), typical_value(name, prop, config, 0)))
+        .collect();
+    if !full.is_empty() {
+        cases.push(Value::Object(full.clone()));
+    }
+
+    // one property at a time, round robin so a small budget still reaches every property
+    let variants: Vec<(&String, Vec<Value>)> = properties
+        .iter()
+        .map(|(name, prop)| (name, variant_values(name, prop, config, 0)))
+        .collect();
+    let rounds = variants.iter().map(|(_, values)| values.len()).max();
+    for round in 0..rounds.unwrap_or(0) {
+        for (name, values) in &variants {
+            if let Some(value) = values.get(round) {
+                let mut case = full.clone();
+                case.insert(name.to_string(), value.clone());
+                cases.push(Value::Object(case));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    cases.retain(|case| seen.insert(case.to_string()));
+    cases.truncate(config.fuzz_cases.max(1));
+    cases
+}
+
+fn schema_type(schema: &Value) -> &str {
+    match &schema["type"] {
+        Value::String(ty) => ty,
+        // ["string", "null"] and the like: the first type that is not null
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|ty| *ty != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "string",
+    }
+}
+
+// a schema size such as minItems or maxLength; u64s past usize saturate
+fn schema_size(schema: &Value, key: &str) -> Option<usize> {
+    schema[key]
+        .as_u64()
+        .map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+}
+
+// a value the schema accepts, to keep the other fields valid while one is varied
+fn typical_value(name: &str, schema: &Value, config: &ExplorationConfig, depth: usize) -> Value {
+    if depth >= MAX_DEPTH {
+        return Value::Null;
+    }
+    if let Some(first) = schema.get("enum").and_then(|values| values.get(0)) {
+        return first.clone();
+    }
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+    match schema_type(schema) {
+        "boolean" => json!(true),
+        "integer" => json!(bounded(schema, 1.0) as i64),
+        "number" => json!(bounded(schema, 1.0)),
+        "null" => Value::Null,
+        "array" => {
+            // arrays inside arrays would multiply, so only the outermost is filled out
+            let min = if depth == 0 {
+                schema_size(schema, "minItems")
+                    .unwrap_or(1)
+                    .clamp(1, MAX_ITEMS)
+            } else {
+                1
+            };
+            json!(vec![
+                typical_value(name, &schema["items"], config, depth + 1);
+                min
+            ])
+        }
+        "object" => Value::Object(
+            schema["properties"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(key, prop)| (key.clone(), typical_value(key, prop, config, depth + 1)))
+                .collect(),
+        ),
+        _ => json!(typical_string(name, schema, config)),
+    }
+}
+
+fn bounded(schema: &Value, preferred: f64) -> f64 {
+    let mut value = preferred;
+    if let Some(min) = schema["minimum"].as_f64() {
+        value = value.max(min);
+    }
+    if let Some(max) = schema["maximum"].as_f64() {
+        value = value.min(max);
+    }
+    value
+}
+
+fn typical_string(name: &str, schema: &Value, config: &ExplorationConfig) -> String {
+    let canary = &config.input_canary;
+    let value = match schema["format"].as_str() {
+        Some("uri" | "url") => format!("https://example.com/{canary}"),
+        Some("email") => format!("{canary}@example.com"),
+        Some("date-time") => "2024-01-01T00:00:00Z".to_string(),
+        Some("date") => "2024-01-01".to_string(),
+        Some("uuid") => "00000000-0000-4000-8000-000000000000".to_string(),
+        _ => match schema["pattern"].as_str().and_then(pattern_sample) {
+            Some(sample) => sample,
+            None => string_value_for_field(name, config),
+        },
+    };
+    let min = schema_size(schema, "minLength")
+        .unwrap_or(0)
+        .min(MAX_STRING);
+    format!("{value:x<min$}")
+}
+
+/// a string matching simple patterns such as `^[a-z0-9-]+$` or `^\d{4}$`;
+/// anything fancier gets None
+fn pattern_sample(pattern: &str) -> Option<String> {
+    let body = pattern.strip_prefix('^').unwrap_or(pattern);
+    let body = body.strip_suffix('$').unwrap_or(body);
+    let (unit, quantifier) = if let Some(rest) = body.strip_prefix("\\d") {
+        ('0', rest)
+    } else if let Some(rest) = body.strip_prefix('[') {
+        let (class, rest) = rest.split_once(']')?;
+        let first = class
+            .chars()
+            .next()
+            .filter(|ch| !matches!(ch, '^' | '\\'))?;
+        (first, rest)
+    } else {
+        let special = |ch: char| "\\.[](){}*+?|^$".contains(ch);
+        return (!body.contains(special)).then(|| body.to_string());
+    };
+    let count = match quantifier {
+        "" | "+" | "*" => 1,
+        quantifier => {
+            let bounds = quantifier.strip_prefix('{')?.strip_suffix('}')?;
+            let min = bounds.split(',').next()?;
+            min.trim().parse::<usize>().ok()?.clamp(1, MAX_STRING)
+        }
+    };
+    Some(unit.to_string().repeat(count))
+}
+
+// values worth trying in place of the typical one, most interesting first
+fn variant_values(
+    name: &str,
+    schema: &Value,
+    config: &ExplorationConfig,
+    depth: usize,
+) -> Vec<Value> {
+    if depth >= MAX_DEPTH {
+        return Vec::new();
+    }
+    let canary = &config.input_canary;
+    let typical = typical_value(name, schema, config, depth);
+    let mut values = Vec::new();
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        values.extend(options.iter().cloned());
+        values.push(json!(canary));
+    } else {
+        match schema_type(schema) {
+            "boolean" => values.extend([json!(false), json!("true")]),
+            ty @ ("integer" | "number") => {
+                for key in ["minimum", "maximum"] {
+                    if let Some(bound) = schema[key].as_f64() {
+                        let outside = if key == "minimum" {
+                            bound - 1.0
+                        } else {
+                            bound + 1.0
+                        };
+                        values.extend([bound, outside].map(|n| number(ty, n)));
+                    }
+                }
+                values.extend([0.0, -1.0, 9_007_199_254_740_993.0].map(|n| number(ty, n)));
+                values.push(json!(canary));
+            }
+            "array" => {
+                let items = &schema["items"];
+                values.push(json!([]));
+                for item in variant_values(name, items, config, depth + 1)
+                    .into_iter()
+                    .take(3)
+                {
+                    values.push(json!([item]));
+                }
+                if let Some(max) = schema_size(schema, "maxItems") {
+                    let item = typical_value(name, items, config, depth + 1);
+                    let over = max.saturating_add(1).min(MAX_ITEMS);
+                    values.push(json!(vec![item; over]));
+                }
+                values.push(json!(canary));
+            }
+            "object" => {
+                values.push(json!({}));
+                let nested = typical.as_object().cloned().unwrap_or_default();
+                for (key, prop) in schema["properties"].as_object().into_iter().flatten() {
+                    if let Some(value) = variant_values(key, prop, config, depth + 1)
+                        .into_iter()
+                        .next()
+                    {
+                        let mut object = nested.clone();
+                        object.insert(key.clone(), value);
+                        values.push(Value::Object(object));
+                    }
+                }
+                values.push(json!(canary));
+            }
+            "null" => {}
+            _ => values.extend(string_payloads(name, schema, config)),
+        }
+    }
+
+    values.retain(|value| *value != typical);
+    values
+}
+
+fn number(ty: &str, n: f64) -> Value {
+    if ty == "integer" {
+        json!(n as i64)
+    } else {
+        json!(n)
+    }
+}
+
+fn string_payloads(name: &str, schema: &Value, config: &ExplorationConfig) -> Vec<Value> {
+    let canary = &config.input_canary;
+    let mut payloads = Vec::new();
+    let path_like = string_value_for_field(name, config) != *canary;
+    if path_like {
+        payloads.push("../../../../../../../../etc/passwd".to_string());
+        payloads.extend(config.file_canary_path.clone());
+    }
+    payloads.extend([
+        format!("; echo {canary}"),
+        format!("$(echo {canary})"),
+        format!("' OR '1'='1' -- {canary}"),
+        format!("{{{{7*7}}}}{canary}"),
+        format!("<img src=x onerror=alert('{canary}')>"),
+        format!("http://127.0.0.1:1/{canary}"),
+    ]);
+    if !path_like {
+        payloads.push("../../../../../../../../etc/passwd".to_string());
+    }
+    payloads.push(String::new());
+    let max = schema_size(schema, "maxLength").unwrap_or(4096);
+    let width = max.saturating_add(1).min(MAX_STRING);
+    payloads.push(format!("{canary:A<width$}"));
+
+    let mut values: Vec<Value> = payloads.into_iter().map(Value::String).collect();
+    values.push(json!(0));
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn covers_optional_fields_bounds_and_payloads() {
+        let config = ExplorationConfig {
+            enabled: true,
+            input_canary: "CANARY".into(),
+            fuzz_cases: 64,
+            ..ExplorationConfig::disabled()
+        };
+        let schema = json!({
+            "type": "object",
+            "required": ["path"],
+            "properties": {
+                "path": {"type": "string"},
+                "limit": {"type": "integer", "minimum": 1, "maximum": 50},
+                "id": {"type": "string", "pattern": "^[a-z0-9]{3}$"},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2}
+            }
+        });
+        let cases = fuzz_arguments(&schema, &config);
+
+        assert_eq!(cases[0], json!({"path": "CANARY"}));
+        assert_eq!(
+            cases[1],
+            json!({"path": "CANARY", "limit": 1, "id": "aaa", "tags": ["CANARY"]})
+        );
+        let has = |key: &str, value: Value| cases.iter().any(|case| case[key] == value);
+        assert!(has("path", json!("../../../../../../../../etc/passwd")));
+        assert!(has("path", json!("; echo CANARY")));
+        assert!(has("limit", json!(51)) && has("limit", json!(0)));
+        assert!(has("tags", json!(["CANARY", "CANARY", "CANARY"])));
+
+        let config = ExplorationConfig {
+            fuzz_cases: 5,
+            ..config
+        };
+        assert_eq!(fuzz_arguments(&schema, &config).len(), 5);
+    }
+
+    #[test]
+    fn caps_sizes_a_schema_asks_for() {
+        let config = ExplorationConfig {
+            enabled: true,
+            input_canary: "CANARY".into(),
+            fuzz_cases: 256,
+            ..ExplorationConfig::disabled()
+        };
+        let mut deep = json!({"type": "string"});
+        for _ in 0..64 {
+            deep = json!({"type": "array", "items": deep, "minItems": 64});
+        }
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "list": {"type": "array", "items": {"type": "string"},
+                         "minItems": 4_000_000_000u64, "maxItems": u64::MAX},
+                "text": {"type": "string", "minLength": u64::MAX, "maxLength": u64::MAX},
+                "code": {"type": "string", "pattern": "^[a-z]{999999999}$"},
+                "deep": deep
+            }
+        });
+
+        let cases = fuzz_arguments(&schema, &config);
+        let full = &cases[1];
+        assert_eq!(full["list"].as_array().unwrap().len(), MAX_ITEMS);
+        assert_eq!(full["text"].as_str().unwrap().len(), MAX_STRING);
+        assert_eq!(full["code"].as_str().unwrap().len(), MAX_STRING);
+        assert!(
+            cases
+                .iter()
+                .all(|case| case.to_string().len() < 4 * MAX_ITEMS * MAX_STRING)
+        );
+    }
+}
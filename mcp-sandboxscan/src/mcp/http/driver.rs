@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::driver::{McpCallPlan, McpDriver, McpDriverResult, McpExplore};
+use crate::mcp::explore::ExplorationConfig;
+use crate::mcp::native_stdio::stderr::{MAX_STDERR_BYTES, StderrCapture};
+use crate::mcp::native_stdio::{reap, settle, timed_out};
+use crate::mcp::session::{Launched, McpLaunch};
+use crate::mcp::surface::SurfaceConfig;
+use crate::mcp::transcript::McpTranscript;
+use crate::mcp::transport::run_session;
+use crate::sandbox::exec_evidence::{ExecutionBackend, ExecutionEvidence, TerminationReason};
+use crate::sandbox::native_trace::SyscallTracer;
+
+use super::transport::HttpTransport;
+
+// how long a server gets to start listening before the scan gives up on it
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// launches a server that speaks MCP over HTTP on loopback and drives it like
+/// the stdio driver does
+#[derive(Clone)]
+pub struct HttpMcpDriver {
+    pub command: String,
+    pub args: Vec<String>,
+    pub current_dir: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+    pub mcp_timeout: Option<Duration>,
+    // the server listens on 127.0.0.1:port and serves MCP at path
+    pub port: u16,
+    pub path: String,
+    pub tracer: Option<Arc<SyscallTracer>>,
+    // resources and prompts to read after tools/list; None skips them
+    pub surface: Option<SurfaceConfig>,
+    pub client: ClientCapabilities,
+}
+
+impl McpDriver for HttpMcpDriver {
+    fn call_tool(&self, plan: &McpCallPlan) -> Result<McpDriverResult> {
+        self.call_with_timeout(plan, None)
+    }
+}
+
+impl McpExplore for HttpMcpDriver {
+    fn call_tool_with_exploration(
+        &self,
+        plan: &McpCallPlan,
+        exploration: &ExplorationConfig,
+    ) -> Result<McpDriverResult> {
+        self.call_with_timeout(plan, Some(exploration.clone()))
+    }
+}
+
+impl HttpMcpDriver {
+    fn call_with_timeout(
+        &self,
+        plan: &McpCallPlan,
+        exploration: Option<ExplorationConfig>,
+    ) -> Result<McpDriverResult> {
+        let child_holder: Arc<Mutex<Option<Child>>> = Arc::default();
+        let Some(timeout) = self.mcp_timeout else {
+            return self.call_inner(plan, exploration.as_ref(), &child_holder);
+        };
+
+        let driver = self.clone();
+        let plan = plan.clone();
+        let child_holder_worker = child_holder.clone();
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let result = driver.call_inner(&plan, exploration.as_ref(), &child_holder_worker);
+            let _ = tx.send(result);
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if let Some(mut child) = child_holder.lock().ok().and_then(|mut slot| slot.take()) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                Err(timed_out(timeout))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                bail!("MCP scan thread exited unexpectedly");
+            }
+        }
+    }
+
+    fn call_inner(
+        &self,
+        plan: &McpCallPlan,
+        exploration: Option<&ExplorationConfig>,
+        child_holder: &Mutex<Option<Child>>,
+    ) -> Result<McpDriverResult> {
+        let started = Instant::now();
+        let Launched {
+            mut transport,
+            stderr,
+            backend,
+            ..
+        } = match self.launch(child_holder) {
+            Ok(launched) => launched,
+            Err(err) => {
+                if let Some(child) = child_holder.lock().ok().and_then(|mut slot| slot.take()) {
+                    reap(child);
+                }
+                return Err(err);
+            }
+        };
+
+        let result = (|| -> Result<McpDriverResult> {
+            self.ready(child_holder)?;
+            let mut transcript = McpTranscript { events: Vec::new() };
+            let tool_result_payload = run_session(
+                &mut transport,
+                &self.client,
+                plan,
+                exploration,
+                self.surface.as_ref(),
+                &mut transcript,
+            )?;
+
+            Ok(McpDriverResult {
+                exec: ExecutionEvidence {
+                    backend,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    exit_code: None,
+                    duration_ms: started.elapsed().as_millis(),
+                    termination: TerminationReason::Normal,
+                },
+                transcript,
+                tool_result_payload,
+            })
+        })();
+
+        let child = child_holder.lock().ok().and_then(|mut slot| slot.take());
+        let exited = child.and_then(reap);
+        let stderr = stderr.map(StderrCapture::finish).unwrap_or_default();
+        settle(result, exited, stderr)
+    }
+}
+
+impl McpLaunch for HttpMcpDriver {
+    fn launch(&self, child_holder: &Mutex<Option<Child>>) -> Result<Launched> {
+        let mut cmd = Command::new(&self.command);
+        cmd.args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+
+        let spawned = match &self.tracer {
+            Some(tracer) => tracer.spawn_with(&mut cmd, |cmd| cmd.spawn()),
+            None => cmd.spawn(),
+        };
+        let mut child =
+            spawned.with_context(|| format!("failed to spawn MCP server `{}`", self.command))?;
+        let stderr = child
+            .stderr
+            .take()
+            .map(|stderr| StderrCapture::start(stderr, MAX_STDERR_BYTES));
+        if let Ok(mut slot) = child_holder.lock() {
+            *slot = Some(child);
+        }
+
+        let root = self
+            .current_dir
+            .as_ref()
+            .map(|dir| format!("file://{}", dir.display()));
+        Ok(Launched {
+            transport: Box::new(HttpTransport::new(
+                self.addr(),
+                &self.path,
+                self.client.clone(),
+                root,
+            )),
+            stderr,
+            backend: ExecutionBackend::NativeHttp,
+            client: self.client.clone(),
+            timeout: self.mcp_timeout,
+        })
+    }
+
+    fn ready(&self, child: &Mutex<Option<Child>>) -> Result<()> {
+        wait_for_port(self.addr(), child)
+    }
+}
+
+impl HttpMcpDriver {
+    fn addr(&self) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], self.port))
+    }
+}
+
+/// poll until the server accepts connections; one that exits first has failed
+fn wait_for_port(addr: SocketAddr, child: &Mutex<Option<Child>>) -> Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_ok() {
+            return Ok(());
+        }
+        let exited = child
+            .lock()
+            .ok()
+            .and_then(|mut slot| slot.as_mut()?.try_wait().ok().flatten());
+        if let Some(status) = exited {
+            bail!("MCP server exited ({status}) before listening on {addr}");
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "MCP server did not listen on {addr} within {}s",
+                STARTUP_TIMEOUT.as_secs()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::mcp::session::McpSession;
+
+    // answers initialize, tools/list and tools/call; MODE picks the transport, or
+    // "rejecting" (400 on every POST) and "silent" (an SSE stream with no endpoint)
+    const MOCK_SERVER: &str = r#"
+import json, os, queue, sys, time
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+MODE = sys.argv[1]
+STREAMABLE = MODE in ("streamable", "rejecting")
+replies = queue.Queue()
+
+def reply(msg):
+    method = msg.get("method")
+    if method == "initialize":
+        result = {"protocolVersion": "2025-06-18", "capabilities": {"tools": {}},
+                  "serverInfo": {"name": "mock-http", "version": "0.1.0"}}
+    elif method == "tools/list":
+        result = {"tools": [{"name": "echo", "inputSchema": {"type": "object"}}]}
+    elif method == "tools/call":
+        result = {"content": [{"type": "text", "text": "mock result over http"}]}
+    else:
+        return None
+    return {"jsonrpc": "2.0", "id": msg["id"], "result": result}
+
+class Handler(BaseHTTPRequestHandler):
+    protocol_version = "HTTP/1.1"
+
+    def log_message(self, *args):
+        pass
+
+    def send(self, status, body=b"", headers=()):
+        self.send_response(status)
+        for key, value in headers:
+            self.send_header(key, value)
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def do_GET(self):
+        if STREAMABLE or self.path != "/sse":
+            return self.send(405)
+        self.send_response(200)
+        self.send_header("Content-Type", "text/event-stream")
+        self.end_headers()
+        while MODE == "silent":
+            self.wfile.write(b": keep-alive\n\n")
+            self.wfile.flush()
+            time.sleep(0.2)
+        self.wfile.write(b"event: endpoint\ndata: /messages?session=1\n\n")
+        self.wfile.flush()
+        while True:
+            msg = replies.get()
+            self.wfile.write(b"event: message\ndata: " + json.dumps(msg).encode() + b"\n\n")
+            self.wfile.flush()
+
+    def do_DELETE(self):
+        print("DELETE " + self.headers.get("Mcp-Session-Id", ""), file=sys.stderr, flush=True)
+        self.send(200)
+
+    def do_POST(self):
+        msg = json.loads(self.rfile.read(int(self.headers["Content-Length"])))
+        if MODE == "rejecting":
+            return self.send(400, b"unsupported protocol version")
+        if not STREAMABLE:
+            if not self.path.startswith("/messages"):
+                return self.send(404)
+            out = reply(msg)
+            if out:
+                replies.put(out)
+            return self.send(202)
+        if msg.get("method") != "initialize" and self.headers.get("Mcp-Session-Id") != "s-1":
+            return self.send(400, b"missing session")
+        out = reply(msg)
+        if out is None:
+            return self.send(202)
+        body = b"data: " + json.dumps(out).encode() + b"\n\n"
+        self.send(200, body, [("Content-Type", "text/event-stream"), ("Mcp-Session-Id", "s-1")])
+
+ThreadingHTTPServer(("127.0.0.1", int(os.environ["PORT"])), Handler).serve_forever()
+"#;
+
+    fn mock_driver(mode: &str) -> HttpMcpDriver {
+        let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+        HttpMcpDriver {
+            command: "python3".to_string(),
+            args: vec!["-c".to_string(), MOCK_SERVER.to_string(), mode.to_string()],
+            current_dir: None,
+            env: HashMap::from([("PORT".to_string(), port.to_string())]),
+            mcp_timeout: Some(Duration::from_secs(30)),
+            port,
+            path: "/mcp".to_string(),
+            tracer: None,
+            surface: None,
+            client: ClientCapabilities::default(),
+        }
+    }
+
+    #[test]
+    fn driver_calls_tool_over_streamable_http_and_legacy_sse() {
+        let plan = McpCallPlan {
+            tool_name: "echo".to_string(),
+            arguments: json!({"message": "hello"}),
+        };
+        for mode in ["streamable", "sse"] {
+            let result = mock_driver(mode).call_tool(&plan).expect(mode);
+            assert_eq!(
+                result.tool_result_payload["content"][0]["text"], "mock result over http",
+                "{mode}"
+            );
+            assert!(matches!(result.exec.backend, ExecutionBackend::NativeHttp));
+            assert_eq!(result.transcript.events.len(), 7, "{mode}");
+        }
+    }
+
+    #[test]
+    fn session_ends_streamable_http_session_with_delete() {
+        let mut session = McpSession::open(&mock_driver("streamable")).expect("open session");
+        for _ in 0..2 {
+            let result = session.call("echo", json!({})).unwrap();
+            assert_eq!(result["content"][0]["text"], "mock result over http");
+        }
+        let result = session.close().expect("close session");
+        assert_eq!(result.exec.stderr, "DELETE s-1\n");
+    }
+
+    #[test]
+    fn other_client_errors_do_not_fall_back_to_legacy_sse() {
+        let plan = McpCallPlan {
+            tool_name: "echo".to_string(),
+            arguments: json!({}),
+        };
+        let Err(err) = mock_driver("rejecting").call_tool(&plan) else {
+            panic!("a 400 on initialize must fail the call");
+        };
+        let message = format!("{err:#}");
+        assert!(message.contains("HTTP 400"), "{message}");
+        assert!(
+            message.contains("unsupported protocol version"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn gives_up_on_an_sse_stream_that_names_no_endpoint() {
+        let plan = McpCallPlan {
+            tool_name: "echo".to_string(),
+            arguments: json!({}),
+        };
+        let started = Instant::now();
+        let Err(err) = mock_driver("silent").call_tool(&plan) else {
+            panic!("a stream without an endpoint must fail the call");
+        };
+        assert!(format!("{err:#}").contains("named no endpoint"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(20));
+    }
+}
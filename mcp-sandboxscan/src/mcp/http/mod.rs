@@ -0,0 +1,5 @@
+mod driver;
+mod transport;
+mod wire;
+
+pub use driver::HttpMcpDriver;
@@ -0,0 +1,300 @@
+use std::io::BufRead;
+use std::net::{Shutdown, SocketAddr};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::transcript::{McpDirection, McpTranscript};
+use crate::mcp::transport::McpTransport;
+
+use super::wire::{Response, next_event, send};
+
+/*
+Streamable HTTP: every message is POSTed to one endpoint and the reply to a
+request comes back as JSON or as an SSE stream that may carry server requests
+first. legacy HTTP+SSE (2024-11-05): a GET stream names a second endpoint to
+POST to, and every reply arrives on that stream. which one a server speaks is
+only known once it accepts or rejects the POSTed initialize.
+*/
+
+// how long a legacy SSE stream gets to name its POST endpoint
+const ENDPOINT_WAIT: Duration = Duration::from_secs(5);
+
+pub(super) struct HttpTransport {
+    addr: SocketAddr,
+    path: String,
+    client: ClientCapabilities,
+    root: Option<String>,
+    // set by the server on initialize; sent back on every later request
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+    // legacy HTTP+SSE only: where to POST, and the stream replies come back on
+    sse_endpoint: Option<String>,
+    sse_stream: Option<Box<dyn BufRead + Send>>,
+    detected: bool,
+}
+
+impl HttpTransport {
+    pub fn new(
+        addr: SocketAddr,
+        path: &str,
+        client: ClientCapabilities,
+        root: Option<String>,
+    ) -> Self {
+        Self {
+            addr,
+            path: path.to_string(),
+            client,
+            root,
+            session_id: None,
+            protocol_version: None,
+            sse_endpoint: None,
+            sse_stream: None,
+            detected: false,
+        }
+    }
+
+    fn post(&mut self, target: &str, message: &Value) -> Result<Response> {
+        let body = serde_json::to_vec(message)?;
+        let mut headers = vec![
+            ("Content-Type", "application/json"),
+            ("Accept", "application/json, text/event-stream"),
+        ];
+        if let Some(id) = &self.session_id {
+            headers.push(("Mcp-Session-Id", id));
+        }
+        if let Some(version) = &self.protocol_version {
+            headers.push(("MCP-Protocol-Version", version));
+        }
+        let response = send(self.addr, "POST", target, &headers, &body)?;
+        if let Some(id) = response.header("mcp-session-id") {
+            self.session_id = Some(id.to_string());
+        }
+        Ok(response)
+    }
+
+    // where client messages go: the endpoint a legacy SSE stream named, or the MCP path
+    fn target(&self) -> String {
+        self.sse_endpoint
+            .clone()
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    /// POST a message whose reply, if any, arrives elsewhere: a notification, an
+    /// answer to a server request, or any request on a legacy SSE server
+    fn post_one_way(&mut self, message: &Value) -> Result<()> {
+        let target = self.target();
+        let response = self.post(&target, message)?;
+        if response.status >= 400 {
+            let status = response.status;
+            bail!("HTTP {status} from {target}: {}", response.text()?.trim());
+        }
+        Ok(())
+    }
+
+    /// a request, reply or notification from the server. returns the reply
+    /// once it is the one waited for
+    fn receive(
+        &mut self,
+        message: Value,
+        expected_id: u64,
+        transcript: &mut McpTranscript,
+    ) -> Result<Option<Value>> {
+        if message.get("method").is_some() {
+            if message.get("id").is_some() {
+                transcript.record(McpDirection::ServerToClient, &message);
+                let reply = self.client.answer(&message, self.root.as_deref());
+                self.post_one_way(&reply)?;
+                transcript.record(McpDirection::ClientToServer, &reply);
+            }
+            return Ok(None);
+        }
+        Ok((message["id"].as_u64() == Some(expected_id)).then_some(message))
+    }
+
+    fn await_reply(
+        &mut self,
+        response: Response,
+        expected_id: u64,
+        transcript: &mut McpTranscript,
+    ) -> Result<Value> {
+        if response.status >= 400 {
+            let status = response.status;
+            bail!(
+                "HTTP {status} from {}: {}",
+                self.path,
+                response.text()?.trim()
+            );
+        }
+        if response.is_event_stream() {
+            let mut body = response.body;
+            while let Some(event) = next_event(&mut *body)? {
+                let Ok(message) = serde_json::from_str(&event.data) else {
+                    continue;
+                };
+                if let Some(reply) = self.receive(message, expected_id, transcript)? {
+                    return Ok(reply);
+                }
+            }
+            bail!("SSE stream ended before the response to request {expected_id}");
+        }
+
+        let body: Value = serde_json::from_str(&response.text()?)
+            .context("failed to parse MCP HTTP response body")?;
+        let messages = match body {
+            Value::Array(batch) => batch,
+            single => vec![single],
+        };
+        for message in messages {
+            if let Some(reply) = self.receive(message, expected_id, transcript)? {
+                return Ok(reply);
+            }
+        }
+        bail!("HTTP response carried no reply to request {expected_id}");
+    }
+
+    fn await_sse_reply(
+        &mut self,
+        expected_id: u64,
+        transcript: &mut McpTranscript,
+    ) -> Result<Value> {
+        let mut stream = self.sse_stream.take().context("SSE stream not open")?;
+        let reply = loop {
+            let Some(event) = next_event(&mut *stream)? else {
+                bail!("SSE stream ended before the response to request {expected_id}");
+            };
+            if event.event != "message" {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str(&event.data) else {
+                continue;
+            };
+            if let Some(reply) = self.receive(message, expected_id, transcript)? {
+                break reply;
+            }
+        };
+        self.sse_stream = Some(stream);
+        Ok(reply)
+    }
+
+    // legacy servers serve the event stream on the MCP path or on /sse
+    fn open_sse(&mut self) -> Result<()> {
+        let mut paths = vec![self.path.clone()];
+        if self.path != "/sse" {
+            paths.push("/sse".to_string());
+        }
+        for path in paths {
+            let response = send(
+                self.addr,
+                "GET",
+                &path,
+                &[("Accept", "text/event-stream")],
+                b"",
+            )?;
+            if response.status != 200 || !response.is_event_stream() {
+                continue;
+            }
+            // keep-alive comments would stop a read timeout from ever firing, so a
+            // watchdog closes the stream once the wait is over
+            let mut body = response.body;
+            let socket = response.socket;
+            let (found, watch) = mpsc::channel::<()>();
+            let watchdog = thread::spawn(move || {
+                let expired = watch.recv_timeout(ENDPOINT_WAIT) == Err(RecvTimeoutError::Timeout);
+                if expired {
+                    let _ = socket.shutdown(Shutdown::Read);
+                }
+                expired
+            });
+            let endpoint = loop {
+                match next_event(&mut *body) {
+                    Ok(Some(event)) if event.event == "endpoint" => break Ok(Some(event.data)),
+                    Ok(Some(_)) => {}
+                    other => break other.map(|_| None),
+                }
+            };
+            drop(found);
+            if watchdog.join().unwrap_or(true) {
+                bail!(
+                    "SSE stream at {path} named no endpoint within {}s",
+                    ENDPOINT_WAIT.as_secs()
+                );
+            }
+            let Some(endpoint) = endpoint? else {
+                bail!("SSE stream at {path} closed before naming an endpoint");
+            };
+            self.sse_endpoint = Some(origin_form(endpoint.trim()));
+            self.sse_stream = Some(body);
+            return Ok(());
+        }
+        bail!(
+            "server at {} speaks neither Streamable HTTP nor HTTP+SSE on {}",
+            self.addr,
+            self.path
+        )
+    }
+}
+
+/// the path and query of an endpoint the server may give as a full URL
+fn origin_form(endpoint: &str) -> String {
+    let path = match endpoint.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |idx| &rest[idx..]),
+        None => endpoint,
+    };
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    }
+}
+
+impl McpTransport for HttpTransport {
+    fn request(&mut self, request: Value, transcript: &mut McpTranscript) -> Result<Value> {
+        let id = request["id"].as_u64().context("request without id")?;
+        transcript.record(McpDirection::ClientToServer, &request);
+
+        let reply = if self.sse_stream.is_some() {
+            self.post_one_way(&request)?;
+            self.await_sse_reply(id, transcript)?
+        } else {
+            let path = self.path.clone();
+            let response = self.post(&path, &request)?;
+            // a legacy-only server has no POST handler on the MCP path; any other
+            // 4xx is a real error and is reported as such
+            if !self.detected && matches!(response.status, 404 | 405) {
+                self.open_sse()?;
+                self.post_one_way(&request)?;
+                self.await_sse_reply(id, transcript)?
+            } else {
+                let reply = self.await_reply(response, id, transcript)?;
+                if let Some(version) = reply["result"]["protocolVersion"].as_str() {
+                    self.protocol_version = Some(version.to_string());
+                }
+                reply
+            }
+        };
+        self.detected = true;
+        transcript.record(McpDirection::ServerToClient, &reply);
+        Ok(reply)
+    }
+
+    fn notify(&mut self, notification: Value, transcript: &mut McpTranscript) -> Result<()> {
+        self.post_one_way(&notification)?;
+        transcript.record(McpDirection::ClientToServer, &notification);
+        Ok(())
+    }
+
+    // Streamable HTTP sessions are ended with a DELETE; servers may refuse it
+    fn close(&mut self) -> Result<()> {
+        let Some(id) = self.session_id.take() else {
+            return Ok(());
+        };
+        let path = self.path.clone();
+        send(self.addr, "DELETE", &path, &[("Mcp-Session-Id", &id)], b"")?;
+        Ok(())
+    }
+}
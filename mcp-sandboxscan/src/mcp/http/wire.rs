@@ -0,0 +1,241 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+
+/*
+just enough HTTP/1.1 for a client talking to a server on loopback: one request
+per connection, bodies framed by Content-Length, chunked encoding or EOF, and
+text/event-stream bodies read one event at a time as they arrive.
+*/
+
+// a server that goes quiet mid-response must not hang the scan when no MCP
+// timeout is set; long enough for a slow tool call
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub(super) struct Response {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    pub body: Box<dyn BufRead + Send>,
+    // the connection under `body`, so a caller can give up on it
+    pub socket: TcpStream,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_event_stream(&self) -> bool {
+        self.header("content-type")
+            .is_some_and(|ct| ct.to_ascii_lowercase().starts_with("text/event-stream"))
+    }
+
+    pub fn text(mut self) -> Result<String> {
+        let mut body = String::new();
+        self.body.read_to_string(&mut body)?;
+        Ok(body)
+    }
+}
+
+pub(super) fn send(
+    addr: SocketAddr,
+    method: &str,
+    target: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<Response> {
+    let mut stream =
+        TcpStream::connect(addr).with_context(|| format!("failed to connect to {addr}"))?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let socket = stream.try_clone()?;
+    let mut head = format!("{method} {target} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n");
+    for (key, value) in headers {
+        head.push_str(&format!("{key}: {value}\r\n"));
+    }
+    if !body.is_empty() || method == "POST" {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("malformed HTTP status line {:?}", line.trim_end()))?;
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("connection closed inside HTTP response headers");
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = header.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut response = Response {
+        status,
+        headers,
+        body: Box::new(io::empty()),
+        socket,
+    };
+    response.body = if response
+        .header("transfer-encoding")
+        .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"))
+    {
+        Box::new(BufReader::new(Chunked {
+            inner: reader,
+            remaining: 0,
+            done: false,
+        }))
+    } else if let Some(len) = response
+        .header("content-length")
+        .and_then(|len| len.parse::<u64>().ok())
+    {
+        Box::new(reader.take(len))
+    } else {
+        Box::new(reader)
+    };
+    Ok(response)
+}
+
+struct Chunked<R> {
+    inner: R,
+    // bytes left in the current chunk
+    remaining: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Read for Chunked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let mut size = String::new();
+            if self.inner.read_line(&mut size)? == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+            let size = size.split(';').next().unwrap_or_default().trim();
+            self.remaining = usize::from_str_radix(size, 16)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad chunk size line"))?;
+            // trailers after the last chunk are not needed
+            if self.remaining == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+        }
+        let want = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..want])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n;
+        if self.remaining == 0 {
+            let mut crlf = String::new();
+            self.inner.read_line(&mut crlf)?;
+        }
+        Ok(n)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// the next event on a text/event-stream body, or None once the stream ends
+pub(super) fn next_event(reader: &mut dyn BufRead) -> Result<Option<SseEvent>> {
+    let mut event = SseEvent::default();
+    let mut has_data = false;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            // an event cut off by the end of the stream is discarded
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if has_data {
+                if event.event.is_empty() {
+                    event.event = "message".to_string();
+                }
+                return Ok(Some(event));
+            }
+            event = SseEvent::default();
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event.event = value.to_string(),
+            "data" => {
+                if has_data {
+                    event.data.push('\n');
+                }
+                event.data.push_str(value);
+                has_data = true;
+            }
+            // comments, id and retry are not needed
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_chunked_event_stream() {
+        let stream = ": keep-alive\n\nevent: endpoint\ndata: /messages?session=1\n\n\
+                      data: {\"a\":\ndata: 1}\n\ndata: cut off";
+        let mut chunked = Vec::new();
+        for piece in stream.as_bytes().chunks(7) {
+            chunked.extend(format!("{:x}\r\n", piece.len()).as_bytes());
+            chunked.extend(piece);
+            chunked.extend(b"\r\n");
+        }
+        chunked.extend(b"0\r\n\r\n");
+
+        let mut body = BufReader::new(Chunked {
+            inner: &chunked[..],
+            remaining: 0,
+            done: false,
+        });
+        assert_eq!(
+            next_event(&mut body).unwrap(),
+            Some(SseEvent {
+                event: "endpoint".to_string(),
+                data: "/messages?session=1".to_string(),
+            })
+        );
+        assert_eq!(
+            next_event(&mut body).unwrap(),
+            Some(SseEvent {
+                event: "message".to_string(),
+                data: "{\"a\":\n1}".to_string(),
+            })
+        );
+        assert_eq!(next_event(&mut body).unwrap(), None);
+    }
+}
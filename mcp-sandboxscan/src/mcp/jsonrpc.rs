@@ -4,7 +4,7 @@ use serde_json::{Value, json};
 generate MCP messages --- message builder from MCP client side
 */
 
-pub fn initialize_request(id: u64) -> Value {
+pub fn initialize_request(id: u64, capabilities: Value) -> Value {
     // variables and expressions can be interpolated directly into the JSON value
     json!({
         "jsonrpc": "2.0",
@@ -12,7 +12,7 @@ pub fn initialize_request(id: u64) -> Value {
         "method": "initialize",
         "params": {
             "protocolVersion": "2025-06-18",
-            "capabilities": {},
+            "capabilities": capabilities,
             "clientInfo": {
                 "name": "mcp-sandboxscan",
                 "version": "0.1.0"
@@ -49,3 +49,53 @@ pub fn tools_call_request(id: u64, name: &str, arguments: Value) -> Value {
         }
     })
 }
+
+pub fn resources_list_request(id: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "resources/list",
+        "params": {}
+    })
+}
+
+pub fn resource_templates_list_request(id: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "resources/templates/list",
+        "params": {}
+    })
+}
+
+pub fn resources_read_request(id: u64, uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "resources/read",
+        "params": {
+            "uri": uri
+        }
+    })
+}
+
+pub fn prompts_list_request(id: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "prompts/list",
+        "params": {}
+    })
+}
+
+pub fn prompts_get_request(id: u64, name: &str, arguments: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "prompts/get",
+        "params": {
+            "name": name,
+            "arguments": arguments
+        }
+    })
+}
@@ -1,6 +1,12 @@
+pub mod client;
 pub mod driver;
 pub mod events;
 pub mod explore;
+pub mod fuzz;
+pub mod http;
 pub mod jsonrpc;
 pub mod native_stdio;
+pub mod session;
+pub mod surface;
 pub mod transcript;
+pub mod transport;
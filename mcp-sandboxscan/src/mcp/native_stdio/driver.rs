@@ -7,38 +7,69 @@ use std::time::Duration;
 
 use anyhow::{Context, Result, bail};
 
-use crate::mcp::driver::{McpCallPlan, McpDriver, McpDriverResult};
-use crate::mcp::explore::{ExplorationConfig, build_exploration_plans};
-use crate::mcp::jsonrpc::{
-    initialize_request, initialized_notification, tools_call_request, tools_list_request,
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::driver::{McpCallPlan, McpDriver, McpDriverResult, McpExplore};
+use crate::mcp::explore::ExplorationConfig;
+use crate::mcp::session::{Launched, McpLaunch};
+use crate::mcp::surface::SurfaceConfig;
+use crate::mcp::transcript::McpTranscript;
+use crate::mcp::transport::run_session;
+use crate::sandbox::exec_evidence::{
+    ExecutionBackend, ExecutionEvidence, TerminationError, TerminationReason,
 };
-use crate::mcp::transcript::{McpDirection, McpTranscript};
-use crate::sandbox::exec_evidence::{ExecutionBackend, ExecutionEvidence};
-
-use super::protocol::{CommandExt, StdioFraming, read_response_with_id, record, send_message};
-
-fn pick_tool_name(tools_response: &serde_json::Value, preferred: &str) -> String {
-    let Some(tools) = tools_response
-        .get("result")
-        .and_then(|r| r.get("tools"))
-        .and_then(|t| t.as_array())
-    else {
-        return preferred.to_string();
-    };
+use crate::sandbox::native_isolation::NativeIsolation;
+use crate::sandbox::native_trace::SyscallTracer;
+
+use super::protocol::{CommandExt, StdioFraming, StdioTransport};
+use super::stderr::{MAX_STDERR_BYTES, StderrCapture};
+
+pub(crate) fn timed_out(timeout: Duration) -> anyhow::Error {
+    TerminationError {
+        termination: TerminationReason::Timeout {
+            after_ms: timeout.as_millis(),
+        },
+        message: format!("MCP server timed out after {}s", timeout.as_secs()),
+    }
+    .into()
+}
 
-    if tools
-        .iter()
-        .any(|t| t.get("name").and_then(|n| n.as_str()) == Some(preferred))
-    {
-        return preferred.to_string();
+/// stop the server; if it had already exited on its own, report how
+pub(crate) fn reap(mut child: Child) -> Option<TerminationReason> {
+    if let Ok(Some(status)) = child.try_wait() {
+        return Some(TerminationReason::from_exit_status(status));
     }
+    let _ = child.kill();
+    let _ = child.wait();
+    None
+}
 
-    tools
-        .first()
-        .and_then(|t| t.get("name"))
-        .and_then(|n| n.as_str())
-        .unwrap_or(preferred)
-        .to_string()
+/// attach what became of the server to the outcome of talking to it
+pub(crate) fn settle(
+    result: Result<McpDriverResult>,
+    exited: Option<TerminationReason>,
+    stderr: String,
+) -> Result<McpDriverResult> {
+    let result = result.map(|mut driven| {
+        driven.exec.stderr = stderr;
+        driven
+    });
+
+    match (result, exited) {
+        (Ok(mut driven), Some(termination)) => {
+            driven.exec.termination = termination;
+            Ok(driven)
+        }
+        // a server that died mid-session explains the protocol error
+        (Err(err), Some(termination)) => match termination.failure() {
+            Some(failure) => Err(TerminationError {
+                message: format!("MCP server {failure}: {err:#}"),
+                termination,
+            }
+            .into()),
+            None => Err(err),
+        },
+        (result, None) => result,
+    }
 }
 
 #[derive(Clone)]
@@ -49,6 +80,11 @@ pub struct NativeStdioMcpDriver {
     pub framing: StdioFraming,
     pub env: HashMap<String, String>,
     pub mcp_timeout: Option<Duration>,
+    pub isolation: Option<NativeIsolation>,
+    pub tracer: Option<Arc<SyscallTracer>>,
+    // resources and prompts to read after tools/list; None skips them
+    pub surface: Option<SurfaceConfig>,
+    pub client: ClientCapabilities,
 }
 
 impl McpDriver for NativeStdioMcpDriver {
@@ -77,7 +113,7 @@ impl McpDriver for NativeStdioMcpDriver {
                         let _ = child.wait();
                     }
                 }
-                bail!("MCP server timed out after {}s", timeout.as_secs());
+                Err(timed_out(timeout))
             }
             Err(mpsc::RecvTimeoutError::Disconnected) => {
                 bail!("MCP scan thread exited unexpectedly");
@@ -86,8 +122,8 @@ impl McpDriver for NativeStdioMcpDriver {
     }
 }
 
-impl NativeStdioMcpDriver {
-    pub fn call_tool_with_exploration(
+impl McpExplore for NativeStdioMcpDriver {
+    fn call_tool_with_exploration(
         &self,
         plan: &McpCallPlan,
         exploration: &ExplorationConfig,
@@ -118,14 +154,16 @@ impl NativeStdioMcpDriver {
                         let _ = child.wait();
                     }
                 }
-                bail!("MCP server timed out after {}s", timeout.as_secs());
+                Err(timed_out(timeout))
             }
             Err(mpsc::RecvTimeoutError::Disconnected) => {
                 bail!("MCP scan thread exited unexpectedly");
             }
         }
     }
+}
 
+impl NativeStdioMcpDriver {
     fn call_tool_inner(
         &self,
         plan: &McpCallPlan,
@@ -141,155 +179,106 @@ impl NativeStdioMcpDriver {
         child_holder: Option<Arc<Mutex<Option<Child>>>>,
     ) -> Result<McpDriverResult> {
         let started = std::time::Instant::now();
-        let mut cmd = Command::new(&self.command);
-        cmd.args(&self.args)
-            .current_dir_opt(self.current_dir.as_ref())
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null());
-        for (key, value) in &self.env {
-            cmd.env(key, value);
-        }
-
-        let mut child = cmd
-            .spawn()
-            .with_context(|| format!("failed to spawn MCP server `{}`", self.command))?;
-
-        let mut stdin = child.stdin.take().context("failed to open MCP stdin")?;
-        let stdout = child.stdout.take().context("failed to open MCP stdout")?;
-
-        let mut owned_child = if let Some(holder) = child_holder.as_ref() {
-            if let Ok(mut slot) = holder.lock() {
-                *slot = Some(child);
+        let child_holder = child_holder.unwrap_or_default();
+        let Launched {
+            mut transport,
+            stderr,
+            backend,
+            ..
+        } = match self.launch(&child_holder) {
+            Ok(launched) => launched,
+            Err(err) => {
+                if let Some(child) = child_holder.lock().ok().and_then(|mut slot| slot.take()) {
+                    reap(child);
+                }
+                return Err(err);
             }
-            None
-        } else {
-            Some(child)
         };
-
-        let mut reader = BufReader::new(stdout);
-
         let mut transcript = McpTranscript { events: Vec::new() };
 
         let result = (|| -> Result<McpDriverResult> {
-            let initialize = initialize_request(1);
-            send_message(&mut stdin, &initialize, self.framing)?;
-            record(&mut transcript, McpDirection::ClientToServer, &initialize);
-
-            let initialize_response = read_response_with_id(&mut reader, 1, self.framing)?;
-            record(
-                &mut transcript,
-                McpDirection::ServerToClient,
-                &initialize_response,
-            );
-
-            let initialized = initialized_notification();
-            send_message(&mut stdin, &initialized, self.framing)?;
-            record(&mut transcript, McpDirection::ClientToServer, &initialized);
-
-            let tools_list = tools_list_request(2);
-            send_message(&mut stdin, &tools_list, self.framing)?;
-            record(&mut transcript, McpDirection::ClientToServer, &tools_list);
-
-            let tools_response = read_response_with_id(&mut reader, 2, self.framing)?;
-            record(
+            let tool_result_payload = run_session(
+                &mut transport,
+                &self.client,
+                plan,
+                exploration,
+                self.surface.as_ref(),
                 &mut transcript,
-                McpDirection::ServerToClient,
-                &tools_response,
-            );
-
-            let exploration_enabled = matches!(exploration, Some(config) if config.enabled);
-            let plans = match exploration {
-                Some(config) if config.enabled => {
-                    let mut plans = build_exploration_plans(&tools_response, config);
-                    if plans.is_empty() {
-                        plans.push(McpCallPlan {
-                            tool_name: pick_tool_name(&tools_response, &plan.tool_name),
-                            arguments: plan.arguments.clone(),
-                        });
-                    }
-                    plans
-                }
-                _ => vec![McpCallPlan {
-                    tool_name: pick_tool_name(&tools_response, &plan.tool_name),
-                    arguments: plan.arguments.clone(),
-                }],
-            };
-
-            let mut results = Vec::new();
-            for (idx, selected_plan) in plans.iter().enumerate() {
-                let request_id = 3 + idx as u64;
-                let call = tools_call_request(
-                    request_id,
-                    &selected_plan.tool_name,
-                    selected_plan.arguments.clone(),
-                );
-                send_message(&mut stdin, &call, self.framing)?;
-                record(&mut transcript, McpDirection::ClientToServer, &call);
-
-                let call_response = read_response_with_id(&mut reader, request_id, self.framing)?;
-                record(
-                    &mut transcript,
-                    McpDirection::ServerToClient,
-                    &call_response,
-                );
-
-                let result = if let Some(result) = call_response.get("result").cloned() {
-                    result
-                } else if exploration_enabled {
-                    serde_json::json!({
-                        "isError": true,
-                        "error": call_response.get("error").cloned().unwrap_or_else(|| {
-                            serde_json::json!({
-                                "message": "tools/call response missing result"
-                            })
-                        })
-                    })
-                } else {
-                    bail!("tools/call response missing result");
-                };
-                results.push(serde_json::json!({
-                    "tool": selected_plan.tool_name,
-                    "arguments": selected_plan.arguments.clone(),
-                    "result": result,
-                }));
-            }
-
-            let tool_result_payload = if results.len() == 1 && exploration.is_none() {
-                results
-                    .into_iter()
-                    .next()
-                    .and_then(|r| r.get("result").cloned())
-                    .context("tools/call response missing result")?
-            } else {
-                serde_json::json!({ "exploration_results": results })
-            };
+            )?;
 
             Ok(McpDriverResult {
                 exec: ExecutionEvidence {
-                    backend: ExecutionBackend::NativeStdio,
+                    backend,
                     stdout: String::new(),
                     stderr: String::new(),
                     exit_code: None,
                     duration_ms: started.elapsed().as_millis(),
+                    termination: TerminationReason::Normal,
                 },
                 transcript,
                 tool_result_payload,
             })
         })();
 
-        if let Some(holder) = child_holder {
-            if let Ok(mut slot) = holder.lock() {
-                if let Some(mut child) = slot.take() {
-                    let _ = child.kill();
-                    let _ = child.wait();
-                }
-            }
-        } else if let Some(mut child) = owned_child.take() {
-            let _ = child.kill();
-            let _ = child.wait();
+        let child = child_holder.lock().ok().and_then(|mut slot| slot.take());
+        let exited = child.and_then(reap);
+        let stderr = stderr.map(StderrCapture::finish).unwrap_or_default();
+        settle(result, exited, stderr)
+    }
+}
+
+impl McpLaunch for NativeStdioMcpDriver {
+    fn launch(&self, child_holder: &Mutex<Option<Child>>) -> Result<Launched> {
+        let mut cmd = Command::new(&self.command);
+        cmd.args(&self.args)
+            .current_dir_opt(self.current_dir.as_ref())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, value) in &self.env {
+            cmd.env(key, value);
         }
 
-        result
+        let spawn = |cmd: &mut Command| match &self.isolation {
+            Some(isolation) => isolation.spawn(cmd),
+            None => cmd.spawn(),
+        };
+        let spawned = match &self.tracer {
+            Some(tracer) => tracer.spawn_with(&mut cmd, spawn),
+            None => spawn(&mut cmd),
+        };
+        let mut child =
+            spawned.with_context(|| format!("failed to spawn MCP server `{}`", self.command))?;
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child
+            .stderr
+            .take()
+            .map(|stderr| StderrCapture::start(stderr, MAX_STDERR_BYTES));
+        if let Ok(mut slot) = child_holder.lock() {
+            *slot = Some(child);
+        }
+
+        Ok(Launched {
+            transport: Box::new(StdioTransport {
+                stdin: stdin.context("failed to open MCP stdin")?,
+                reader: BufReader::new(stdout.context("failed to open MCP stdout")?),
+                framing: self.framing,
+                client: self.client.clone(),
+                root: self
+                    .current_dir
+                    .as_ref()
+                    .map(|dir| format!("file://{}", dir.display())),
+            }),
+            stderr,
+            backend: if self.isolation.is_some() {
+                ExecutionBackend::NativeIsolated
+            } else {
+                ExecutionBackend::NativeStdio
+            },
+            client: self.client.clone(),
+            timeout: self.mcp_timeout,
+        })
     }
 }
@@ -1,7 +1,9 @@
 mod driver;
 mod protocol;
+pub(crate) mod stderr;
 
 pub use driver::NativeStdioMcpDriver;
+pub(crate) use driver::{reap, settle, timed_out};
 pub use protocol::StdioFraming;
 
 #[cfg(test)]
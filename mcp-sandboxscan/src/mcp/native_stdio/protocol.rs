@@ -4,7 +4,9 @@ use std::process::Command;
 use anyhow::{Context, Result, bail};
 use serde_json::Value;
 
-use crate::mcp::transcript::{McpDirection, McpEvent, McpTranscript};
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::transcript::{McpDirection, McpTranscript};
+use crate::mcp::transport::McpTransport;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StdioFraming {
@@ -42,10 +44,13 @@ pub fn send_message(stdin: &mut impl Write, message: &Value, framing: StdioFrami
     Ok(())
 }
 
+/// wait for the response to `expected_id`. requests the server sends in the
+/// meantime go to `on_request`, which must answer them or the server may stall
 pub fn read_response_with_id(
     reader: &mut impl BufRead,
     expected_id: u64,
     framing: StdioFraming,
+    mut on_request: impl FnMut(&Value) -> Result<()>,
 ) -> Result<Value> {
     let mut line = String::new();
     loop {
@@ -54,6 +59,12 @@ pub fn read_response_with_id(
             StdioFraming::ContentLength => read_content_length_json(reader, &mut line)?,
         };
 
+        if value.get("method").is_some() {
+            if value.get("id").is_some() {
+                on_request(&value)?;
+            }
+            continue;
+        }
         if value.get("id").and_then(|id| id.as_u64()) == Some(expected_id) {
             return Ok(value);
         }
@@ -107,13 +118,42 @@ fn read_content_length_json(reader: &mut impl BufRead, line: &mut String) -> Res
     serde_json::from_slice(&body).context("failed to parse MCP Content-Length JSON body")
 }
 
-pub fn record(transcript: &mut McpTranscript, direction: McpDirection, payload: &Value) {
-    transcript.events.push(McpEvent {
-        direction,
-        method: payload
-            .get("method")
-            .and_then(|value| value.as_str())
-            .map(|s| s.to_string()),
-        payload: payload.clone(),
-    });
+/// a server's stdin and stdout, framed one way or the other
+pub struct StdioTransport<W, R> {
+    pub stdin: W,
+    pub reader: R,
+    pub framing: StdioFraming,
+    pub client: ClientCapabilities,
+    // file:// URI handed out for roots/list
+    pub root: Option<String>,
+}
+
+impl<W: Write, R: BufRead> McpTransport for StdioTransport<W, R> {
+    fn request(&mut self, request: Value, transcript: &mut McpTranscript) -> Result<Value> {
+        let id = request["id"].as_u64().context("request without id")?;
+        send_message(&mut self.stdin, &request, self.framing)?;
+        transcript.record(McpDirection::ClientToServer, &request);
+        let Self {
+            stdin,
+            reader,
+            framing,
+            client,
+            root,
+        } = self;
+        let response = read_response_with_id(reader, id, *framing, |server_request| {
+            transcript.record(McpDirection::ServerToClient, server_request);
+            let reply = client.answer(server_request, root.as_deref());
+            send_message(stdin, &reply, *framing)?;
+            transcript.record(McpDirection::ClientToServer, &reply);
+            Ok(())
+        })?;
+        transcript.record(McpDirection::ServerToClient, &response);
+        Ok(response)
+    }
+
+    fn notify(&mut self, notification: Value, transcript: &mut McpTranscript) -> Result<()> {
+        send_message(&mut self.stdin, &notification, self.framing)?;
+        transcript.record(McpDirection::ClientToServer, &notification);
+        Ok(())
+    }
 }
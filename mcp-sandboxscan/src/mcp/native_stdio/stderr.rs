@@ -0,0 +1,57 @@
+use std::io::Read;
+use std::process::ChildStderr;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+// server stderr kept in the evidence; the rest is drained and dropped
+pub(crate) const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+// how long to wait for the tail of stderr once the server is gone; a child it
+// spawned may still hold the pipe open
+const DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+/// drains a server's stderr on a background thread so a chatty server never
+/// blocks on a full pipe
+pub(crate) struct StderrCapture {
+    kept: Arc<Mutex<Vec<u8>>>,
+    dropped: Arc<Mutex<usize>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl StderrCapture {
+    pub(crate) fn start(mut stderr: ChildStderr, limit: usize) -> Self {
+        let kept = Arc::new(Mutex::new(Vec::new()));
+        let dropped = Arc::new(Mutex::new(0));
+        let (tx, done) = mpsc::channel();
+        let (kept_worker, dropped_worker) = (Arc::clone(&kept), Arc::clone(&dropped));
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = stderr.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let mut kept = kept_worker.lock().unwrap();
+                let room = limit.saturating_sub(kept.len()).min(n);
+                kept.extend_from_slice(&buf[..room]);
+                *dropped_worker.lock().unwrap() += n - room;
+            }
+            let _ = tx.send(());
+        });
+        Self {
+            kept,
+            dropped,
+            done,
+        }
+    }
+
+    /// everything captured so far; call once the server has been stopped
+    pub(crate) fn finish(self) -> String {
+        let _ = self.done.recv_timeout(DRAIN_GRACE);
+        let mut out = String::from_utf8_lossy(&self.kept.lock().unwrap()).into_owned();
+        let dropped = *self.dropped.lock().unwrap();
+        if dropped > 0 {
+            out.push_str(&format!("\n[{dropped} more bytes of stderr not kept]\n"));
+        }
+        out
+    }
+}
@@ -0,0 +1,204 @@
+use std::process::Child;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::driver::McpDriverResult;
+use crate::mcp::jsonrpc::{
+    initialize_request, initialized_notification, tools_call_request, tools_list_request,
+};
+use crate::mcp::native_stdio::stderr::StderrCapture;
+use crate::mcp::native_stdio::{reap, settle, timed_out};
+use crate::mcp::transcript::McpTranscript;
+use crate::mcp::transport::McpTransport;
+use crate::sandbox::exec_evidence::{ExecutionBackend, ExecutionEvidence, TerminationReason};
+
+/*
+one server process kept alive across many tool calls. drivers spawn a fresh
+server per scan; a session lets exploration, fuzzing and multi-step sequences
+share one, so state a call leaves behind is visible to the next.
+*/
+
+/// a started server and the transport to reach it
+pub struct Launched {
+    pub(crate) transport: Box<dyn McpTransport + Send>,
+    pub(crate) stderr: Option<StderrCapture>,
+    pub(crate) backend: ExecutionBackend,
+    pub(crate) client: ClientCapabilities,
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// how a driver starts its server; the child goes into `child` as soon as it
+/// exists so a watchdog can kill it
+pub trait McpLaunch {
+    fn launch(&self, child: &Mutex<Option<Child>>) -> Result<Launched>;
+
+    /// wait until the server can be talked to
+    fn ready(&self, _child: &Mutex<Option<Child>>) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct McpSession {
+    transport: Box<dyn McpTransport + Send>,
+    child: Arc<Mutex<Option<Child>>>,
+    stderr: Option<StderrCapture>,
+    backend: ExecutionBackend,
+    // applies to each request on its own, not to the session
+    timeout: Option<Duration>,
+    started: Instant,
+    next_id: u64,
+    pub initialize_response: Value,
+    pub transcript: McpTranscript,
+    // every call so far, shaped like exploration results
+    results: Vec<Value>,
+}
+
+impl McpSession {
+    /// start the server and run initialize
+    pub fn open(driver: &impl McpLaunch) -> Result<Self> {
+        let started = Instant::now();
+        let child: Arc<Mutex<Option<Child>>> = Arc::default();
+        let launched = match driver.launch(&child) {
+            Ok(launched) => launched,
+            Err(err) => {
+                if let Some(child) = child.lock().ok().and_then(|mut slot| slot.take()) {
+                    reap(child);
+                }
+                return Err(err);
+            }
+        };
+
+        let mut session = Self {
+            transport: launched.transport,
+            child,
+            stderr: launched.stderr,
+            backend: launched.backend,
+            timeout: launched.timeout,
+            started,
+            next_id: 1,
+            initialize_response: Value::Null,
+            transcript: McpTranscript { events: Vec::new() },
+            results: Vec::new(),
+        };
+        let capabilities = launched.client.to_json();
+        let opened = driver
+            .ready(&session.child)
+            .and_then(|()| session.request(|id| initialize_request(id, capabilities)))
+            .and_then(|response| {
+                session.initialize_response = response;
+                session
+                    .transport
+                    .notify(initialized_notification(), &mut session.transcript)
+            });
+        if let Err(err) = opened {
+            let (exited, stderr) = session.stop();
+            return match settle(Err(err), exited, stderr) {
+                Err(err) => Err(err),
+                Ok(_) => unreachable!("settle never turns an error into success"),
+            };
+        }
+        Ok(session)
+    }
+
+    pub fn list_tools(&mut self) -> Result<Value> {
+        self.request(tools_list_request)
+    }
+
+    /// call a tool and return its result; an error response comes back as an
+    /// `isError` result so a sequence of calls can carry on past it
+    pub fn call(&mut self, tool_name: &str, arguments: Value) -> Result<Value> {
+        let response = self.request(|id| tools_call_request(id, tool_name, arguments.clone()))?;
+        let result = response.get("result").cloned().unwrap_or_else(|| {
+            json!({
+                "isError": true,
+                "error": response.get("error").cloned().unwrap_or_else(|| {
+                    json!({"message": "tools/call response missing result"})
+                })
+            })
+        });
+        self.results.push(json!({
+            "tool": tool_name,
+            "arguments": arguments,
+            "result": result,
+        }));
+        Ok(result)
+    }
+
+    /// any other request, built from the id it should carry
+    pub fn request(&mut self, build: impl FnOnce(u64) -> Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = build(id);
+
+        let Some(timeout) = self.timeout else {
+            return self.transport.request(request, &mut self.transcript);
+        };
+        // killing the server unblocks the read with an error
+        let (done, watch) = mpsc::channel::<()>();
+        let child = Arc::clone(&self.child);
+        let watchdog = std::thread::spawn(move || {
+            let expired = matches!(
+                watch.recv_timeout(timeout),
+                Err(mpsc::RecvTimeoutError::Timeout)
+            );
+            if expired
+                && let Ok(mut slot) = child.lock()
+                && let Some(child) = slot.as_mut()
+            {
+                let _ = child.kill();
+            }
+            expired
+        });
+        let response = self.transport.request(request, &mut self.transcript);
+        let _ = done.send(());
+        if watchdog.join().unwrap_or(false) {
+            return Err(timed_out(timeout));
+        }
+        response
+    }
+
+    /// end the session and stop the server. the payload holds every call made,
+    /// in the shape `scan_mcp_driver_result` reads exploration results from
+    pub fn close(mut self) -> Result<McpDriverResult> {
+        // tell the server the session is over; one that cannot be told is stopped anyway
+        let _ = self.transport.close();
+        let result = McpDriverResult {
+            exec: ExecutionEvidence {
+                backend: self.backend.clone(),
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: None,
+                duration_ms: self.started.elapsed().as_millis(),
+                termination: TerminationReason::Normal,
+            },
+            transcript: McpTranscript {
+                events: std::mem::take(&mut self.transcript.events),
+            },
+            tool_result_payload: json!({ "exploration_results": std::mem::take(&mut self.results) }),
+        };
+        let (exited, stderr) = self.stop();
+        settle(Ok(result), exited, stderr)
+    }
+
+    fn stop(&mut self) -> (Option<TerminationReason>, String) {
+        let child = self.child.lock().ok().and_then(|mut slot| slot.take());
+        let exited = child.and_then(reap);
+        let stderr = self
+            .stderr
+            .take()
+            .map(StderrCapture::finish)
+            .unwrap_or_default();
+        (exited, stderr)
+    }
+}
+
+// a session dropped without close still stops its server
+impl Drop for McpSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
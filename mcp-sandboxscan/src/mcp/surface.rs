@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value, json};
+
+/*
+the non-tool MCP surface: resources, resource templates and prompts.
+their text goes into the model's context as-is, so after listing them the
+driver reads a bounded number of each.
+*/
+
+#[derive(Debug, Clone)]
+pub struct SurfaceConfig {
+    pub max_resources: usize,
+    pub max_prompts: usize,
+}
+
+impl Default for SurfaceConfig {
+    fn default() -> Self {
+        Self {
+            max_resources: 8,
+            max_prompts: 8,
+        }
+    }
+}
+
+// filled into templated URIs and prompt arguments
+pub(crate) const PLACEHOLDER: &str = "example";
+
+pub fn advertises(initialize_response: &Value, capability: &str) -> bool {
+    initialize_response
+        .pointer(&format!("/result/capabilities/{capability}"))
+        .is_some_and(|cap| !cap.is_null())
+}
+
+/// URIs to read: listed resources first, then expanded templates, `max` in total
+pub fn resource_uris(
+    resources_response: Option<&Value>,
+    templates_response: Option<&Value>,
+    max: usize,
+) -> Vec<String> {
+    let listed = |response: Option<&Value>, list: &str, key: &str| -> Vec<String> {
+        response
+            .and_then(|r| r.pointer(&format!("/result/{list}")))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.get(key)?.as_str().map(str::to_string))
+            .collect()
+    };
+
+    let mut uris = listed(resources_response, "resources", "uri");
+    uris.extend(
+        listed(templates_response, "resourceTemplates", "uriTemplate")
+            .iter()
+            .map(|template| expand_uri_template(template)),
+    );
+    let mut seen = HashSet::new();
+    uris.retain(|uri| seen.insert(uri.clone()));
+    uris.truncate(max);
+    uris
+}
+
+/// `{var}` becomes a placeholder; query-style expansions (`{?q}`, `{&q}`) are dropped
+pub fn expand_uri_template(template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let expr = &rest[start + 1..start + end];
+        if !expr.starts_with(['?', '&']) {
+            out.push_str(PLACEHOLDER);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// (name, arguments) for up to `max` listed prompts, every argument filled in
+pub fn prompt_requests(prompts_response: &Value, max: usize) -> Vec<(String, Value)> {
+    prompts_response
+        .pointer("/result/prompts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|prompt| {
+            let name = prompt.get("name")?.as_str()?.to_string();
+            let arguments: Map<String, Value> = prompt
+                .get("arguments")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|arg| arg.get("name")?.as_str())
+                .map(|arg| (arg.to_string(), json!(PLACEHOLDER)))
+                .collect();
+            Some((name, Value::Object(arguments)))
+        })
+        .take(max)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_resource_reads_and_prompt_gets() {
+        let init =
+            json!({"result": {"capabilities": {"tools": {}, "prompts": {"listChanged": true}}}});
+        assert!(advertises(&init, "prompts"));
+        assert!(!advertises(&init, "resources"));
+
+        let resources =
+            json!({"result": {"resources": [{"uri": "file:///notes.md", "name": "notes"}]}});
+        // the last template expands to a URI already listed, two entries back
+        let templates = json!({"result": {"resourceTemplates": [
+            {"uriTemplate": "repo://{owner}/{repo}/issues{?state}", "name": "issues"},
+            {"uriTemplate": "file:///notes.md", "name": "notes again"}
+        ]}});
+        assert_eq!(
+            resource_uris(Some(&resources), Some(&templates), 8),
+            ["file:///notes.md", "repo://example/example/issues"]
+        );
+        assert_eq!(
+            resource_uris(Some(&resources), Some(&templates), 1).len(),
+            1
+        );
+
+        let prompts = json!({"result": {"prompts": [
+            {"name": "review", "arguments": [{"name": "code", "required": true}]},
+            {"name": "greet"}
+        ]}});
+        assert_eq!(
+            prompt_requests(&prompts, 8),
+            [
+                ("review".to_string(), json!({"code": "example"})),
+                ("greet".to_string(), json!({}))
+            ]
+        );
+    }
+}
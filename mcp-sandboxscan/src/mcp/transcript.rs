@@ -10,6 +10,19 @@ pub struct McpTranscript {
     pub events: Vec<McpEvent>,
 }
 
+impl McpTranscript {
+    pub fn record(&mut self, direction: McpDirection, payload: &Value) {
+        self.events.push(McpEvent {
+            direction,
+            method: payload
+                .get("method")
+                .and_then(|value| value.as_str())
+                .map(|s| s.to_string()),
+            payload: payload.clone(),
+        });
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpEvent {
     pub direction: McpDirection,
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+
+use crate::mcp::client::ClientCapabilities;
+use crate::mcp::driver::McpCallPlan;
+use crate::mcp::explore::{ExplorationConfig, build_exploration_plans};
+use crate::mcp::jsonrpc::{
+    initialize_request, initialized_notification, prompts_get_request, prompts_list_request,
+    resource_templates_list_request, resources_list_request, resources_read_request,
+    tools_call_request, tools_list_request,
+};
+use crate::mcp::surface::{SurfaceConfig, advertises, prompt_requests, resource_uris};
+use crate::mcp::transcript::McpTranscript;
+
+/*
+how a driver moves JSON-RPC messages to and from a server. the session itself
+(initialize, tools/list, resources, prompts, tool calls) is the same whatever
+the wire, so drivers only implement this and hand it to run_session.
+*/
+
+pub trait McpTransport {
+    /// send a request and wait for its response, both recorded. requests the
+    /// server makes of the client in the meantime are answered and recorded too
+    fn request(&mut self, request: Value, transcript: &mut McpTranscript) -> Result<Value>;
+
+    fn notify(&mut self, notification: Value, transcript: &mut McpTranscript) -> Result<()>;
+
+    /// end the session on the server's side, where the wire has a way to
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: McpTransport + ?Sized> McpTransport for Box<T> {
+    fn request(&mut self, request: Value, transcript: &mut McpTranscript) -> Result<Value> {
+        (**self).request(request, transcript)
+    }
+
+    fn notify(&mut self, notification: Value, transcript: &mut McpTranscript) -> Result<()> {
+        (**self).notify(notification, transcript)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        (**self).close()
+    }
+}
+
+fn pick_tool_name(tools_response: &Value, preferred: &str) -> String {
+    let Some(tools) = tools_response
+        .get("result")
+        .and_then(|r| r.get("tools"))
+        .and_then(|t| t.as_array())
+    else {
+        return preferred.to_string();
+    };
+
+    if tools
+        .iter()
+        .any(|t| t.get("name").and_then(|n| n.as_str()) == Some(preferred))
+    {
+        return preferred.to_string();
+    }
+
+    tools
+        .first()
+        .and_then(|t| t.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or(preferred)
+        .to_string()
+}
+
+/// initialize, list tools, read the surface and run the planned calls; returns
+/// the tool result payload
+pub fn run_session(
+    transport: &mut impl McpTransport,
+    client: &ClientCapabilities,
+    plan: &McpCallPlan,
+    exploration: Option<&ExplorationConfig>,
+    surface: Option<&SurfaceConfig>,
+    transcript: &mut McpTranscript,
+) -> Result<Value> {
+    let initialize_response =
+        transport.request(initialize_request(1, client.to_json()), transcript)?;
+    transport.notify(initialized_notification(), transcript)?;
+
+    let tools_response = transport.request(tools_list_request(2), transcript)?;
+
+    let first_call_id = match surface {
+        Some(surface) => explore_surface(transport, transcript, &initialize_response, surface, 3)?,
+        None => 3,
+    };
+
+    let exploration_enabled = matches!(exploration, Some(config) if config.enabled);
+    let plans = match exploration {
+        Some(config) if config.enabled => {
+            let mut plans = build_exploration_plans(&tools_response, config);
+            if plans.is_empty() {
+                plans.push(McpCallPlan {
+                    tool_name: pick_tool_name(&tools_response, &plan.tool_name),
+                    arguments: plan.arguments.clone(),
+                });
+            }
+            plans
+        }
+        _ => vec![McpCallPlan {
+            tool_name: pick_tool_name(&tools_response, &plan.tool_name),
+            arguments: plan.arguments.clone(),
+        }],
+    };
+
+    let mut results = Vec::new();
+    for (idx, selected_plan) in plans.iter().enumerate() {
+        let request_id = first_call_id + idx as u64;
+        let call = tools_call_request(
+            request_id,
+            &selected_plan.tool_name,
+            selected_plan.arguments.clone(),
+        );
+        let call_response = transport.request(call, transcript)?;
+
+        let result = if let Some(result) = call_response.get("result").cloned() {
+            result
+        } else if exploration_enabled {
+            json!({
+                "isError": true,
+                "error": call_response.get("error").cloned().unwrap_or_else(|| {
+                    json!({
+                        "message": "tools/call response missing result"
+                    })
+                })
+            })
+        } else {
+            bail!("tools/call response missing result");
+        };
+        results.push(json!({
+            "tool": selected_plan.tool_name,
+            "arguments": selected_plan.arguments.clone(),
+            "result": result,
+        }));
+    }
+
+    // list again once the tools have been used, for servers that change them
+    // after a first look (scan::rug_pull); a server that cannot answer keeps its results
+    if exploration_enabled {
+        let relist_id = first_call_id + plans.len() as u64;
+        let _ = transport.request(tools_list_request(relist_id), transcript);
+    }
+
+    if results.len() == 1 && exploration.is_none() {
+        results
+            .into_iter()
+            .next()
+            .and_then(|r| r.get("result").cloned())
+            .context("tools/call response missing result")
+    } else {
+        Ok(json!({ "exploration_results": results }))
+    }
+}
+
+/*
+list and read resources and prompts the server advertises. error responses
+are kept in the transcript and yield nothing to read; the returned id is the
+next one free for tools/call
+*/
+fn explore_surface(
+    transport: &mut impl McpTransport,
+    transcript: &mut McpTranscript,
+    initialize_response: &Value,
+    surface: &SurfaceConfig,
+    mut next_id: u64,
+) -> Result<u64> {
+    let mut id = || {
+        next_id += 1;
+        next_id - 1
+    };
+
+    if advertises(initialize_response, "resources") {
+        let resources = transport.request(resources_list_request(id()), transcript)?;
+        let templates = transport.request(resource_templates_list_request(id()), transcript)?;
+        for uri in resource_uris(Some(&resources), Some(&templates), surface.max_resources) {
+            transport.request(resources_read_request(id(), &uri), transcript)?;
+        }
+    }
+
+    if advertises(initialize_response, "prompts") {
+        let prompts = transport.request(prompts_list_request(id()), transcript)?;
+        for (name, arguments) in prompt_requests(&prompts, surface.max_prompts) {
+            transport.request(prompts_get_request(id(), &name, arguments), transcript)?;
+        }
+    }
+
+    Ok(id())
+}
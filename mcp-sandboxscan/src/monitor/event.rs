@@ -1,6 +1,10 @@
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::scan::egress_sink::EgressSink;
+use crate::scan::finding::Finding;
 use crate::scan::prompt_sink::PromptSink;
 use crate::taint::flow::FlowMatch;
 use crate::taint::source::TaintSource;
@@ -25,6 +29,7 @@ pub enum MonitorEventKind {
     NetworkConnectAllowed,
     NetworkConnectDenied,
     ProcessSpawn,
+    SyscallDenied,
     McpInitialize,
     McpToolsList,
     McpToolCall,
@@ -34,6 +39,7 @@ pub enum MonitorEventKind {
     ToolInputObserved,
     SinkObserved,
     FlowDetected,
+    FindingDetected,
 }
 
 pub fn sink_events(sinks: &[PromptSink]) -> Vec<MonitorEvent> {
@@ -48,6 +54,9 @@ pub fn sink_events(sinks: &[PromptSink]) -> Vec<MonitorEvent> {
                     PromptSink::JsonPrompt { .. } => "json-prompt",
                     PromptSink::ToolReturnLeaf { .. } => "tool-return",
                     PromptSink::McpToolResultText { .. } => "mcp-tool-result",
+                    PromptSink::McpResourceText { .. } => "mcp-resource",
+                    PromptSink::McpPromptMessage { .. } => "mcp-prompt",
+                    PromptSink::McpSamplingMessage { .. } => "mcp-sampling",
                 }
                 .to_string(),
             ),
@@ -59,6 +68,21 @@ pub fn sink_events(sinks: &[PromptSink]) -> Vec<MonitorEvent> {
         .collect()
 }
 
+pub fn egress_sink_events(sinks: &[EgressSink]) -> Vec<MonitorEvent> {
+    sinks
+        .iter()
+        .map(|sink| MonitorEvent {
+            kind: MonitorEventKind::SinkObserved,
+            actor: "scanner".to_string(),
+            target: Some("network-egress".to_string()),
+            evidence: json!({
+                "sink": sink,
+                "text_len": sink.as_text().len()
+            }),
+        })
+        .collect()
+}
+
 pub fn flow_events(flows: &[FlowMatch]) -> Vec<MonitorEvent> {
     flows
         .iter()
@@ -71,18 +95,58 @@ pub fn flow_events(flows: &[FlowMatch]) -> Vec<MonitorEvent> {
         .collect()
 }
 
-pub fn source_inventory_events(sources: &[TaintSource]) -> Vec<MonitorEvent> {
+pub fn finding_events(findings: &[Finding]) -> Vec<MonitorEvent> {
+    findings
+        .iter()
+        .map(|finding| MonitorEvent {
+            kind: MonitorEventKind::FindingDetected,
+            actor: "scanner".to_string(),
+            target: Some(match finding {
+                Finding::ToolPoisoning { tool, .. }
+                | Finding::ToolDefinitionChanged { tool, .. } => tool.clone(),
+                Finding::PromptInjection { sink_type, .. } => sink_type.clone(),
+            }),
+            evidence: json!(finding),
+        })
+        .collect()
+}
+
+/// `env_read` is the set of env keys the guest was seen reading; None when the
+/// runtime cannot observe env access and every granted var is assumed read
+pub fn source_inventory_events(
+    sources: &[TaintSource],
+    env_read: Option<&HashSet<String>>,
+) -> Vec<MonitorEvent> {
     sources
         .iter()
         .map(|source| match source {
-            TaintSource::EnvVar { key, value } => MonitorEvent {
-                kind: MonitorEventKind::EnvRead,
-                actor: "scanner".to_string(),
-                target: Some(key.clone()),
-                evidence: json!({
-                    "source_id": source.short_id(),
-                    "value_len": value.len()
-                }),
+            TaintSource::EnvVar { key, value } => match env_read {
+                None => MonitorEvent {
+                    kind: MonitorEventKind::EnvRead,
+                    actor: "scanner".to_string(),
+                    target: Some(key.clone()),
+                    evidence: json!({
+                        "source_id": source.short_id(),
+                        "value_len": value.len()
+                    }),
+                },
+                Some(read) => {
+                    let was_read = read.contains(key);
+                    MonitorEvent {
+                        kind: if was_read {
+                            MonitorEventKind::EnvRead
+                        } else {
+                            MonitorEventKind::CapabilityGranted
+                        },
+                        actor: "scanner".to_string(),
+                        target: Some(key.clone()),
+                        evidence: json!({
+                            "source_id": source.short_id(),
+                            "value_len": value.len(),
+                            "access": if was_read { "read" } else { "granted" }
+                        }),
+                    }
+                }
             },
             TaintSource::FileRead { path, content } => MonitorEvent {
                 kind: MonitorEventKind::FileRead,
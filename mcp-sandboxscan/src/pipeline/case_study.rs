@@ -44,16 +44,10 @@ pub fn default_env_for_subject(
         out.insert("FILE_TO_READ".to_string(), "secret.txt".to_string());
     }
 
-    if !out.contains_key("DEMO_SECRET")
-        && (subject.name.contains("env-leak")
-            || subject.name.contains("env_leak")
-            || subject.name.contains("c2-beacon")
-            || subject.name.contains("c2_beacon"))
-    {
-        out.insert(
-            "DEMO_SECRET".to_string(),
-            "SEKRET_0123456789abcdef".to_string(),
-        );
+    if let Some(expect) = &subject.expect {
+        for (key, value) in &expect.env {
+            out.entry(key.clone()).or_insert_with(|| value.clone());
+        }
     }
 
     out
@@ -1,12 +1,15 @@
 use std::time::Duration;
 
 use crate::mcp::explore::ExplorationConfig;
+use crate::sandbox::limits::{SandboxLimits, SandboxOptions};
 
 #[derive(Debug, Clone)]
 pub struct ScanLimits {
     pub build_timeout: Option<Duration>,
     pub mcp_timeout: Option<Duration>,
     pub exploration: ExplorationConfig,
+    pub sandbox: SandboxLimits,
+    pub sandbox_options: SandboxOptions,
 }
 
 impl ScanLimits {
@@ -15,6 +18,8 @@ impl ScanLimits {
             build_timeout: None,
             mcp_timeout: None,
             exploration: ExplorationConfig::disabled(),
+            sandbox: SandboxLimits::none(),
+            sandbox_options: SandboxOptions::default(),
         }
     }
 
@@ -23,6 +28,8 @@ impl ScanLimits {
             build_timeout: Some(Duration::from_secs(300)),
             mcp_timeout: Some(Duration::from_secs(60)),
             exploration: ExplorationConfig::disabled(),
+            sandbox: SandboxLimits::corpus_defaults(),
+            sandbox_options: SandboxOptions::default(),
         }
     }
 }
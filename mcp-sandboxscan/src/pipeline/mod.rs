@@ -20,7 +20,7 @@ use crate::adapter::python_wasi::PythonWasiAdapter;
 use crate::adapter::rust_wasi::RustWasiAdapter;
 use crate::adapter::typescript_wasi::TypeScriptWasiAdapter;
 use crate::adapter::{AdaptationStatus, Adapter, BuildArtifact};
-use crate::scan::dynamic::{run_dynamic_scan, run_python_dynamic_scan};
+use crate::scan::dynamic::{run_dynamic_scan_with_limits, run_python_dynamic_scan};
 use crate::scan::native_mcp::run_native_mcp_scan;
 use crate::scan::report::ScanReport;
 use crate::subject::{Capability, Language, SubjectManifest};
@@ -89,9 +89,15 @@ pub fn scan_subject_with_limits(
     env: &HashMap<String, String>,
     data_dir: Option<&Path>,
     max_output_bytes: usize,
-    limits: ScanLimits,
+    mut limits: ScanLimits,
 ) -> Result<SubjectScanResult> {
     let total_start = std::time::Instant::now();
+    let options = &mut limits.sandbox_options;
+    options.network = subject.network.merged(&options.network);
+    options
+        .network
+        .validate()
+        .with_context(|| format!("invalid network policy for subject {}", subject.name))?;
     let adapter = select_adapter(subject, &limits)?;
 
     let build_start = std::time::Instant::now();
@@ -116,6 +122,7 @@ pub fn scan_subject_with_limits(
                 data_dir,
                 limits.mcp_timeout,
                 &limits.exploration,
+                &limits.sandbox_options,
             )
             .with_context(|| {
                 format!(
@@ -128,20 +135,29 @@ pub fn scan_subject_with_limits(
             match adaptation.artifact {
                 Some(BuildArtifact::Wasm { wasm_path }) => {
                     let stdin_input = build_ts_wasi_stdin_payload(subject, env, data_dir);
-                    run_dynamic_scan(&wasm_path, data_dir, env, stdin_input, max_output_bytes)
+                    run_dynamic_scan_with_limits(
+                        &wasm_path,
+                        data_dir,
+                        env,
+                        stdin_input,
+                        max_output_bytes,
+                        &limits.sandbox,
+                        &limits.sandbox_options,
+                    )
                 }
                 .with_context(|| format!("failed to scan wasm artifact {}", wasm_path.display()))?,
-                Some(BuildArtifact::PythonWasm {
-                    interpreter_wasm,
-                    work_dir,
-                    argv,
-                }) => run_python_dynamic_scan(
-                    &interpreter_wasm,
-                    &work_dir,
-                    &argv,
+                Some(
+                    ref artifact @ BuildArtifact::PythonWasm {
+                        ref interpreter_wasm,
+                        ..
+                    },
+                ) => run_python_dynamic_scan(
+                    artifact,
                     data_dir,
                     env,
                     max_output_bytes,
+                    &limits.sandbox,
+                    &limits.sandbox_options,
                 )
                 .with_context(|| {
                     format!(
@@ -1,20 +1,135 @@
+use std::fmt;
+use std::process::ExitStatus;
+
 use crate::sandbox::exec_result::WasmExecResult;
+use crate::sandbox::limits::LimitKind;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExecutionEvidence {
     pub backend: ExecutionBackend,
     pub stdout: String,
     pub stderr: String,
     pub exit_code: Option<i32>,
     pub duration_ms: u128,
+    #[serde(default)]
+    pub termination: TerminationReason,
+}
+
+/// why execution stopped, beyond what exit_code can say
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum TerminationReason {
+    // _start / wasi:cli/run returned, or the native session finished
+    #[default]
+    Normal,
+    // WASI proc_exit(n) or a native process exit status
+    Exit {
+        code: i32,
+    },
+    Trap {
+        // wasmtime trap code when the error was a wasm trap, None for host errors
+        code: Option<String>,
+        message: String,
+        backtrace: Option<String>,
+    },
+    Timeout {
+        after_ms: u128,
+    },
+    ResourceLimit {
+        limit: LimitKind,
+        detail: String,
+    },
+    MissingStart,
+    Signal {
+        signal: i32,
+    },
+}
+
+impl TerminationReason {
+    pub fn from_exit_status(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return Self::Signal { signal };
+            }
+        }
+        match status.code() {
+            Some(0) | None => Self::Normal,
+            Some(code) => Self::Exit { code },
+        }
+    }
+
+    /// exit code for reports: only meaningful when the guest exited on its own
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            Self::Normal => Some(0),
+            Self::Exit { code } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// description of an abnormal termination, None for a clean run
+    pub fn failure(&self) -> Option<String> {
+        match self {
+            Self::Normal | Self::Exit { code: 0 } => None,
+            Self::Exit { code } => Some(format!("exit({code})")),
+            Self::Trap { code, message, .. } => Some(match code {
+                Some(code) => format!("trap {code}: {message}"),
+                None => format!("trap: {message}"),
+            }),
+            Self::Timeout { after_ms } => Some(format!("timed out after {after_ms}ms")),
+            Self::ResourceLimit { limit, .. } => Some(format!("sandbox limit {limit:?}")),
+            Self::MissingStart => Some("module has no _start export".to_string()),
+            Self::Signal { signal } => Some(format!("killed by signal {signal}")),
+        }
+    }
+
+    /// coarse bucket used by corpus failure stats
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Normal | Self::Exit { code: 0 } => "ok",
+            Self::Exit { .. } => "exit",
+            Self::Trap { .. } => "trap",
+            Self::Timeout { .. } => "timeout",
+            Self::ResourceLimit { .. } => "resource_limit",
+            Self::MissingStart => "missing_start",
+            Self::Signal { .. } => "signal",
+        }
+    }
+}
+
+/// error for runs that ended before producing a report, carrying how they ended
+#[derive(Debug)]
+pub struct TerminationError {
+    pub termination: TerminationReason,
+    pub message: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl fmt::Display for TerminationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TerminationError {}
+
+/// the termination reason behind an error, wherever it sits in the context chain
+pub fn termination_of(err: &anyhow::Error) -> Option<&TerminationReason> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<TerminationError>())
+        .map(|err| &err.termination)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum ExecutionBackend {
+    #[default]
     Wasm,
     NativeStdio,
+    NativeIsolated,
+    NativeHttp,
 }
 
 impl From<WasmExecResult> for ExecutionEvidence {
@@ -23,8 +138,29 @@ impl From<WasmExecResult> for ExecutionEvidence {
             backend: ExecutionBackend::Wasm,
             stdout: exec.stdout,
             stderr: exec.stderr,
-            exit_code: Some(exec.exit_code),
+            exit_code: exec.termination.exit_code(),
             duration_ms: exec.duration_ms,
+            termination: exec.termination,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_termination_through_context() {
+        let err = anyhow::Error::new(TerminationError {
+            termination: TerminationReason::Timeout { after_ms: 1000 },
+            message: "MCP server timed out after 1s".to_string(),
+        })
+        .context("failed to scan native MCP subject demo");
+
+        assert_eq!(
+            termination_of(&err),
+            Some(&TerminationReason::Timeout { after_ms: 1000 })
+        );
+        assert_eq!(termination_of(&anyhow::anyhow!("plain")), None);
+    }
+}
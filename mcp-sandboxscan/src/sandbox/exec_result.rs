@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use crate::sandbox::exec_evidence::TerminationReason;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WasmExecResult {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
     pub duration_ms: u128,
+    #[serde(default)]
+    pub termination: TerminationReason,
 }
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::ResourceLimiter;
+
+use crate::sandbox::network_policy::NetworkPolicy;
+use wasmtime_wasi::{WasiCtxView, WasiView};
+
+/// per-execution caps on a wasm guest; `None` leaves that dimension unbounded
+#[derive(Debug, Clone, Default)]
+pub struct SandboxLimits {
+    // wasmtime fuel, roughly one unit per wasm instruction
+    pub fuel: Option<u64>,
+    pub wall_clock: Option<Duration>,
+    pub max_memory_bytes: Option<usize>,
+    pub max_tables: Option<usize>,
+    pub max_instances: Option<usize>,
+}
+
+impl SandboxLimits {
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn corpus_defaults() -> Self {
+        Self {
+            // CPython start-up alone burns a few billion units
+            fuel: Some(50_000_000_000),
+            wall_clock: Some(Duration::from_secs(120)),
+            max_memory_bytes: Some(1 << 30),
+            max_tables: Some(1_000),
+            max_instances: Some(1_000),
+        }
+    }
+}
+
+/// what a scan lets the server reach and how it watches it, passed next to the limits
+#[derive(Debug, Clone, Default)]
+pub struct SandboxOptions {
+    // outbound connections; deny-all unless the subject or CLI allows more
+    pub network: NetworkPolicy,
+    // native servers only: namespaces + seccomp, see native_isolation
+    pub isolate_native: bool,
+    // native servers only: ptrace their spawns, sensitive opens and connects
+    pub trace_native: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LimitKind {
+    Fuel,
+    Memory,
+    Tables,
+    Instances,
+}
+
+/// ResourceLimiter over SandboxLimits that remembers which limit stopped the guest
+pub struct GuestLimiter {
+    max_memory_bytes: Option<usize>,
+    max_tables: usize,
+    max_instances: usize,
+    hit: Option<LimitKind>,
+}
+
+impl GuestLimiter {
+    pub fn new(limits: &SandboxLimits) -> Self {
+        Self {
+            max_memory_bytes: limits.max_memory_bytes,
+            // wasmtime's own defaults when unset
+            max_tables: limits.max_tables.unwrap_or(10_000),
+            max_instances: limits.max_instances.unwrap_or(10_000),
+            hit: None,
+        }
+    }
+
+    pub fn hit(&self) -> Option<LimitKind> {
+        self.hit
+    }
+}
+
+impl ResourceLimiter for GuestLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if let Some(limit) = self.max_memory_bytes
+            && desired > limit
+        {
+            // trap rather than fail the grow, so the guest cannot limp on
+            self.hit = Some(LimitKind::Memory);
+            return Err(wasmtime::Error::msg(format!(
+                "sandbox memory limit exceeded: {desired} > {limit} bytes"
+            )));
+        }
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+
+    fn instances(&self) -> usize {
+        self.max_instances
+    }
+
+    fn tables(&self) -> usize {
+        self.max_tables
+    }
+
+    fn memories(&self) -> usize {
+        10_000
+    }
+}
+
+/// store data for a guest: the runtime's WASI context plus its limiter
+pub struct GuestState<C> {
+    pub ctx: C,
+    pub limiter: GuestLimiter,
+}
+
+impl<C: WasiView> WasiView for GuestState<C> {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        self.ctx.ctx()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_limit_traps_and_is_remembered() {
+        let mut limiter = GuestLimiter::new(&SandboxLimits {
+            max_memory_bytes: Some(1 << 20),
+            ..SandboxLimits::none()
+        });
+        assert!(limiter.memory_growing(0, 1 << 16, None).unwrap());
+        assert!(limiter.memory_growing(1 << 16, 2 << 20, None).is_err());
+        assert_eq!(limiter.hit(), Some(LimitKind::Memory));
+    }
+}
@@ -1,5 +1,9 @@
 pub mod exec_evidence;
 pub mod exec_result;
+pub mod limits;
+pub mod native_isolation;
+pub mod native_trace;
+pub mod network_policy;
 pub mod wasi;
 pub mod wasi_hooks;
 pub mod wasm_runner;
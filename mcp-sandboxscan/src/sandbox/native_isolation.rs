@@ -0,0 +1,782 @@
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+
+/*
+opt-in isolation for native MCP servers (Linux only):
+  - user, mount, net and pid namespaces; the server runs as pid 1 of its own pid
+    namespace and dies with the scanner
+  - mounts are made private and `read_only` paths (the source dir) re-bound read-only
+  - the net namespace only has loopback. listeners for `relays` are bound inside it
+    before exec and handed back to the scanner, which forwards them to the host,
+    so HTTP_PROXY and the DNS stub keep working unchanged
+  - a seccomp filter passes blocked syscalls to a supervisor thread that records a
+    SyscallDenied event and fails the call with EPERM
+none of this needs root: the child is root only inside its own user namespace.
+*/
+#[derive(Debug, Clone)]
+pub struct NativeIsolation {
+    pub read_only: Vec<PathBuf>,
+    // bound over /etc/resolv.conf inside the mount namespace
+    pub resolv_conf: Option<PathBuf>,
+    pub relays: Vec<LoopbackRelay>,
+    pub monitor: Arc<SyscallMonitor>,
+}
+
+/// host loopback service the isolated server may still reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopbackRelay {
+    // same address inside and on the host
+    Tcp(SocketAddr),
+    // `inner` in the namespace forwarded to `outer` on the host, e.g. port 53 for
+    // resolv.conf inside while the host stub sits on any free port
+    Udp {
+        inner: SocketAddr,
+        outer: SocketAddr,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyscallDenial {
+    pub syscall: String,
+    pub nr: i32,
+    pub pid: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct SyscallMonitor {
+    denials: Mutex<Vec<SyscallDenial>>,
+}
+
+impl SyscallMonitor {
+    pub fn record(&self, denial: SyscallDenial) {
+        self.denials.lock().unwrap().push(denial);
+    }
+
+    pub fn denials(&self) -> Vec<SyscallDenial> {
+        self.denials.lock().unwrap().clone()
+    }
+
+    pub fn as_monitor_events(&self) -> Vec<MonitorEvent> {
+        self.denials()
+            .into_iter()
+            .map(|denial| MonitorEvent {
+                kind: MonitorEventKind::SyscallDenied,
+                actor: "seccomp".to_string(),
+                target: Some(denial.syscall.clone()),
+                evidence: json!(denial),
+            })
+            .collect()
+    }
+}
+
+impl NativeIsolation {
+    pub fn new(monitor: Arc<SyscallMonitor>) -> Self {
+        Self {
+            read_only: Vec::new(),
+            resolv_conf: None,
+            relays: Vec::new(),
+            monitor,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn spawn(&self, cmd: &mut Command) -> io::Result<Child> {
+        linux::spawn(self, cmd)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn spawn(&self, _cmd: &mut Command) -> io::Result<Child> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "native isolation needs Linux namespaces and seccomp",
+        ))
+    }
+}
+
+fn relay_tcp(listener: TcpListener, upstream: SocketAddr) {
+    for inner in listener.incoming().flatten() {
+        thread::spawn(move || {
+            if let Ok(outer) = TcpStream::connect(upstream) {
+                let _ = splice(inner, outer);
+            }
+        });
+    }
+}
+
+fn splice(a: TcpStream, b: TcpStream) -> io::Result<()> {
+    let (mut a_read, mut b_write) = (a.try_clone()?, b.try_clone()?);
+    let forward = thread::spawn(move || {
+        let _ = io::copy(&mut a_read, &mut b_write);
+        let _ = b_write.shutdown(std::net::Shutdown::Write);
+    });
+    let (mut b_read, mut a_write) = (b, a);
+    let _ = io::copy(&mut b_read, &mut a_write);
+    let _ = a_write.shutdown(std::net::Shutdown::Write);
+    let _ = forward.join();
+    Ok(())
+}
+
+// one query, one answer: enough for DNS
+fn relay_udp(inner: UdpSocket, upstream: SocketAddr) {
+    let mut buf = [0u8; 4096];
+    while let Ok((n, peer)) = inner.recv_from(&mut buf) {
+        let Ok(outer) = UdpSocket::bind("127.0.0.1:0") else {
+            continue;
+        };
+        let _ = outer.set_read_timeout(Some(Duration::from_secs(5)));
+        if outer.send_to(&buf[..n], upstream).is_err() {
+            continue;
+        }
+        let mut answer = [0u8; 4096];
+        if let Ok(len) = outer.recv(&mut answer) {
+            let _ = inner.send_to(&answer[..len], peer);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod linux {
+    use std::ffi::CString;
+    use std::io;
+    use std::mem;
+    use std::net::{SocketAddr, TcpListener, UdpSocket};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixStream;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Child, Command};
+    use std::sync::Arc;
+    use std::thread;
+
+    use libc::{c_int, c_uint, sock_filter};
+
+    use super::{LoopbackRelay, NativeIsolation, SyscallDenial, SyscallMonitor};
+
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    pub(crate) const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+    macro_rules! syscalls {
+        ($($name:ident),* $(,)?) => {
+            &[$((libc::$name as i64, stringify!($name)),)*]
+        };
+    }
+
+    // escape hatches out of the namespaces plus kernel-wide state changes
+    #[cfg(target_arch = "x86_64")]
+    const DENIED: &[(i64, &str)] = syscalls![
+        SYS_mount,
+        SYS_umount2,
+        SYS_pivot_root,
+        SYS_chroot,
+        SYS_unshare,
+        SYS_setns,
+        SYS_ptrace,
+        SYS_process_vm_readv,
+        SYS_process_vm_writev,
+        SYS_kexec_load,
+        SYS_kexec_file_load,
+        SYS_init_module,
+        SYS_finit_module,
+        SYS_delete_module,
+        SYS_bpf,
+        SYS_perf_event_open,
+        SYS_userfaultfd,
+        SYS_keyctl,
+        SYS_add_key,
+        SYS_request_key,
+        SYS_open_by_handle_at,
+        SYS_reboot,
+        SYS_swapon,
+        SYS_swapoff,
+        SYS_acct,
+        SYS_settimeofday,
+        SYS_clock_settime,
+        SYS_iopl,
+        SYS_ioperm,
+    ];
+    #[cfg(target_arch = "aarch64")]
+    const DENIED: &[(i64, &str)] = syscalls![
+        SYS_mount,
+        SYS_umount2,
+        SYS_pivot_root,
+        SYS_chroot,
+        SYS_unshare,
+        SYS_setns,
+        SYS_ptrace,
+        SYS_process_vm_readv,
+        SYS_process_vm_writev,
+        SYS_kexec_load,
+        SYS_kexec_file_load,
+        SYS_init_module,
+        SYS_finit_module,
+        SYS_delete_module,
+        SYS_bpf,
+        SYS_perf_event_open,
+        SYS_userfaultfd,
+        SYS_keyctl,
+        SYS_add_key,
+        SYS_request_key,
+        SYS_open_by_handle_at,
+        SYS_reboot,
+        SYS_swapon,
+        SYS_swapoff,
+        SYS_acct,
+        SYS_settimeofday,
+        SYS_clock_settime,
+    ];
+
+    pub fn syscall_name(nr: i32) -> String {
+        DENIED
+            .iter()
+            .find(|(denied, _)| *denied == nr as i64)
+            .map(|(_, name)| name.trim_start_matches("SYS_").to_string())
+            .unwrap_or_else(|| format!("syscall_{nr}"))
+    }
+
+    pub(crate) fn stmt(code: u32, k: u32) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    pub(crate) fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    pub fn seccomp_filter() -> Vec<sock_filter> {
+        use libc::{BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+        // struct seccomp_data { int nr; __u32 arch; ... }
+        let mut filter = vec![
+            stmt(BPF_LD | BPF_W | BPF_ABS, 4),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD | BPF_W | BPF_ABS, 0),
+            // x32 ABI numbers alias the denied ones
+            jump(BPF_JMP | BPF_JGE | BPF_K, 0x4000_0000, 0, 1),
+            stmt(
+                BPF_RET | BPF_K,
+                libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+            ),
+        ];
+        for (nr, _) in DENIED {
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, *nr as u32, 0, 1));
+            filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_USER_NOTIF));
+        }
+        filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+        filter
+    }
+
+    fn cstring(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(io::Error::other)
+    }
+
+    pub(crate) fn check(ret: c_int) -> io::Result<c_int> {
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(ret)
+        }
+    }
+
+    // everything below `spawn` up to the exec runs in the forked child: no
+    // allocation, no locks, only raw syscalls on data prepared by the parent
+
+    unsafe fn write_file(path: &CString, data: &[u8]) -> io::Result<()> {
+        unsafe {
+            let fd = check(libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC))?;
+            let written = libc::write(fd, data.as_ptr().cast(), data.len());
+            libc::close(fd);
+            if written < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn bind_read_only(path: &CString) -> io::Result<()> {
+        unsafe {
+            check(libc::mount(
+                path.as_ptr(),
+                path.as_ptr(),
+                std::ptr::null(),
+                libc::MS_BIND | libc::MS_REC,
+                std::ptr::null(),
+            ))?;
+            // a remount has to keep the flags the kernel locked on the original mount
+            let mut stat: libc::statvfs = mem::zeroed();
+            check(libc::statvfs(path.as_ptr(), &mut stat))?;
+            let mut flags = libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY;
+            for (st, ms) in [
+                (libc::ST_NOSUID, libc::MS_NOSUID),
+                (libc::ST_NODEV, libc::MS_NODEV),
+                (libc::ST_NOEXEC, libc::MS_NOEXEC),
+                (libc::ST_NOATIME, libc::MS_NOATIME),
+                (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+                (libc::ST_RELATIME, libc::MS_RELATIME),
+            ] {
+                if stat.f_flag & st != 0 {
+                    flags |= ms;
+                }
+            }
+            check(libc::mount(
+                std::ptr::null(),
+                path.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    unsafe fn loopback_up() -> io::Result<()> {
+        unsafe {
+            let sock = check(libc::socket(
+                libc::AF_INET,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                0,
+            ))?;
+            let mut req: libc::ifreq = mem::zeroed();
+            for (dst, src) in req.ifr_name.iter_mut().zip(b"lo") {
+                *dst = *src as libc::c_char;
+            }
+            let result =
+                check(libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut req)).and_then(|_| {
+                    req.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+                    check(libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &req))
+                });
+            libc::close(sock);
+            result.map(|_| ())
+        }
+    }
+
+    unsafe fn bind_relay(relay: LoopbackRelay) -> io::Result<RawFd> {
+        let (addr, kind) = match relay {
+            LoopbackRelay::Tcp(addr) => (addr, libc::SOCK_STREAM),
+            LoopbackRelay::Udp { inner, .. } => (inner, libc::SOCK_DGRAM),
+        };
+        let SocketAddr::V4(addr) = addr else {
+            return Err(io::Error::from_raw_os_error(libc::EAFNOSUPPORT));
+        };
+        unsafe {
+            let fd = check(libc::socket(libc::AF_INET, kind | libc::SOCK_CLOEXEC, 0))?;
+            let mut sin: libc::sockaddr_in = mem::zeroed();
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+            check(libc::bind(
+                fd,
+                (&sin as *const libc::sockaddr_in).cast(),
+                mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            ))?;
+            if kind == libc::SOCK_STREAM {
+                check(libc::listen(fd, 128))?;
+            }
+            Ok(fd)
+        }
+    }
+
+    // one marker byte plus up to 8 descriptors
+    const MAX_FDS: usize = 8;
+
+    unsafe fn send_fds(sock: RawFd, fds: &[RawFd]) -> io::Result<()> {
+        unsafe {
+            let mut byte = [1u8];
+            let mut iov = libc::iovec {
+                iov_base: byte.as_mut_ptr().cast(),
+                iov_len: 1,
+            };
+            let mut control = [0u64; 8];
+            let mut msg: libc::msghdr = mem::zeroed();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            if !fds.is_empty() {
+                let len = mem::size_of_val(fds) as c_uint;
+                msg.msg_control = control.as_mut_ptr().cast();
+                msg.msg_controllen = libc::CMSG_SPACE(len) as _;
+                let cmsg = libc::CMSG_FIRSTHDR(&msg);
+                (*cmsg).cmsg_level = libc::SOL_SOCKET;
+                (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+                (*cmsg).cmsg_len = libc::CMSG_LEN(len) as _;
+                std::ptr::copy_nonoverlapping(
+                    fds.as_ptr().cast::<u8>(),
+                    libc::CMSG_DATA(cmsg),
+                    len as usize,
+                );
+            }
+            if libc::sendmsg(sock, &msg, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    fn recv_fds(sock: &UnixStream) -> io::Result<Vec<OwnedFd>> {
+        let mut byte = [0u8];
+        let mut iov = libc::iovec {
+            iov_base: byte.as_mut_ptr().cast(),
+            iov_len: 1,
+        };
+        let mut control = [0u64; 8];
+        // SAFETY: msghdr points at live stack buffers; received fds are owned once wrapped
+        unsafe {
+            let mut msg: libc::msghdr = mem::zeroed();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr().cast();
+            msg.msg_controllen = mem::size_of_val(&control) as _;
+            let n = libc::recvmsg(sock.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC);
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let mut fds = Vec::new();
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data_len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                    let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                    for idx in 0..data_len / mem::size_of::<RawFd>() {
+                        fds.push(OwnedFd::from_raw_fd(data.add(idx).read_unaligned()));
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+            Ok(fds)
+        }
+    }
+
+    /// wait for the pid-1 server and leave with its status
+    unsafe fn mirror_exit(pid: libc::pid_t) -> ! {
+        unsafe {
+            libc::close_range(0, c_uint::MAX, 0);
+            let mut status = 0;
+            while libc::waitpid(pid, &mut status, 0) < 0
+                && io::Error::last_os_error().raw_os_error() == Some(libc::EINTR)
+            {}
+            exit_like(status)
+        }
+    }
+
+    /// leave the way a child with wait status `status` did
+    pub(crate) unsafe fn exit_like(status: c_int) -> ! {
+        unsafe {
+            if libc::WIFSIGNALED(status) {
+                let signal = libc::WTERMSIG(status);
+                libc::signal(signal, libc::SIG_DFL);
+                libc::kill(libc::getpid(), signal);
+                libc::_exit(128 + signal);
+            }
+            libc::_exit(libc::WEXITSTATUS(status))
+        }
+    }
+
+    unsafe fn install_seccomp(filter: &[sock_filter], sock: RawFd) -> io::Result<()> {
+        unsafe {
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+            let prog = libc::sock_fprog {
+                len: filter.len() as u16,
+                filter: filter.as_ptr() as *mut sock_filter,
+            };
+            let listener = libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                &prog as *const libc::sock_fprog,
+            );
+            if listener < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let listener = listener as RawFd;
+            let sent = send_fds(sock, &[listener]);
+            libc::close(listener);
+            sent
+        }
+    }
+
+    pub fn spawn(isolation: &NativeIsolation, cmd: &mut Command) -> io::Result<Child> {
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        let setgroups = cstring(b"/proc/self/setgroups")?;
+        let uid_map_path = cstring(b"/proc/self/uid_map")?;
+        let gid_map_path = cstring(b"/proc/self/gid_map")?;
+        let uid_map = format!("{uid} {uid} 1\n");
+        let gid_map = format!("{gid} {gid} 1\n");
+        let root = cstring(b"/")?;
+        let proc_fs = cstring(b"proc")?;
+        let proc_dir = cstring(b"/proc")?;
+        let resolv_target = cstring(b"/etc/resolv.conf")?;
+        let read_only = isolation
+            .read_only
+            .iter()
+            .map(|path| cstring(path.as_os_str().as_bytes()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let resolv_conf = isolation
+            .resolv_conf
+            .as_ref()
+            .map(|path| cstring(path.as_os_str().as_bytes()))
+            .transpose()?;
+        let relays = isolation.relays.clone();
+        if relays.len() > MAX_FDS {
+            return Err(io::Error::other("too many loopback relays"));
+        }
+        let filter = seccomp_filter();
+
+        let (parent_sock, child_sock) = UnixStream::pair()?;
+        let child_fd = child_sock.as_raw_fd();
+
+        // SAFETY: the closure only makes async-signal-safe calls on data captured above
+        unsafe {
+            cmd.pre_exec(move || {
+                check(libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL, 0, 0, 0))?;
+                check(libc::unshare(
+                    libc::CLONE_NEWUSER
+                        | libc::CLONE_NEWNS
+                        | libc::CLONE_NEWNET
+                        | libc::CLONE_NEWPID,
+                ))?;
+                write_file(&setgroups, b"deny")?;
+                write_file(&uid_map_path, uid_map.as_bytes())?;
+                write_file(&gid_map_path, gid_map.as_bytes())?;
+
+                check(libc::mount(
+                    std::ptr::null(),
+                    root.as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ))?;
+                for path in &read_only {
+                    bind_read_only(path)?;
+                }
+                if let Some(resolv_conf) = &resolv_conf {
+                    check(libc::mount(
+                        resolv_conf.as_ptr(),
+                        resolv_target.as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_BIND,
+                        std::ptr::null(),
+                    ))?;
+                }
+
+                loopback_up()?;
+                let mut fds = [0 as RawFd; MAX_FDS];
+                for (slot, relay) in fds.iter_mut().zip(&relays) {
+                    *slot = bind_relay(*relay)?;
+                }
+                send_fds(child_fd, &fds[..relays.len()])?;
+                for fd in &fds[..relays.len()] {
+                    libc::close(*fd);
+                }
+
+                // only children enter the new pid namespace
+                match check(libc::fork())? {
+                    0 => {}
+                    pid => mirror_exit(pid),
+                }
+                check(libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL, 0, 0, 0))?;
+                // best effort: a container may hide parts of /proc and forbid a fresh mount
+                libc::mount(
+                    proc_fs.as_ptr(),
+                    proc_dir.as_ptr(),
+                    proc_fs.as_ptr(),
+                    libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                    std::ptr::null(),
+                );
+                install_seccomp(&filter, child_fd)
+            });
+        }
+
+        let monitor = Arc::clone(&isolation.monitor);
+        let relays = isolation.relays.clone();
+        thread::spawn(move || supervise(parent_sock, relays, monitor));
+
+        let child = cmd.spawn();
+        drop(child_sock);
+        child
+    }
+
+    fn supervise(sock: UnixStream, relays: Vec<LoopbackRelay>, monitor: Arc<SyscallMonitor>) {
+        let Ok(fds) = recv_fds(&sock) else {
+            return;
+        };
+        for (fd, relay) in fds.into_iter().zip(relays) {
+            match relay {
+                LoopbackRelay::Tcp(upstream) => {
+                    let listener = TcpListener::from(fd);
+                    thread::spawn(move || super::relay_tcp(listener, upstream));
+                }
+                LoopbackRelay::Udp { outer, .. } => {
+                    let socket = UdpSocket::from(fd);
+                    thread::spawn(move || super::relay_udp(socket, outer));
+                }
+            }
+        }
+
+        let Some(listener) = recv_fds(&sock).ok().and_then(|fds| fds.into_iter().next()) else {
+            return;
+        };
+        while let Some(denial) = next_denial(&listener) {
+            if let Some(denial) = denial {
+                monitor.record(denial);
+            }
+        }
+    }
+
+    /// Some(None) for a notification that went away before it could be answered,
+    /// None once no process is left behind the filter
+    fn next_denial(listener: &OwnedFd) -> Option<Option<SyscallDenial>> {
+        let fd = listener.as_raw_fd();
+        // SAFETY: plain poll/ioctl on an fd we own, with zeroed out-structs
+        unsafe {
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if libc::poll(&mut pollfd, 1, -1) < 0 {
+                return match io::Error::last_os_error().raw_os_error() {
+                    Some(libc::EINTR) => Some(None),
+                    _ => None,
+                };
+            }
+            if pollfd.revents & libc::POLLIN == 0 {
+                return None;
+            }
+            let mut req: libc::seccomp_notif = mem::zeroed();
+            if libc::ioctl(fd, libc::SECCOMP_IOCTL_NOTIF_RECV as _, &mut req) < 0 {
+                return Some(None);
+            }
+            let resp = libc::seccomp_notif_resp {
+                id: req.id,
+                val: 0,
+                error: -libc::EPERM,
+                flags: 0,
+            };
+            libc::ioctl(fd, libc::SECCOMP_IOCTL_NOTIF_SEND as _, &resp);
+            Some(Some(SyscallDenial {
+                syscall: syscall_name(req.data.nr),
+                nr: req.data.nr,
+                pid: req.pid,
+            }))
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::io::{Read, Write};
+    use std::process::Stdio;
+
+    use super::*;
+
+    #[test]
+    fn isolates_and_reports_denied_syscalls() {
+        let monitor = Arc::new(SyscallMonitor::default());
+        let dir =
+            std::env::temp_dir().join(format!("mcp-sandboxscan-isolation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut isolation = NativeIsolation::new(Arc::clone(&monitor));
+        isolation.read_only.push(dir.clone());
+        let host = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host_addr = host.local_addr().unwrap();
+        isolation.relays.push(LoopbackRelay::Tcp(host_addr));
+        thread::spawn(move || {
+            let (mut conn, _) = host.accept().unwrap();
+            conn.write_all(b"relayed").unwrap();
+        });
+
+        // pid 1 in its own namespace, only loopback (relayed to the host listener),
+        // read-only source dir, and `unshare` (denied by seccomp) fails
+        let script = format!(
+            "echo $$; grep -c : /proc/net/dev; \
+             python3 -c 'import socket; print(socket.create_connection((\"127.0.0.1\", {port})).recv(16).decode())'; \
+             touch {dir}/x 2>/dev/null || echo ro; unshare -U true 2>/dev/null || echo denied",
+            port = host_addr.port(),
+            dir = dir.display()
+        );
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &script]).stdout(Stdio::piped());
+        let mut child = match isolation.spawn(&mut cmd) {
+            Ok(child) => child,
+            // hosts without unprivileged user namespaces cannot run this
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+            Err(err) => panic!("spawn isolated: {err}"),
+        };
+        let mut out = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        child.wait().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, ["1", "1", "relayed", "ro", "denied"]);
+        let denials = monitor.denials();
+        assert!(denials.iter().any(|d| d.syscall == "unshare"));
+        assert_eq!(
+            monitor.as_monitor_events()[0].kind,
+            MonitorEventKind::SyscallDenied
+        );
+    }
+
+    #[test]
+    fn relays_udp_from_port_53_inside_to_any_host_port() {
+        let host = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let host_addr = host.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; 64];
+            let (n, peer) = host.recv_from(&mut buf).unwrap();
+            host.send_to(&buf[..n], peer).unwrap();
+        });
+        let mut isolation = NativeIsolation::new(Arc::new(SyscallMonitor::default()));
+        isolation.relays.push(LoopbackRelay::Udp {
+            inner: SocketAddr::from(([127, 53, 0, 1], 53)),
+            outer: host_addr,
+        });
+
+        let script = "import socket; s = socket.socket(socket.AF_INET, socket.SOCK_DGRAM); \
+                      s.sendto(b'query', ('127.53.0.1', 53)); print(s.recv(64).decode())";
+        let mut cmd = Command::new("python3");
+        cmd.args(["-c", script]).stdout(Stdio::piped());
+        let mut child = match isolation.spawn(&mut cmd) {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+            Err(err) => panic!("spawn isolated: {err}"),
+        };
+        let mut out = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        child.wait().unwrap();
+        assert_eq!(out, "query\n");
+    }
+}
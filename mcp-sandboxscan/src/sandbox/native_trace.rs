@@ -0,0 +1,704 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::process::{Child, Command};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+
+/*
+ptrace-based behaviour tracing for native MCP servers (Linux only).
+the spawned process forks once more before exec: the outer half becomes the
+tracer, the inner half the server. a seccomp filter stops the server and every
+process it forks only on execve/execveat, open/openat/openat2/creat and connect;
+the tracer copies the arguments onto a pipe and exits with the server's status,
+so the driver cannot tell the difference. arguments are read at syscall entry:
+these are attempts, whether or not the kernel then lets them succeed.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "syscall", rename_all = "kebab-case")]
+pub enum TracedSyscall {
+    Exec {
+        pid: u32,
+        path: String,
+        argv: Vec<String>,
+    },
+    Open {
+        pid: u32,
+        path: String,
+        write: bool,
+    },
+    Connect {
+        pid: u32,
+        addr: SocketAddr,
+    },
+}
+
+// credential stores and secrets a server has no business opening
+const SENSITIVE_FILES: &[&str] = &[
+    "/etc/shadow",
+    "/etc/gshadow",
+    "/etc/sudoers",
+    "/.netrc",
+    "/.git-credentials",
+    "/.npmrc",
+    "/.pypirc",
+    "/.bash_history",
+    "/.env",
+    "/.kube/config",
+    "/.docker/config.json",
+];
+const SENSITIVE_DIRS: &[&str] = &["/.ssh/", "/.aws/", "/.gnupg/", "/.config/gcloud/"];
+
+pub fn is_sensitive_path(path: &str) -> bool {
+    // /proc/self/environ, /proc/<pid>/environ, /proc/<pid>/task/<tid>/environ
+    if path.starts_with("/proc/") && path.ends_with("/environ") {
+        return true;
+    }
+    SENSITIVE_FILES.iter().any(|file| path.ends_with(file))
+        || SENSITIVE_DIRS.iter().any(|dir| path.contains(dir))
+}
+
+#[derive(Default)]
+pub struct SyscallTracer {
+    // more paths worth reporting, e.g. the data dir and canary files
+    watch: Vec<String>,
+    // execs that happen before the server's own, by a wrapper that then execs it
+    launcher_execs: usize,
+    syscalls: Mutex<Vec<TracedSyscall>>,
+    readers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl SyscallTracer {
+    pub fn new(watch: Vec<String>) -> Self {
+        Self {
+            watch,
+            ..Self::default()
+        }
+    }
+
+    pub fn skip_launcher_execs(mut self, execs: usize) -> Self {
+        self.launcher_execs = execs;
+        self
+    }
+
+    /// spawn `cmd` traced; `spawn` does the actual spawning, so other pre-exec
+    /// layers (isolation) compose underneath the tracer
+    #[cfg(target_os = "linux")]
+    pub fn spawn_with(
+        self: &std::sync::Arc<Self>,
+        cmd: &mut Command,
+        spawn: impl FnOnce(&mut Command) -> io::Result<Child>,
+    ) -> io::Result<Child> {
+        let (records, writer) = linux::attach(cmd)?;
+        let child = spawn(cmd);
+        drop(writer);
+        let tracer = std::sync::Arc::clone(self);
+        let reader = std::thread::spawn(move || tracer.read_records(records));
+        self.readers.lock().unwrap().push(reader);
+        child
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn spawn_with(
+        self: &std::sync::Arc<Self>,
+        _cmd: &mut Command,
+        _spawn: impl FnOnce(&mut Command) -> io::Result<Child>,
+    ) -> io::Result<Child> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "syscall tracing needs Linux ptrace and seccomp",
+        ))
+    }
+
+    /// wait until every traced server is gone and its records are in
+    pub fn finish(&self) {
+        let readers = std::mem::take(&mut *self.readers.lock().unwrap());
+        for reader in readers {
+            let _ = reader.join();
+        }
+    }
+
+    pub fn syscalls(&self) -> Vec<TracedSyscall> {
+        self.syscalls.lock().unwrap().clone()
+    }
+
+    pub fn as_monitor_events(&self) -> Vec<MonitorEvent> {
+        self.syscalls()
+            .into_iter()
+            .map(|syscall| {
+                let (kind, target) = match &syscall {
+                    TracedSyscall::Exec { path, .. } => {
+                        (MonitorEventKind::ProcessSpawn, path.clone())
+                    }
+                    TracedSyscall::Open { path, write, .. } => (
+                        if *write {
+                            MonitorEventKind::FileWrite
+                        } else {
+                            MonitorEventKind::FileRead
+                        },
+                        path.clone(),
+                    ),
+                    TracedSyscall::Connect { addr, .. } => {
+                        (MonitorEventKind::NetworkConnectAttempt, addr.to_string())
+                    }
+                };
+                MonitorEvent {
+                    kind,
+                    actor: "ptrace".to_string(),
+                    target: Some(target),
+                    evidence: json!(syscall),
+                }
+            })
+            .collect()
+    }
+
+    fn read_records(&self, pipe: impl Read) {
+        let mut pipe = BufReader::new(pipe);
+        // execve attempts by pid; only the ones that went through are spawns
+        // (execvp tries every PATH entry); the first of those are the launcher and
+        // the server itself
+        let mut attempts: HashMap<u32, TracedSyscall> = HashMap::new();
+        let mut to_skip = self.launcher_execs + 1;
+        let mut len = [0u8; 2];
+        while pipe.read_exact(&mut len).is_ok() {
+            let mut record = vec![0u8; u16::from_le_bytes(len) as usize];
+            if pipe.read_exact(&mut record).is_err() {
+                break;
+            }
+            if record.first() == Some(&EXECED) {
+                let Some(pid) = record.get(1..5).and_then(|pid| pid.try_into().ok()) else {
+                    continue;
+                };
+                let exec = attempts.remove(&u32::from_le_bytes(pid));
+                if to_skip > 0 {
+                    to_skip -= 1;
+                } else if let Some(exec) = exec {
+                    self.push(exec);
+                }
+                continue;
+            }
+            match parse_record(&record) {
+                Some(exec @ TracedSyscall::Exec { pid, .. }) => {
+                    attempts.insert(pid, exec);
+                }
+                Some(TracedSyscall::Open { ref path, .. }) if !self.is_watched(path) => {}
+                Some(syscall) => self.push(syscall),
+                None => {}
+            }
+        }
+    }
+
+    fn is_watched(&self, path: &str) -> bool {
+        is_sensitive_path(path) || self.watch.iter().any(|watch| path.starts_with(watch))
+    }
+
+    fn push(&self, syscall: TracedSyscall) {
+        let mut syscalls = self.syscalls.lock().unwrap();
+        if !syscalls.contains(&syscall) {
+            syscalls.push(syscall);
+        }
+    }
+}
+
+// record kinds, also the SECCOMP_RET_DATA of the filter rule that stops on them
+const EXECVE: u8 = 1;
+const EXECVEAT: u8 = 2;
+const OPEN: u8 = 3;
+const OPENAT: u8 = 4;
+const OPENAT2: u8 = 5;
+const CREAT: u8 = 6;
+const CONNECT: u8 = 7;
+// PTRACE_EVENT_EXEC: the execve attempt recorded for this pid succeeded
+const EXECED: u8 = 8;
+
+/*
+record = kind u8, pid u32, flags u32, then
+  exec:    path \0 argv[0] \0 argv[1] ...
+  open:    dir \0 path      (dir resolved by the tracer for relative paths)
+  connect: raw sockaddr
+*/
+fn parse_record(record: &[u8]) -> Option<TracedSyscall> {
+    let kind = *record.first()?;
+    let pid = u32::from_le_bytes(record.get(1..5)?.try_into().ok()?);
+    let flags = i32::from_le_bytes(record.get(5..9)?.try_into().ok()?);
+    let data = &record[9..];
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+
+    match kind {
+        EXECVE | EXECVEAT => {
+            let mut parts = data.split(|b| *b == 0);
+            let path = text(parts.next()?);
+            let argv = parts.filter(|arg| !arg.is_empty()).map(text).collect();
+            Some(TracedSyscall::Exec { pid, path, argv })
+        }
+        OPEN | OPENAT | OPENAT2 | CREAT => {
+            let (dir, path) = data.split_at(data.iter().position(|b| *b == 0)?);
+            let (dir, path) = (text(dir), text(&path[1..]));
+            let path = if path.starts_with('/') || dir.is_empty() {
+                path
+            } else {
+                format!("{}/{}", dir.trim_end_matches('/'), path)
+            };
+            let write_flags = libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC;
+            Some(TracedSyscall::Open {
+                pid,
+                path,
+                write: kind == CREAT || flags & write_flags != 0,
+            })
+        }
+        CONNECT => {
+            let family = u16::from_ne_bytes(data.get(0..2)?.try_into().ok()?) as i32;
+            let port = u16::from_be_bytes(data.get(2..4)?.try_into().ok()?);
+            let addr = match family {
+                libc::AF_INET => {
+                    let ip: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+                    SocketAddr::from((Ipv4Addr::from(ip), port))
+                }
+                libc::AF_INET6 => {
+                    let ip: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+                    SocketAddr::from((Ipv6Addr::from(ip), port))
+                }
+                // unix sockets and netlink are not network egress
+                _ => return None,
+            };
+            Some(TracedSyscall::Connect { pid, addr })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::File;
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    use libc::{c_int, c_long, c_uint, c_void, sock_filter};
+
+    use super::{CONNECT, CREAT, EXECED, EXECVE, EXECVEAT, OPEN, OPENAT, OPENAT2};
+    use crate::sandbox::native_isolation::linux::{AUDIT_ARCH, check, exit_like, jump, stmt};
+
+    #[cfg(target_arch = "x86_64")]
+    const TRACED: &[(c_long, u8)] = &[
+        (libc::SYS_execve, EXECVE),
+        (libc::SYS_execveat, EXECVEAT),
+        (libc::SYS_open, OPEN),
+        (libc::SYS_openat, OPENAT),
+        (libc::SYS_openat2, OPENAT2),
+        (libc::SYS_creat, CREAT),
+        (libc::SYS_connect, CONNECT),
+    ];
+    #[cfg(target_arch = "aarch64")]
+    const TRACED: &[(c_long, u8)] = &[
+        (libc::SYS_execve, EXECVE),
+        (libc::SYS_execveat, EXECVEAT),
+        (libc::SYS_openat, OPENAT),
+        (libc::SYS_openat2, OPENAT2),
+        (libc::SYS_connect, CONNECT),
+    ];
+
+    fn trace_filter() -> Vec<sock_filter> {
+        use libc::{BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+        let mut filter = vec![
+            stmt(BPF_LD | BPF_W | BPF_ABS, 4),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW),
+            stmt(BPF_LD | BPF_W | BPF_ABS, 0),
+        ];
+        for (nr, kind) in TRACED {
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, *nr as u32, 0, 1));
+            filter.push(stmt(
+                BPF_RET | BPF_K,
+                libc::SECCOMP_RET_TRACE | *kind as u32,
+            ));
+        }
+        filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+        filter
+    }
+
+    /// register the fork-and-trace step on `cmd`; returns the record pipe and the
+    /// write end, which the caller drops once the command is spawned
+    pub fn attach(cmd: &mut Command) -> io::Result<(File, OwnedFd)> {
+        let mut fds = [0 as RawFd; 2];
+        check(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) })?;
+        // SAFETY: pipe2 just returned these descriptors
+        let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        let out = writer.as_raw_fd();
+        let filter = trace_filter();
+
+        // SAFETY: the closure only makes async-signal-safe calls on data captured above
+        unsafe {
+            cmd.pre_exec(move || {
+                match check(libc::fork())? {
+                    0 => {}
+                    server => trace(server, out),
+                }
+                check(libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL, 0, 0, 0))?;
+                check(libc::ptrace(
+                    libc::PTRACE_TRACEME,
+                    0,
+                    std::ptr::null_mut::<c_void>(),
+                    std::ptr::null_mut::<c_void>(),
+                ) as c_int)?;
+                // wait here until the tracer has set its options
+                libc::raise(libc::SIGSTOP);
+                check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+                let prog = libc::sock_fprog {
+                    len: filter.len() as u16,
+                    filter: filter.as_ptr() as *mut sock_filter,
+                };
+                check(libc::syscall(
+                    libc::SYS_seccomp,
+                    libc::SECCOMP_SET_MODE_FILTER,
+                    0,
+                    &prog as *const libc::sock_fprog,
+                ) as c_int)?;
+                Ok(())
+            });
+        }
+        Ok((reader, writer))
+    }
+
+    unsafe fn trace(server: libc::pid_t, out: RawFd) -> ! {
+        unsafe {
+            // keep nothing but the record pipe: stdio and spawn's error pipe belong to the server
+            if out > 0 {
+                libc::close_range(0, out as c_uint - 1, 0);
+            }
+            libc::close_range(out as c_uint + 1, c_uint::MAX, 0);
+
+            let options = libc::PTRACE_O_TRACESECCOMP
+                | libc::PTRACE_O_TRACEFORK
+                | libc::PTRACE_O_TRACEVFORK
+                | libc::PTRACE_O_TRACECLONE
+                | libc::PTRACE_O_TRACEEXEC
+                | libc::PTRACE_O_EXITKILL;
+            let mut started = false;
+            loop {
+                let mut status = 0;
+                let pid = libc::waitpid(-1, &mut status, libc::__WALL);
+                if pid < 0 {
+                    if io::Error::last_os_error().raw_os_error() == Some(libc::EINTR) {
+                        continue;
+                    }
+                    libc::_exit(1);
+                }
+                if !libc::WIFSTOPPED(status) {
+                    if pid == server {
+                        // anything the server left behind dies with us (EXITKILL)
+                        exit_like(status);
+                    }
+                    continue;
+                }
+
+                let signal = libc::WSTOPSIG(status);
+                let event = status >> 16;
+                if !started && pid == server {
+                    libc::ptrace(
+                        libc::PTRACE_SETOPTIONS,
+                        server,
+                        std::ptr::null_mut::<c_void>(),
+                        options as *mut c_void,
+                    );
+                    started = true;
+                }
+                let deliver = match (signal, event) {
+                    (libc::SIGTRAP, libc::PTRACE_EVENT_SECCOMP) => {
+                        report(pid, out);
+                        0
+                    }
+                    (libc::SIGTRAP, libc::PTRACE_EVENT_EXEC) => {
+                        report_exec(pid, out);
+                        0
+                    }
+                    // fork/clone event stops
+                    (libc::SIGTRAP, event) if event != 0 => 0,
+                    // our own stop and the one new tracees start with
+                    (libc::SIGSTOP, _) => 0,
+                    (signal, _) => signal,
+                };
+                libc::ptrace(
+                    libc::PTRACE_CONT,
+                    pid,
+                    std::ptr::null_mut::<c_void>(),
+                    deliver as c_long as *mut c_void,
+                );
+            }
+        }
+    }
+
+    const RECORD_MAX: usize = 2048;
+    const PATH_MAX: usize = 1024;
+
+    struct Record {
+        buf: [u8; RECORD_MAX],
+        len: usize,
+    }
+
+    impl Record {
+        fn new(kind: u8, pid: libc::pid_t, flags: i32) -> Self {
+            let mut record = Self {
+                buf: [0; RECORD_MAX],
+                len: 2,
+            };
+            record.push(&[kind]);
+            record.push(&(pid as u32).to_le_bytes());
+            record.push(&flags.to_le_bytes());
+            record
+        }
+
+        fn push(&mut self, bytes: &[u8]) {
+            let n = bytes.len().min(RECORD_MAX - self.len);
+            self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+        }
+
+        fn room(&mut self) -> &mut [u8] {
+            &mut self.buf[self.len..]
+        }
+
+        unsafe fn send(mut self, out: RawFd) {
+            let body = (self.len - 2) as u16;
+            self.buf[..2].copy_from_slice(&body.to_le_bytes());
+            unsafe {
+                libc::write(out, self.buf.as_ptr().cast(), self.len);
+            }
+        }
+    }
+
+    unsafe fn report_exec(pid: libc::pid_t, out: RawFd) {
+        // an exec from a non-leader thread takes over the leader's pid
+        let mut former: libc::c_ulong = pid as libc::c_ulong;
+        unsafe {
+            libc::ptrace(
+                libc::PTRACE_GETEVENTMSG,
+                pid,
+                std::ptr::null_mut::<c_void>(),
+                &mut former as *mut libc::c_ulong as *mut c_void,
+            );
+            Record::new(EXECED, former as libc::pid_t, 0).send(out);
+        }
+    }
+
+    unsafe fn report(pid: libc::pid_t, out: RawFd) {
+        unsafe {
+            let mut info: libc::ptrace_syscall_info = mem::zeroed();
+            let size = mem::size_of::<libc::ptrace_syscall_info>();
+            let got = libc::ptrace(
+                libc::PTRACE_GET_SYSCALL_INFO,
+                pid,
+                size as *mut c_void,
+                &mut info as *mut _ as *mut c_void,
+            );
+            if got <= 0 || info.op != libc::PTRACE_SYSCALL_INFO_SECCOMP {
+                return;
+            }
+            let seccomp = info.u.seccomp;
+            let args = seccomp.args;
+            let kind = seccomp.ret_data as u8;
+            let at_cwd = libc::AT_FDCWD as i64;
+
+            let record = match kind {
+                EXECVE | EXECVEAT => {
+                    let (path, argv) = if kind == EXECVE {
+                        (args[0], args[1])
+                    } else {
+                        (args[1], args[2])
+                    };
+                    let mut record = Record::new(kind, pid, 0);
+                    let n = peek_str(pid, path, record.room());
+                    record.len += n;
+                    for idx in 0..16u64 {
+                        let arg = peek_word(pid, argv + idx * 8);
+                        if arg == 0 || record.room().len() < 2 {
+                            break;
+                        }
+                        record.push(&[0]);
+                        let n = peek_str(pid, arg as u64, record.room());
+                        record.len += n;
+                    }
+                    record
+                }
+                OPEN | OPENAT | OPENAT2 | CREAT => {
+                    let (dirfd, path, flags) = match kind {
+                        OPEN => (at_cwd, args[0], args[1] as i32),
+                        CREAT => (at_cwd, args[0], 0),
+                        OPENAT => (args[0] as i32 as i64, args[1], args[2] as i32),
+                        // struct open_how starts with the u64 flags
+                        _ => (
+                            args[0] as i32 as i64,
+                            args[1],
+                            peek_word(pid, args[2]) as i32,
+                        ),
+                    };
+                    let mut path_buf = [0u8; PATH_MAX];
+                    let path_len = peek_str(pid, path, &mut path_buf);
+                    let mut record = Record::new(kind, pid, flags);
+                    if path_buf.first() != Some(&b'/') {
+                        let n = base_dir(pid, dirfd, record.room());
+                        record.len += n;
+                    }
+                    record.push(&[0]);
+                    record.push(&path_buf[..path_len]);
+                    record
+                }
+                CONNECT => {
+                    let mut addr = [0u8; 32];
+                    let len = (args[2] as usize).min(addr.len());
+                    peek_bytes(pid, args[1], &mut addr[..len]);
+                    let mut record = Record::new(kind, pid, 0);
+                    record.push(&addr[..len]);
+                    record
+                }
+                _ => return,
+            };
+            record.send(out);
+        }
+    }
+
+    unsafe fn peek_word(pid: libc::pid_t, addr: u64) -> c_long {
+        unsafe {
+            *libc::__errno_location() = 0;
+            let word = libc::ptrace(
+                libc::PTRACE_PEEKDATA,
+                pid,
+                addr as *mut c_void,
+                std::ptr::null_mut::<c_void>(),
+            );
+            if word == -1 && *libc::__errno_location() != 0 {
+                return 0;
+            }
+            word
+        }
+    }
+
+    /// NUL-terminated string at `addr`, truncated to `out`; returns its length
+    unsafe fn peek_str(pid: libc::pid_t, addr: u64, out: &mut [u8]) -> usize {
+        let mut n = 0;
+        while n < out.len() {
+            let word = unsafe { peek_word(pid, addr + n as u64) };
+            for byte in word.to_ne_bytes() {
+                if byte == 0 || n == out.len() {
+                    return n;
+                }
+                out[n] = byte;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    unsafe fn peek_bytes(pid: libc::pid_t, addr: u64, out: &mut [u8]) {
+        for (idx, chunk) in out.chunks_mut(mem::size_of::<c_long>()).enumerate() {
+            let word = unsafe { peek_word(pid, addr + (idx * mem::size_of::<c_long>()) as u64) };
+            chunk.copy_from_slice(&word.to_ne_bytes()[..chunk.len()]);
+        }
+    }
+
+    /// directory a relative path resolves against: the cwd or the dirfd's target
+    unsafe fn base_dir(pid: libc::pid_t, dirfd: i64, out: &mut [u8]) -> usize {
+        // "/proc/<pid>/cwd" or "/proc/<pid>/fd/<n>", built without allocating
+        let mut link = [0u8; 64];
+        let mut len = 0;
+        let mut push = |bytes: &[u8]| {
+            link[len..len + bytes.len()].copy_from_slice(bytes);
+            len += bytes.len();
+        };
+        push(b"/proc/");
+        push(decimal(pid as u64, &mut [0u8; 20]));
+        if dirfd == libc::AT_FDCWD as i64 {
+            push(b"/cwd");
+        } else if dirfd >= 0 {
+            push(b"/fd/");
+            push(decimal(dirfd as u64, &mut [0u8; 20]));
+        } else {
+            return 0;
+        }
+        let n = unsafe { libc::readlink(link.as_ptr().cast(), out.as_mut_ptr().cast(), out.len()) };
+        n.max(0) as usize
+    }
+
+    fn decimal(mut value: u64, buf: &mut [u8; 20]) -> &[u8] {
+        let mut start = buf.len();
+        loop {
+            start -= 1;
+            buf[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                return &buf[start..];
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::net::TcpListener;
+    use std::process::Stdio;
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn traces_spawns_sensitive_opens_and_connects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let tracer = Arc::new(SyscallTracer::new(Vec::new()));
+
+        let script = format!(
+            "cat /proc/self/environ > /dev/null; \
+             python3 -c 'import socket; socket.create_connection((\"127.0.0.1\", {port}))'; \
+             echo done"
+        );
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &script]).stdout(Stdio::piped());
+        let mut child = match tracer.spawn_with(&mut cmd, |cmd| cmd.spawn()) {
+            Ok(child) => child,
+            // ptrace can be forbidden in containers
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+            Err(err) => panic!("spawn traced: {err}"),
+        };
+        let mut out = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        assert!(child.wait().unwrap().success());
+        tracer.finish();
+        assert_eq!(out, "done\n");
+
+        let syscalls = tracer.syscalls();
+        assert!(syscalls.iter().any(
+            |s| matches!(s, TracedSyscall::Exec { argv, .. } if argv.first().is_some_and(|a| a == "cat"))
+        ));
+        assert!(syscalls.iter().any(
+            |s| matches!(s, TracedSyscall::Open { path, write: false, .. } if path == "/proc/self/environ")
+        ));
+        assert!(
+            syscalls
+                .iter()
+                .any(|s| matches!(s, TracedSyscall::Connect { addr, .. } if addr.port() == port))
+        );
+        // the traced server itself is not a spawn
+        assert!(!syscalls.iter().any(
+            |s| matches!(s, TracedSyscall::Exec { argv, .. } if argv.first().is_some_and(|a| a == "sh"))
+        ));
+        let kinds: Vec<MonitorEventKind> = tracer
+            .as_monitor_events()
+            .into_iter()
+            .map(|e| e.kind)
+            .collect();
+        assert!(kinds.contains(&MonitorEventKind::ProcessSpawn));
+        assert!(kinds.contains(&MonitorEventKind::NetworkConnectAttempt));
+    }
+}
@@ -0,0 +1,403 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+/*
+which outbound connections a subject may make.
+deny-by-default: a destination is allowed only if an allow rule matches it, and
+with a stand-in configured allowed traffic is sent to that local server instead.
+
+subject.toml:
+    [network]
+    allow = ["api.example.com:443", "*.githubusercontent.com", "10.0.0.0/8"]
+    stand_in = "127.0.0.1:18080"
+    fixtures = "case_studies/weather/http_fixtures.toml"
+    intercept_tls = true
+    capture_dns = true
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkPolicy {
+    #[serde(default)]
+    pub allow: Vec<NetworkRule>,
+    // loopback server that answers for every allowed destination
+    pub stand_in: Option<SocketAddr>,
+    // canned HTTP responses served by the egress proxy before the policy is consulted
+    pub fixtures: Option<PathBuf>,
+    // terminate CONNECT tunnels with a throwaway CA so HTTPS requests are visible
+    #[serde(default)]
+    pub intercept_tls: bool,
+    // point native servers at a stub resolver that logs and sinkholes every query
+    #[serde(default)]
+    pub capture_dns: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkDecision {
+    Deny,
+    Allow,
+    Redirect(SocketAddr),
+}
+
+impl NetworkDecision {
+    pub fn allowed(&self) -> bool {
+        !matches!(self, Self::Deny)
+    }
+
+    pub fn redirected_to(&self) -> Option<String> {
+        match self {
+            Self::Redirect(addr) => Some(addr.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl NetworkPolicy {
+    pub fn deny_all() -> Self {
+        Self::default()
+    }
+
+    /// stand-ins must stay on the host, otherwise "redirect" is just egress
+    pub fn validate(&self) -> Result<()> {
+        if let Some(addr) = self.stand_in
+            && !addr.ip().is_loopback()
+        {
+            bail!("network stand-in {addr} is not a loopback address");
+        }
+        Ok(())
+    }
+
+    /// subject rules plus `other`'s (e.g. CLI) rules; `other`'s stand-in wins
+    pub fn merged(&self, other: &NetworkPolicy) -> NetworkPolicy {
+        let mut allow = self.allow.clone();
+        for rule in &other.allow {
+            if !allow.contains(rule) {
+                allow.push(rule.clone());
+            }
+        }
+        NetworkPolicy {
+            allow,
+            stand_in: other.stand_in.or(self.stand_in),
+            fixtures: other.fixtures.clone().or_else(|| self.fixtures.clone()),
+            intercept_tls: self.intercept_tls || other.intercept_tls,
+            capture_dns: self.capture_dns || other.capture_dns,
+        }
+    }
+
+    pub fn decide(&self, host: &str, port: Option<u16>) -> NetworkDecision {
+        if let Some(stand_in) = self.stand_in
+            && is_addr(stand_in, host, port)
+        {
+            return NetworkDecision::Allow;
+        }
+        if !self.allow.iter().any(|rule| rule.matches(host, port)) {
+            return NetworkDecision::Deny;
+        }
+        match self.stand_in {
+            Some(addr) => NetworkDecision::Redirect(addr),
+            None => NetworkDecision::Allow,
+        }
+    }
+
+    /// parts of the policy a WASI socket check cannot enforce: guests connect to
+    /// bare IPs, so hostname rules never match, and sockets cannot be re-addressed,
+    /// so a stand-in never receives the traffic it is meant to answer
+    pub fn wasi_socket_warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .allow
+            .iter()
+            .filter(|rule| {
+                matches!(rule, NetworkRule::Host { host, .. }
+                    if host.trim_matches(['[', ']']).parse::<IpAddr>().is_err())
+            })
+            .map(|rule| format!("hostname rule `{rule}` never matches a WASI socket"))
+            .collect();
+        if let Some(addr) = self.stand_in
+            && !self.allow.is_empty()
+        {
+            warnings.push(format!(
+                "WASI sockets cannot be redirected to stand-in {addr}; allowed destinations are denied"
+            ));
+        }
+        warnings
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match (self.allow.is_empty(), self.stand_in.is_some()) {
+            (true, _) => "deny-by-default",
+            (false, false) => "allowlist",
+            (false, true) => "allowlist-stand-in",
+        }
+    }
+}
+
+fn is_addr(addr: SocketAddr, host: &str, port: Option<u16>) -> bool {
+    port == Some(addr.port())
+        && host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip == addr.ip())
+}
+
+/// one allowlist entry: `host`, `host:port`, `*.suffix[:port]`, `[v6]:port` or `cidr[:port]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum NetworkRule {
+    Host {
+        host: String,
+        port: Option<u16>,
+    },
+    Cidr {
+        network: IpAddr,
+        prefix: u8,
+        port: Option<u16>,
+    },
+}
+
+impl NetworkRule {
+    pub fn matches(&self, host: &str, port: Option<u16>) -> bool {
+        let port_ok = |rule_port: &Option<u16>| rule_port.is_none() || *rule_port == port;
+        let host = host.trim_matches(['[', ']']);
+        match self {
+            Self::Host {
+                host: pattern,
+                port: rule_port,
+            } => {
+                let host_ok = match pattern.strip_prefix("*.") {
+                    Some(suffix) => host
+                        .to_ascii_lowercase()
+                        .strip_suffix(suffix)
+                        .is_some_and(|rest| rest.ends_with('.')),
+                    None => host.eq_ignore_ascii_case(pattern),
+                };
+                host_ok && port_ok(rule_port)
+            }
+            Self::Cidr {
+                network,
+                prefix,
+                port: rule_port,
+            } => {
+                host.parse::<IpAddr>()
+                    .is_ok_and(|ip| in_cidr(ip, *network, *prefix))
+                    && port_ok(rule_port)
+            }
+        }
+    }
+}
+
+fn in_cidr(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+fn parse_port(port: &str, rule: &str) -> Result<u16> {
+    port.parse()
+        .map_err(|_| anyhow::anyhow!("invalid port in network rule `{rule}`"))
+}
+
+impl FromStr for NetworkRule {
+    type Err = anyhow::Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let rule = rule.trim();
+        if rule.is_empty() {
+            bail!("empty network rule");
+        }
+
+        if let Some((network, rest)) = rule.split_once('/') {
+            let (prefix, port) = match rest.split_once(':') {
+                Some((prefix, port)) => (prefix, Some(parse_port(port, rule)?)),
+                None => (rest, None),
+            };
+            let network: IpAddr = network
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid network in CIDR rule `{rule}`"))?;
+            let max = if network.is_ipv4() { 32 } else { 128 };
+            let prefix: u8 = prefix
+                .parse()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| anyhow::anyhow!("invalid prefix length in CIDR rule `{rule}`"))?;
+            return Ok(Self::Cidr {
+                network,
+                prefix,
+                port,
+            });
+        }
+
+        // bare IPv6 address or [v6]:port
+        if rule.parse::<IpAddr>().is_ok() {
+            return Ok(Self::Host {
+                host: rule.to_string(),
+                port: None,
+            });
+        }
+        if let Some(rest) = rule.strip_prefix('[') {
+            let (host, port) = rest
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("unclosed `[` in network rule `{rule}`"))?;
+            let port = match port.strip_prefix(':') {
+                Some(port) => Some(parse_port(port, rule)?),
+                None => None,
+            };
+            return Ok(Self::Host {
+                host: host.to_string(),
+                port,
+            });
+        }
+
+        let (host, port) = match rule.rsplit_once(':') {
+            Some((host, port)) => (host, Some(parse_port(port, rule)?)),
+            None => (rule, None),
+        };
+        Ok(Self::Host {
+            host: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+}
+
+impl TryFrom<String> for NetworkRule {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: String) -> Result<Self> {
+        rule.parse()
+    }
+}
+
+impl From<NetworkRule> for String {
+    fn from(rule: NetworkRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl fmt::Display for NetworkRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host { host, port } => {
+                if host.contains(':') {
+                    write!(f, "[{host}]")?;
+                } else {
+                    f.write_str(host)?;
+                }
+                if let Some(port) = port {
+                    write!(f, ":{port}")?;
+                }
+                Ok(())
+            }
+            Self::Cidr {
+                network,
+                prefix,
+                port,
+            } => {
+                write!(f, "{network}/{prefix}")?;
+                if let Some(port) = port {
+                    write!(f, ":{port}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(rules: &[&str], stand_in: Option<&str>) -> NetworkPolicy {
+        NetworkPolicy {
+            allow: rules.iter().map(|rule| rule.parse().unwrap()).collect(),
+            stand_in: stand_in.map(|addr| addr.parse().unwrap()),
+            ..NetworkPolicy::default()
+        }
+    }
+
+    #[test]
+    fn matches_hosts_ports_and_cidrs() {
+        let policy = policy(
+            &["api.example.com:443", "*.github.com", "10.0.0.0/8:5432"],
+            None,
+        );
+        assert!(policy.decide("API.example.com", Some(443)).allowed());
+        assert!(!policy.decide("api.example.com", Some(80)).allowed());
+        assert!(policy.decide("raw.github.com", Some(80)).allowed());
+        assert!(
+            !policy
+                .decide("github.com.evil.example", Some(443))
+                .allowed()
+        );
+        assert!(policy.decide("10.1.2.3", Some(5432)).allowed());
+        assert!(!policy.decide("11.1.2.3", Some(5432)).allowed());
+        assert_eq!(
+            NetworkPolicy::deny_all().decide("api.example.com", Some(443)),
+            NetworkDecision::Deny
+        );
+    }
+
+    #[test]
+    fn redirects_allowed_traffic_to_stand_in() {
+        let policy = policy(&["api.example.com"], Some("127.0.0.1:18080"));
+        assert_eq!(
+            policy.decide("api.example.com", Some(443)),
+            NetworkDecision::Redirect("127.0.0.1:18080".parse().unwrap())
+        );
+        assert_eq!(
+            policy.decide("127.0.0.1", Some(18080)),
+            NetworkDecision::Allow
+        );
+        assert!(policy.validate().is_ok());
+        assert!(
+            NetworkPolicy {
+                stand_in: Some("203.0.113.5:80".parse().unwrap()),
+                ..NetworkPolicy::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn warns_about_rules_wasi_sockets_cannot_enforce() {
+        assert!(
+            policy(&["10.0.0.0/8", "127.0.0.1:8080", "[::1]:8080"], None)
+                .wasi_socket_warnings()
+                .is_empty()
+        );
+
+        let warnings = policy(
+            &["api.example.com:443", "10.0.0.0/8"],
+            Some("127.0.0.1:18080"),
+        )
+        .wasi_socket_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("`api.example.com:443`"));
+        assert!(warnings[1].contains("127.0.0.1:18080"));
+    }
+
+    #[test]
+    fn parses_from_subject_toml() {
+        let policy: NetworkPolicy = toml::from_str(
+            r#"
+            allow = ["api.example.com:443", "[::1]:8080", "fd00::/8"]
+            stand_in = "127.0.0.1:18080"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy.allow.len(), 3);
+        assert_eq!(policy.allow[1].to_string(), "[::1]:8080");
+        assert!(policy.decide("fd00::1", None).allowed());
+        assert!(toml::from_str::<NetworkPolicy>(r#"allow = ["10.0.0.0/40"]"#).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::json;
+use wasmtime_wasi::WasiCtxBuilder;
+use wasmtime_wasi::filesystem::{DirPerms, FilePerms};
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::sockets::SocketAddrUse;
+
+use crate::collect::NetworkCollector;
+use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+use crate::sandbox::network_policy::NetworkPolicy;
+
+/// guest-facing setup shared by the preview1 and preview2 runtimes
+pub(crate) struct GuestSetup<'a> {
+    pub env: &'a HashMap<String, String>,
+    pub args: &'a [String],
+    pub stdin_input: Option<&'a [u8]>,
+    pub stdout: &'a MemoryOutputPipe,
+    pub stderr: &'a MemoryOutputPipe,
+    pub max_output_bytes: usize,
+    // (host dir, guest path) in preopen order
+    pub preopens: &'a [(&'a Path, &'static str)],
+    pub network_collector: &'a Arc<NetworkCollector>,
+    pub network_policy: &'a NetworkPolicy,
+}
+
+/// guest root, work dir and data dir, in the order they are preopened
+pub(crate) fn preopen_dirs<'a>(
+    guest_root: &'a Option<PathBuf>,
+    work_dir: &'a Option<PathBuf>,
+    data_dir: &'a Option<PathBuf>,
+) -> Vec<(&'a Path, &'static str)> {
+    [(guest_root, "/"), (work_dir, "/work"), (data_dir, "/data")]
+        .into_iter()
+        .filter_map(|(dir, guest)| dir.as_deref().map(|dir| (dir, guest)))
+        .collect()
+}
+
+impl GuestSetup<'_> {
+    /// grant stdio, env, args, preopens and the network monitor, recording a
+    /// CapabilityGranted event for each
+    pub(crate) fn apply(
+        &self,
+        builder: &mut WasiCtxBuilder,
+        monitor_events: &mut Vec<MonitorEvent>,
+    ) -> Result<()> {
+        if let Some(bytes) = self.stdin_input {
+            builder.stdin(MemoryInputPipe::new(bytes.to_vec()));
+            monitor_events.push(MonitorEvent {
+                kind: MonitorEventKind::CapabilityGranted,
+                actor: "wasi-runtime".to_string(),
+                target: Some("stdin".to_string()),
+                evidence: json!({
+                    "capability": "stdio",
+                    "stream": "stdin",
+                    "bytes_len": bytes.len()
+                }),
+            });
+        }
+
+        builder.stdout(self.stdout.clone());
+        monitor_events.push(MonitorEvent {
+            kind: MonitorEventKind::CapabilityGranted,
+            actor: "wasi-runtime".to_string(),
+            target: Some("stdout".to_string()),
+            evidence: json!({
+                "capability": "stdio",
+                "stream": "stdout",
+                "max_output_bytes": self.max_output_bytes
+            }),
+        });
+
+        builder.stderr(self.stderr.clone());
+        monitor_events.push(MonitorEvent {
+            kind: MonitorEventKind::CapabilityGranted,
+            actor: "wasi-runtime".to_string(),
+            target: Some("stderr".to_string()),
+            evidence: json!({
+                "capability": "stdio",
+                "stream": "stderr",
+                "max_output_bytes": self.max_output_bytes
+            }),
+        });
+
+        for (k, v) in self.env {
+            builder.env(k, v);
+            monitor_events.push(MonitorEvent {
+                kind: MonitorEventKind::CapabilityGranted,
+                actor: "wasi-runtime".to_string(),
+                target: Some(k.clone()),
+                evidence: json!({
+                    "capability": "env",
+                    "key": k,
+                    "value_len": v.len()
+                }),
+            });
+        }
+
+        for arg in self.args {
+            builder.arg(arg);
+        }
+
+        for (dir, guest_path) in self.preopens {
+            builder.preopened_dir(dir, guest_path, DirPerms::all(), FilePerms::all())?;
+            monitor_events.push(MonitorEvent {
+                kind: MonitorEventKind::CapabilityGranted,
+                actor: "wasi-runtime".to_string(),
+                target: Some(guest_path.to_string()),
+                evidence: json!({
+                    "capability": "filesystem-preopen",
+                    "guest_path": guest_path,
+                    "host_path": dir,
+                    "dir_perms": "all",
+                    "file_perms": "all"
+                }),
+            });
+        }
+
+        let network_collector = Arc::clone(self.network_collector);
+        network_collector.set_policy(self.network_policy.clone());
+        let policy = self.network_policy;
+        builder.allow_tcp(true);
+        builder.allow_udp(true);
+        builder.socket_addr_check(move |addr: SocketAddr, use_case: SocketAddrUse| {
+            let allowed = network_collector.check_socket(addr, use_case);
+            Box::pin(async move { allowed }) as Pin<Box<dyn Future<Output = bool> + Send + Sync>>
+        });
+        monitor_events.push(MonitorEvent {
+            kind: MonitorEventKind::CapabilityGranted,
+            actor: "wasi-runtime".to_string(),
+            target: Some("network-monitor".to_string()),
+            evidence: json!({
+                "capability": "network-monitor",
+                "policy": policy.describe(),
+                "allow": policy.allow,
+                "stand_in": policy.stand_in,
+                "warnings": policy.wasi_socket_warnings(),
+                "tcp": true,
+                "udp": true
+            }),
+        });
+
+        Ok(())
+    }
+}
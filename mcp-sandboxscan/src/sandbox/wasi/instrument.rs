@@ -0,0 +1,447 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use wasmtime::{
+    AsContextMut, Caller, Extern, Func, Instance, Linker, Module, Store, WasmParams, WasmResults,
+};
+
+use crate::collect::{EnvCollector, FileCollector};
+use crate::taint::tracker::TaintTracker;
+
+/*
+shadow the preview1 host calls we want to observe. each wrapper calls the
+original wasmtime-wasi implementation, then reads guest memory to label
+(environ_get, fd_read) or check (fd_write) the bytes involved and to keep the
+fd -> path table behind the FileRead / FileWrite events
+*/
+
+const MODULE: &str = "wasi_snapshot_preview1";
+const ERRNO_SUCCESS: i32 = 0;
+
+// (name, params); every hooked call returns an i32 errno
+const HOOKED: &[(&str, &[&str])] = &[
+    ("environ_sizes_get", &["i32", "i32"]),
+    ("environ_get", &["i32", "i32"]),
+    ("fd_read", &["i32", "i32", "i32", "i32"]),
+    ("fd_write", &["i32", "i32", "i32", "i32"]),
+    ("fd_seek", &["i32", "i64", "i32", "i32"]),
+    ("fd_close", &["i32"]),
+    (
+        "path_open",
+        &[
+            "i32", "i32", "i32", "i32", "i32", "i64", "i64", "i32", "i32",
+        ],
+    ),
+    ("path_unlink_file", &["i32", "i32", "i32"]),
+    ("path_rename", &["i32", "i32", "i32", "i32", "i32", "i32"]),
+];
+
+/// state the wrappers report into
+pub struct Preview1Hooks {
+    pub tracker: Arc<TaintTracker>,
+    pub files: Arc<FileCollector>,
+    pub env: Arc<EnvCollector>,
+    // number of KEY=VALUE entries environ_get hands out
+    pub env_count: usize,
+}
+
+// wasmtime-wasi resolves guest memory through the calling instance's "memory"
+// export, so the originals have to be called from wasm. the trampoline module
+// re-exports the guest memory and forwards to the original host functions.
+fn trampoline_wat() -> String {
+    let mut wat = String::from("(module\n");
+    for (name, params) in HOOKED {
+        wat.push_str(&format!(
+            "  (import \"{MODULE}\" \"{name}\" (func ${name} (param {}) (result i32)))\n",
+            params.join(" ")
+        ));
+    }
+    wat.push_str("  (import \"guest\" \"memory\" (memory 0))\n  (export \"memory\" (memory 0))\n");
+    for (name, params) in HOOKED {
+        let locals: Vec<String> = (0..params.len())
+            .map(|i| format!("local.get {i}"))
+            .collect();
+        wat.push_str(&format!(
+            "  (func (export \"{name}\") (param {}) (result i32) {} call ${name})\n",
+            params.join(" "),
+            locals.join(" ")
+        ));
+    }
+    wat.push(')');
+    wat
+}
+
+struct Shared {
+    hooks: Preview1Hooks,
+    module: Module,
+    originals: Vec<Func>,
+    // instantiated on first use, once the guest memory exists
+    trampoline: Mutex<Option<Instance>>,
+}
+
+impl Shared {
+    fn call<T, P, R>(
+        &self,
+        caller: &mut Caller<'_, T>,
+        name: &str,
+        params: P,
+    ) -> wasmtime::Result<R>
+    where
+        T: 'static,
+        P: WasmParams,
+        R: WasmResults,
+    {
+        let instance = self.trampoline(caller)?;
+        instance
+            .get_typed_func::<P, R>(&mut *caller, name)?
+            .call(&mut *caller, params)
+    }
+
+    fn trampoline<T: 'static>(&self, caller: &mut Caller<'_, T>) -> wasmtime::Result<Instance> {
+        if let Some(instance) = *self.trampoline.lock().unwrap() {
+            return Ok(instance);
+        }
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or_else(|| wasmtime::Error::msg("guest does not export memory"))?;
+        let mut imports: Vec<Extern> = self.originals.iter().map(|f| (*f).into()).collect();
+        imports.push(memory.into());
+        let instance = Instance::new(&mut *caller, &self.module, &imports)?;
+        *self.trampoline.lock().unwrap() = Some(instance);
+        Ok(instance)
+    }
+}
+
+fn original<T: 'static>(linker: &Linker<T>, store: &mut Store<T>, name: &str) -> Result<Func> {
+    linker
+        .get(store.as_context_mut(), MODULE, name)
+        .ok()
+        .and_then(Extern::into_func)
+        .with_context(|| format!("{MODULE}::{name} is not defined in the linker"))
+}
+
+pub fn instrument_preview1<T: Send + 'static>(
+    linker: &mut Linker<T>,
+    store: &mut Store<T>,
+    hooks: Preview1Hooks,
+) -> Result<()> {
+    let module = Module::new(store.engine(), trampoline_wat())
+        .map_err(|err| anyhow::anyhow!("failed to compile WASI trampoline: {err}"))?;
+    let originals = HOOKED
+        .iter()
+        .map(|(name, _)| original(linker, store, name))
+        .collect::<Result<Vec<_>>>()?;
+    let shared = Arc::new(Shared {
+        hooks,
+        module,
+        originals,
+        trampoline: Mutex::new(None),
+    });
+
+    linker.allow_shadowing(true);
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "environ_sizes_get",
+        move |mut caller: Caller<'_, T>, count: i32, buf_size: i32| {
+            let errno: i32 = s.call(&mut caller, "environ_sizes_get", (count, buf_size))?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                if let (Some(count), Some(buf_size)) = (
+                    read_u32(memory, count as u32),
+                    read_u32(memory, buf_size as u32),
+                ) {
+                    s.hooks.env.sizes_queried(count, buf_size);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "environ_get",
+        move |mut caller: Caller<'_, T>, environ: i32, environ_buf: i32| {
+            let errno: i32 = s.call(&mut caller, "environ_get", (environ, environ_buf))?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                for (addr, key, value) in
+                    environ_entries(memory, environ_buf as u32, s.hooks.env_count)
+                {
+                    s.hooks.env.granted(&key, Some(addr), value.len());
+                    s.hooks.tracker.taint_env(addr, &key, value);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "fd_read",
+        move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, nread: i32| {
+            let errno: i32 = s.call(&mut caller, "fd_read", (fd, iovs, iovs_len, nread))?;
+            // stdin carries the caller's own request, not an external source
+            if errno == ERRNO_SUCCESS && fd != 0 {
+                let memory = guest_memory(&mut caller);
+                let total = read_u32(memory, nread as u32).unwrap_or(0) as usize;
+                let chunks = iovec_chunks(memory, iovs as u32, iovs_len as u32, total);
+                let bytes: Vec<u8> = chunks.iter().flat_map(|(_, b)| b.iter().copied()).collect();
+                let (label, is_source) = s
+                    .hooks
+                    .files
+                    .read(fd as u32, &bytes)
+                    .unwrap_or_else(|| (format!("fd:{fd}"), false));
+                for (addr, chunk) in &chunks {
+                    s.hooks.tracker.taint_read(*addr, &label, chunk, is_source);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "fd_write",
+        move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, nwritten: i32| {
+            let errno: i32 = s.call(&mut caller, "fd_write", (fd, iovs, iovs_len, nwritten))?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                let total = read_u32(memory, nwritten as u32).unwrap_or(0) as usize;
+                let chunks = iovec_chunks(memory, iovs as u32, iovs_len as u32, total);
+                s.hooks.files.written(fd as u32, total as u64);
+                s.hooks.tracker.observe_write(fd as u32, &chunks);
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "fd_seek",
+        move |mut caller: Caller<'_, T>, fd: i32, offset: i64, whence: i32, newoffset: i32| {
+            let errno: i32 = s.call(&mut caller, "fd_seek", (fd, offset, whence, newoffset))?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                if let Some(position) = read_u64(memory, newoffset as u32) {
+                    s.hooks.files.seeked(fd as u32, position);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "fd_close",
+        move |mut caller: Caller<'_, T>, fd: i32| {
+            let errno: i32 = s.call(&mut caller, "fd_close", fd)?;
+            if errno == ERRNO_SUCCESS {
+                s.hooks.files.closed(fd as u32);
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "path_open",
+        move |mut caller: Caller<'_, T>,
+              dir_fd: i32,
+              dirflags: i32,
+              path: i32,
+              path_len: i32,
+              oflags: i32,
+              rights_base: i64,
+              rights_inheriting: i64,
+              fdflags: i32,
+              fd_out: i32| {
+            let params = (
+                dir_fd,
+                dirflags,
+                path,
+                path_len,
+                oflags,
+                rights_base,
+                rights_inheriting,
+                fdflags,
+                fd_out,
+            );
+            let errno: i32 = s.call(&mut caller, "path_open", params)?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                if let (Some(relative), Some(fd)) = (
+                    read_str(memory, path as u32, path_len as u32),
+                    read_u32(memory, fd_out as u32),
+                ) {
+                    s.hooks.files.opened(dir_fd as u32, &relative, fd);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = Arc::clone(&shared);
+    wrap(
+        linker,
+        "path_unlink_file",
+        move |mut caller: Caller<'_, T>, dir_fd: i32, path: i32, path_len: i32| {
+            let errno: i32 = s.call(&mut caller, "path_unlink_file", (dir_fd, path, path_len))?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                if let Some(relative) = read_str(memory, path as u32, path_len as u32) {
+                    s.hooks.files.unlinked(dir_fd as u32, &relative);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    let s = shared;
+    wrap(
+        linker,
+        "path_rename",
+        move |mut caller: Caller<'_, T>,
+              old_fd: i32,
+              old_path: i32,
+              old_len: i32,
+              new_fd: i32,
+              new_path: i32,
+              new_len: i32| {
+            let params = (old_fd, old_path, old_len, new_fd, new_path, new_len);
+            let errno: i32 = s.call(&mut caller, "path_rename", params)?;
+            if errno == ERRNO_SUCCESS {
+                let memory = guest_memory(&mut caller);
+                if let (Some(old), Some(new)) = (
+                    read_str(memory, old_path as u32, old_len as u32),
+                    read_str(memory, new_path as u32, new_len as u32),
+                ) {
+                    s.hooks
+                        .files
+                        .renamed(old_fd as u32, &old, new_fd as u32, &new);
+                }
+            }
+            Ok(errno)
+        },
+    )?;
+
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+fn wrap<T: 'static, Params, Args>(
+    linker: &mut Linker<T>,
+    name: &str,
+    func: impl wasmtime::IntoFunc<T, Params, Args>,
+) -> Result<()> {
+    linker
+        .func_wrap(MODULE, name, func)
+        .map(|_| ())
+        .map_err(|err| anyhow::anyhow!("failed to wrap {MODULE}::{name}: {err}"))
+}
+
+fn guest_memory<'a, T: 'static>(caller: &'a mut Caller<'_, T>) -> &'a [u8] {
+    match caller.get_export("memory").and_then(Extern::into_memory) {
+        Some(memory) => memory.data(caller),
+        None => &[],
+    }
+}
+
+fn read_u32(memory: &[u8], addr: u32) -> Option<u32> {
+    let start = addr as usize;
+    let bytes = memory.get(start..start + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(memory: &[u8], addr: u32) -> Option<u64> {
+    let start = addr as usize;
+    let bytes = memory.get(start..start + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_str(memory: &[u8], addr: u32, len: u32) -> Option<String> {
+    let bytes = memory.get(addr as usize..addr as usize + len as usize)?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// (guest address, bytes) for the first `total` bytes spread over an iovec array
+fn iovec_chunks(memory: &[u8], iovs: u32, iovs_len: u32, total: usize) -> Vec<(u32, Vec<u8>)> {
+    let mut out = vec![];
+    let mut remaining = total;
+    for i in 0..iovs_len {
+        if remaining == 0 {
+            break;
+        }
+        let (Some(buf), Some(len)) = (
+            read_u32(memory, iovs + i * 8),
+            read_u32(memory, iovs + i * 8 + 4),
+        ) else {
+            break;
+        };
+        let take = (len as usize).min(remaining);
+        let Some(bytes) = memory.get(buf as usize..buf as usize + take) else {
+            break;
+        };
+        if take > 0 {
+            out.push((buf, bytes.to_vec()));
+        }
+        remaining -= take;
+    }
+    out
+}
+
+/// environ_buf holds `count` NUL-terminated KEY=VALUE strings back to back;
+/// yields (address of VALUE, KEY, VALUE)
+fn environ_entries(memory: &[u8], environ_buf: u32, count: usize) -> Vec<(u32, String, &[u8])> {
+    let mut out = vec![];
+    let mut pos = environ_buf as usize;
+    for _ in 0..count {
+        let Some(rest) = memory.get(pos..) else {
+            break;
+        };
+        let Some(nul) = rest.iter().position(|b| *b == 0) else {
+            break;
+        };
+        let entry = &rest[..nul];
+        if let Some(eq) = entry.iter().position(|b| *b == b'=') {
+            let key = String::from_utf8_lossy(&entry[..eq]).into_owned();
+            out.push(((pos + eq + 1) as u32, key, &entry[eq + 1..]));
+        }
+        pos += nul + 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_iovecs_by_bytes_transferred() {
+        let mut memory = vec![0u8; 64];
+        // iovec[0] = {buf: 32, len: 4}, iovec[1] = {buf: 48, len: 8}
+        memory[0..4].copy_from_slice(&32u32.to_le_bytes());
+        memory[4..8].copy_from_slice(&4u32.to_le_bytes());
+        memory[8..12].copy_from_slice(&48u32.to_le_bytes());
+        memory[12..16].copy_from_slice(&8u32.to_le_bytes());
+        memory[32..36].copy_from_slice(b"abcd");
+        memory[48..54].copy_from_slice(b"efghij");
+
+        let chunks = iovec_chunks(&memory, 0, 2, 6);
+        assert_eq!(chunks, vec![(32, b"abcd".to_vec()), (48, b"ef".to_vec())]);
+    }
+
+    #[test]
+    fn trampoline_wat_compiles() {
+        let engine = wasmtime::Engine::default();
+        Module::new(&engine, trampoline_wat()).expect("trampoline compiles");
+    }
+}
@@ -0,0 +1,404 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use wasmtime::component::{HasData, Linker, Resource};
+use wasmtime_wasi::cli::WasiCliCtxView;
+use wasmtime_wasi::filesystem::WasiFilesystemCtxView;
+use wasmtime_wasi::p2::bindings::cli::environment;
+use wasmtime_wasi::p2::bindings::filesystem::preopens;
+use wasmtime_wasi::p2::bindings::sync::filesystem::types::{
+    self, Advice, Descriptor, DescriptorFlags, DescriptorStat, DescriptorType, DirectoryEntry,
+    DirectoryEntryStream, ErrorCode, Filesize, MetadataHashValue, NewTimestamp, OpenFlags,
+    PathFlags,
+};
+use wasmtime_wasi::p2::bindings::sync::io::streams::{self, InputStream, OutputStream};
+use wasmtime_wasi::p2::{FsError, FsResult};
+
+use crate::collect::{EnvCollector, FileCollector};
+use crate::sandbox::limits::GuestState;
+
+use super::preview2::WasiPreview2Ctx;
+
+/*
+shadow the wasi:filesystem and wasi:cli/environment host calls of a component.
+each wrapper calls the wasmtime-wasi implementation and reports to the same
+collectors the preview1 hooks feed, keyed by descriptor handle instead of fd.
+reads through a stream are not seen chunk by chunk: opening the stream counts
+as reading the file from the stream's offset on.
+*/
+
+// cap on bytes taken from a file read through a stream (matches collect_file_sources)
+const MAX_STREAM_BYTES: u64 = 64 * 1024;
+
+/// state the wrappers report into
+#[derive(Clone)]
+pub struct Preview2Hooks {
+    pub files: Arc<FileCollector>,
+    pub env: Arc<EnvCollector>,
+    // (host dir, guest path) of each preopen
+    pub preopens: Vec<(PathBuf, String)>,
+}
+
+pub fn instrument_preview2(linker: &mut Linker<GuestState<WasiPreview2Ctx>>) -> Result<()> {
+    linker.allow_shadowing(true);
+    environment::add_to_linker::<_, HookedCli>(linker, |state| {
+        let (ctx, table, hooks) = state.ctx.hooked();
+        HookedCliView {
+            inner: WasiCliCtxView {
+                ctx: ctx.cli(),
+                table,
+            },
+            hooks,
+        }
+    })?;
+    preopens::add_to_linker::<_, HookedFilesystem>(linker, filesystem_view)?;
+    types::add_to_linker::<_, HookedFilesystem>(linker, filesystem_view)?;
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+fn filesystem_view(state: &mut GuestState<WasiPreview2Ctx>) -> HookedFilesystemView<'_> {
+    let (ctx, table, hooks) = state.ctx.hooked();
+    HookedFilesystemView {
+        inner: WasiFilesystemCtxView {
+            ctx: ctx.filesystem(),
+            table,
+        },
+        hooks,
+    }
+}
+
+struct HookedCli;
+
+impl HasData for HookedCli {
+    type Data<'a> = HookedCliView<'a>;
+}
+
+struct HookedCliView<'a> {
+    inner: WasiCliCtxView<'a>,
+    hooks: &'a Preview2Hooks,
+}
+
+// components fetch the whole environment as a list, so this only records the grant
+impl environment::Host for HookedCliView<'_> {
+    fn get_environment(&mut self) -> wasmtime::Result<Vec<(String, String)>> {
+        let vars = environment::Host::get_environment(&mut self.inner)?;
+        for (key, value) in &vars {
+            self.hooks.env.granted(key, None, value.len());
+        }
+        Ok(vars)
+    }
+
+    fn get_arguments(&mut self) -> wasmtime::Result<Vec<String>> {
+        environment::Host::get_arguments(&mut self.inner)
+    }
+
+    fn initial_cwd(&mut self) -> wasmtime::Result<Option<String>> {
+        environment::Host::initial_cwd(&mut self.inner)
+    }
+}
+
+struct HookedFilesystem;
+
+impl HasData for HookedFilesystem {
+    type Data<'a> = HookedFilesystemView<'a>;
+}
+
+struct HookedFilesystemView<'a> {
+    inner: WasiFilesystemCtxView<'a>,
+    hooks: &'a Preview2Hooks,
+}
+
+impl preopens::Host for HookedFilesystemView<'_> {
+    fn get_directories(&mut self) -> wasmtime::Result<Vec<(Resource<Descriptor>, String)>> {
+        let dirs = preopens::Host::get_directories(&mut self.inner)?;
+        for (dir, guest_path) in &dirs {
+            let host = self
+                .hooks
+                .preopens
+                .iter()
+                .find(|(_, guest)| guest == guest_path);
+            if let Some((host, _)) = host {
+                self.hooks
+                    .files
+                    .add_preopen(dir.rep(), guest_path, host, guest_path == "/data");
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+impl types::Host for HookedFilesystemView<'_> {
+    fn convert_error_code(&mut self, err: FsError) -> wasmtime::Result<ErrorCode> {
+        types::Host::convert_error_code(&mut self.inner, err)
+    }
+
+    fn filesystem_error_code(
+        &mut self,
+        err: Resource<streams::Error>,
+    ) -> wasmtime::Result<Option<ErrorCode>> {
+        types::Host::filesystem_error_code(&mut self.inner, err)
+    }
+}
+
+impl types::HostDescriptor for HookedFilesystemView<'_> {
+    fn open_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: PathFlags,
+        path: String,
+        oflags: OpenFlags,
+        flags: DescriptorFlags,
+    ) -> FsResult<Resource<Descriptor>> {
+        let dir = fd.rep();
+        let opened = types::HostDescriptor::open_at(
+            &mut self.inner,
+            fd,
+            path_flags,
+            path.clone(),
+            oflags,
+            flags,
+        )?;
+        self.hooks.files.opened(dir, &path, opened.rep());
+        Ok(opened)
+    }
+
+    fn read(
+        &mut self,
+        fd: Resource<Descriptor>,
+        len: Filesize,
+        offset: Filesize,
+    ) -> FsResult<(Vec<u8>, bool)> {
+        let rep = fd.rep();
+        let (bytes, end) = types::HostDescriptor::read(&mut self.inner, fd, len, offset)?;
+        self.hooks.files.seeked(rep, offset);
+        self.hooks.files.read(rep, &bytes);
+        Ok((bytes, end))
+    }
+
+    fn read_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<InputStream>> {
+        let rep = fd.rep();
+        let stream = types::HostDescriptor::read_via_stream(&mut self.inner, fd, offset)?;
+        if let Some(host) = self.hooks.files.host_path(rep) {
+            let mut bytes = Vec::new();
+            if let Ok(mut file) = File::open(host)
+                && file.seek(SeekFrom::Start(offset)).is_ok()
+            {
+                let _ = file.take(MAX_STREAM_BYTES).read_to_end(&mut bytes);
+            }
+            self.hooks.files.seeked(rep, offset);
+            self.hooks.files.read(rep, &bytes);
+        }
+        Ok(stream)
+    }
+
+    fn write(
+        &mut self,
+        fd: Resource<Descriptor>,
+        buf: Vec<u8>,
+        offset: Filesize,
+    ) -> FsResult<Filesize> {
+        let rep = fd.rep();
+        let written = types::HostDescriptor::write(&mut self.inner, fd, buf, offset)?;
+        self.hooks.files.seeked(rep, offset);
+        self.hooks.files.written(rep, written);
+        Ok(written)
+    }
+
+    fn unlink_file_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        let dir = fd.rep();
+        types::HostDescriptor::unlink_file_at(&mut self.inner, fd, path.clone())?;
+        self.hooks.files.unlinked(dir, &path);
+        Ok(())
+    }
+
+    fn rename_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path: String,
+        new_fd: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        let (old_dir, new_dir) = (fd.rep(), new_fd.rep());
+        types::HostDescriptor::rename_at(
+            &mut self.inner,
+            fd,
+            old_path.clone(),
+            new_fd,
+            new_path.clone(),
+        )?;
+        self.hooks
+            .files
+            .renamed(old_dir, &old_path, new_dir, &new_path);
+        Ok(())
+    }
+
+    fn drop(&mut self, fd: Resource<Descriptor>) -> wasmtime::Result<()> {
+        self.hooks.files.closed(fd.rep());
+        types::HostDescriptor::drop(&mut self.inner, fd)
+    }
+
+    // everything below passes straight through
+
+    fn advise(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+        len: Filesize,
+        advice: Advice,
+    ) -> FsResult<()> {
+        types::HostDescriptor::advise(&mut self.inner, fd, offset, len, advice)
+    }
+
+    fn sync_data(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        types::HostDescriptor::sync_data(&mut self.inner, fd)
+    }
+
+    fn get_flags(&mut self, fd: Resource<Descriptor>) -> FsResult<DescriptorFlags> {
+        types::HostDescriptor::get_flags(&mut self.inner, fd)
+    }
+
+    fn get_type(&mut self, fd: Resource<Descriptor>) -> FsResult<DescriptorType> {
+        types::HostDescriptor::get_type(&mut self.inner, fd)
+    }
+
+    fn set_size(&mut self, fd: Resource<Descriptor>, size: Filesize) -> FsResult<()> {
+        types::HostDescriptor::set_size(&mut self.inner, fd, size)
+    }
+
+    fn set_times(
+        &mut self,
+        fd: Resource<Descriptor>,
+        atim: NewTimestamp,
+        mtim: NewTimestamp,
+    ) -> FsResult<()> {
+        types::HostDescriptor::set_times(&mut self.inner, fd, atim, mtim)
+    }
+
+    fn read_directory(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DirectoryEntryStream>> {
+        types::HostDescriptor::read_directory(&mut self.inner, fd)
+    }
+
+    fn sync(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        types::HostDescriptor::sync(&mut self.inner, fd)
+    }
+
+    fn create_directory_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        types::HostDescriptor::create_directory_at(&mut self.inner, fd, path)
+    }
+
+    fn stat(&mut self, fd: Resource<Descriptor>) -> FsResult<DescriptorStat> {
+        types::HostDescriptor::stat(&mut self.inner, fd)
+    }
+
+    fn stat_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: PathFlags,
+        path: String,
+    ) -> FsResult<DescriptorStat> {
+        types::HostDescriptor::stat_at(&mut self.inner, fd, path_flags, path)
+    }
+
+    fn set_times_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: PathFlags,
+        path: String,
+        atim: NewTimestamp,
+        mtim: NewTimestamp,
+    ) -> FsResult<()> {
+        types::HostDescriptor::set_times_at(&mut self.inner, fd, path_flags, path, atim, mtim)
+    }
+
+    fn link_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path_flags: PathFlags,
+        old_path: String,
+        new_descriptor: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        types::HostDescriptor::link_at(
+            &mut self.inner,
+            fd,
+            old_path_flags,
+            old_path,
+            new_descriptor,
+            new_path,
+        )
+    }
+
+    fn readlink_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<String> {
+        types::HostDescriptor::readlink_at(&mut self.inner, fd, path)
+    }
+
+    fn remove_directory_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        types::HostDescriptor::remove_directory_at(&mut self.inner, fd, path)
+    }
+
+    fn symlink_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        src_path: String,
+        dest_path: String,
+    ) -> FsResult<()> {
+        types::HostDescriptor::symlink_at(&mut self.inner, fd, src_path, dest_path)
+    }
+
+    fn write_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<OutputStream>> {
+        types::HostDescriptor::write_via_stream(&mut self.inner, fd, offset)
+    }
+
+    fn append_via_stream(&mut self, fd: Resource<Descriptor>) -> FsResult<Resource<OutputStream>> {
+        types::HostDescriptor::append_via_stream(&mut self.inner, fd)
+    }
+
+    fn is_same_object(
+        &mut self,
+        a: Resource<Descriptor>,
+        b: Resource<Descriptor>,
+    ) -> wasmtime::Result<bool> {
+        types::HostDescriptor::is_same_object(&mut self.inner, a, b)
+    }
+
+    fn metadata_hash(&mut self, fd: Resource<Descriptor>) -> FsResult<MetadataHashValue> {
+        types::HostDescriptor::metadata_hash(&mut self.inner, fd)
+    }
+
+    fn metadata_hash_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: PathFlags,
+        path: String,
+    ) -> FsResult<MetadataHashValue> {
+        types::HostDescriptor::metadata_hash_at(&mut self.inner, fd, path_flags, path)
+    }
+}
+
+impl types::HostDirectoryEntryStream for HookedFilesystemView<'_> {
+    fn read_directory_entry(
+        &mut self,
+        stream: Resource<DirectoryEntryStream>,
+    ) -> FsResult<Option<DirectoryEntry>> {
+        types::HostDirectoryEntryStream::read_directory_entry(&mut self.inner, stream)
+    }
+
+    fn drop(&mut self, stream: Resource<DirectoryEntryStream>) -> wasmtime::Result<()> {
+        types::HostDirectoryEntryStream::drop(&mut self.inner, stream)
+    }
+}
@@ -1,6 +1,13 @@
+pub(crate) mod guest;
+pub mod instrument;
+pub mod instrument_p2;
 pub mod preview1;
-use anyhow::Result;
-use wasmtime::Linker;
+pub mod preview2;
+use anyhow::{Result, bail};
+use wasmtime::component::Linker as ComponentLinker;
+use wasmtime::{Linker, Store};
+
+use crate::sandbox::limits::GuestState;
 
 /// WASI runtime adapter abstraction.
 /// This isolates ABI differences (preview1 / preview2).
@@ -15,7 +22,24 @@ pub trait WasiRuntime {
 
     fn build_ctx(&self) -> Result<Self::Ctx>;
 
-    fn add_to_linker(&self, linker: &mut Linker<Self::Ctx>) -> Result<()>;
+    fn add_to_linker(&self, linker: &mut Linker<GuestState<Self::Ctx>>) -> Result<()>;
+
+    /// link WASI for components (wasi:cli/run); only preview2 supports this
+    fn add_to_component_linker(
+        &self,
+        _linker: &mut ComponentLinker<GuestState<Self::Ctx>>,
+    ) -> Result<()> {
+        bail!("this WASI runtime cannot run components")
+    }
+
+    /// wrap host calls once a store exists (e.g. for taint tracking); no-op by default
+    fn instrument(
+        &self,
+        _linker: &mut Linker<GuestState<Self::Ctx>>,
+        _store: &mut Store<GuestState<Self::Ctx>>,
+    ) -> Result<()> {
+        Ok(())
+    }
 
     /// collect execution outputs
     fn take_io(&self) -> Result<WasiExecutionIO>;
@@ -1,24 +1,23 @@
 use std::collections::HashMap;
-use std::future::Future;
-use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use anyhow::Result;
-use serde_json::json;
-use wasmtime::Linker;
+use wasmtime::{Linker, Store};
 
-use crate::collect::NetworkCollector;
-use crate::monitor::event::{MonitorEvent, MonitorEventKind};
+use crate::collect::{EnvCollector, FileCollector, NetworkCollector};
+use crate::monitor::event::MonitorEvent;
+use crate::sandbox::limits::GuestState;
+use crate::sandbox::network_policy::NetworkPolicy;
+use crate::taint::tracker::{TaintTracker, TaintedWrite};
 
 use wasmtime_wasi::WasiCtxBuilder;
-use wasmtime_wasi::filesystem::{DirPerms, FilePerms};
 use wasmtime_wasi::p1::{self, WasiP1Ctx};
-use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
-use wasmtime_wasi::sockets::SocketAddrUse;
+use wasmtime_wasi::p2::pipe::MemoryOutputPipe;
 
+use super::guest::{GuestSetup, preopen_dirs};
+use super::instrument::{Preview1Hooks, instrument_preview1};
 use super::{WasiExecutionIO, WasiRuntime};
 
 /// Preview1 (_start-based) WASI runtime adapter
@@ -30,12 +29,16 @@ pub struct WasiPreview1 {
     pub args: Vec<String>,
     pub max_output_bytes: usize,
     pub stdin_input: Option<Vec<u8>>,
+    pub network_policy: NetworkPolicy,
 
     stdout: MemoryOutputPipe,
     stderr: MemoryOutputPipe,
     start: Mutex<Option<Instant>>,
     monitor_events: Mutex<Vec<MonitorEvent>>,
     network_collector: Arc<NetworkCollector>,
+    taint_tracker: Arc<TaintTracker>,
+    file_collector: Arc<FileCollector>,
+    env_collector: Arc<EnvCollector>,
 }
 
 impl WasiPreview1 {
@@ -63,11 +66,15 @@ impl WasiPreview1 {
             args,
             max_output_bytes,
             stdin_input: None,
+            network_policy: NetworkPolicy::deny_all(),
             stdout: MemoryOutputPipe::new(max_output_bytes),
             stderr: MemoryOutputPipe::new(max_output_bytes),
             start: Mutex::new(None),
             monitor_events: Mutex::new(Vec::new()),
             network_collector: Arc::new(NetworkCollector::new()),
+            taint_tracker: Arc::new(TaintTracker::new()),
+            file_collector: Arc::new(FileCollector::new()),
+            env_collector: Arc::new(EnvCollector::new()),
         }
     }
 
@@ -78,6 +85,19 @@ impl WasiPreview1 {
     pub fn network_collector(&self) -> Arc<NetworkCollector> {
         Arc::clone(&self.network_collector)
     }
+
+    pub fn file_collector(&self) -> Arc<FileCollector> {
+        Arc::clone(&self.file_collector)
+    }
+
+    pub fn env_collector(&self) -> Arc<EnvCollector> {
+        Arc::clone(&self.env_collector)
+    }
+
+    /// guest writes that carried bytes labelled by environ_get / fd_read
+    pub fn take_tainted_writes(&self) -> Vec<TaintedWrite> {
+        self.taint_tracker.take_writes()
+    }
 }
 
 impl WasiRuntime for WasiPreview1 {
@@ -87,142 +107,57 @@ impl WasiRuntime for WasiPreview1 {
         *self.start.lock().unwrap() = Some(Instant::now());
         let mut monitor_events = self.monitor_events.lock().unwrap();
         monitor_events.clear();
+        self.taint_tracker.reset();
+        self.file_collector.reset();
+        self.env_collector.reset();
 
         let mut builder = WasiCtxBuilder::new();
-
-        if let Some(bytes) = &self.stdin_input {
-            builder.stdin(MemoryInputPipe::new(bytes.clone()));
-            monitor_events.push(MonitorEvent {
-                kind: MonitorEventKind::CapabilityGranted,
-                actor: "wasi-runtime".to_string(),
-                target: Some("stdin".to_string()),
-                evidence: json!({
-                    "capability": "stdio",
-                    "stream": "stdin",
-                    "bytes_len": bytes.len()
-                }),
-            });
+        let preopens = preopen_dirs(&self.guest_root, &self.work_dir, &self.data_dir);
+        GuestSetup {
+            env: &self.env,
+            args: &self.args,
+            stdin_input: self.stdin_input.as_deref(),
+            stdout: &self.stdout,
+            stderr: &self.stderr,
+            max_output_bytes: self.max_output_bytes,
+            preopens: &preopens,
+            network_collector: &self.network_collector,
+            network_policy: &self.network_policy,
         }
-
-        builder.stdout(self.stdout.clone());
-        monitor_events.push(MonitorEvent {
-            kind: MonitorEventKind::CapabilityGranted,
-            actor: "wasi-runtime".to_string(),
-            target: Some("stdout".to_string()),
-            evidence: json!({
-                "capability": "stdio",
-                "stream": "stdout",
-                "max_output_bytes": self.max_output_bytes
-            }),
-        });
-
-        builder.stderr(self.stderr.clone());
-        monitor_events.push(MonitorEvent {
-            kind: MonitorEventKind::CapabilityGranted,
-            actor: "wasi-runtime".to_string(),
-            target: Some("stderr".to_string()),
-            evidence: json!({
-                "capability": "stdio",
-                "stream": "stderr",
-                "max_output_bytes": self.max_output_bytes
-            }),
-        });
-
-        for (k, v) in &self.env {
-            builder.env(k, v);
-            monitor_events.push(MonitorEvent {
-                kind: MonitorEventKind::CapabilityGranted,
-                actor: "wasi-runtime".to_string(),
-                target: Some(k.clone()),
-                evidence: json!({
-                    "capability": "env",
-                    "key": k,
-                    "value_len": v.len()
-                }),
-            });
-        }
-
-        for arg in &self.args {
-            builder.arg(arg);
+        .apply(&mut builder, &mut monitor_events)?;
+
+        // preview1 numbers preopens from fd 3 in the order they are added
+        for (index, (dir, guest_path)) in preopens.iter().enumerate() {
+            self.file_collector.add_preopen(
+                3 + index as u32,
+                guest_path,
+                dir,
+                *guest_path == "/data",
+            );
         }
 
-        if let Some(dir) = &self.guest_root {
-            builder.preopened_dir(dir, "/", DirPerms::all(), FilePerms::all())?;
-            monitor_events.push(MonitorEvent {
-                kind: MonitorEventKind::CapabilityGranted,
-                actor: "wasi-runtime".to_string(),
-                target: Some("/".to_string()),
-                evidence: json!({
-                    "capability": "filesystem-preopen",
-                    "guest_path": "/",
-                    "host_path": dir,
-                    "dir_perms": "all",
-                    "file_perms": "all"
-                }),
-            });
-        }
-
-        if let Some(dir) = &self.work_dir {
-            builder.preopened_dir(dir, "/work", DirPerms::all(), FilePerms::all())?;
-            monitor_events.push(MonitorEvent {
-                kind: MonitorEventKind::CapabilityGranted,
-                actor: "wasi-runtime".to_string(),
-                target: Some("/work".to_string()),
-                evidence: json!({
-                    "capability": "filesystem-preopen",
-                    "guest_path": "/work",
-                    "host_path": dir,
-                    "dir_perms": "all",
-                    "file_perms": "all"
-                }),
-            });
-        }
-
-        if let Some(dir) = &self.data_dir {
-            builder.preopened_dir(dir, "/data", DirPerms::all(), FilePerms::all())?;
-            monitor_events.push(MonitorEvent {
-                kind: MonitorEventKind::CapabilityGranted,
-                actor: "wasi-runtime".to_string(),
-                target: Some("/data".to_string()),
-                evidence: json!({
-                    "capability": "filesystem-preopen",
-                    "guest_path": "/data",
-                    "host_path": dir,
-                    "dir_perms": "all",
-                    "file_perms": "all"
-                }),
-            });
-        }
-
-        let network_collector = Arc::clone(&self.network_collector);
-        builder.allow_tcp(true);
-        builder.allow_udp(true);
-        builder.socket_addr_check(move |addr: SocketAddr, use_case: SocketAddrUse| {
-            let collector = Arc::clone(&network_collector);
-            let allowed = false;
-            collector.record_socket_attempt(addr, use_case, allowed);
-            Box::pin(async move { allowed }) as Pin<Box<dyn Future<Output = bool> + Send + Sync>>
-        });
-        monitor_events.push(MonitorEvent {
-            kind: MonitorEventKind::CapabilityGranted,
-            actor: "wasi-runtime".to_string(),
-            target: Some("network-monitor".to_string()),
-            evidence: json!({
-                "capability": "network-monitor",
-                "policy": "deny-by-default",
-                "tcp": true,
-                "udp": true
-            }),
-        });
-
         Ok(builder.build_p1())
     }
 
-    fn add_to_linker(&self, linker: &mut Linker<Self::Ctx>) -> Result<()> {
-        p1::add_to_linker_sync(linker, |ctx: &mut WasiP1Ctx| ctx)?;
+    fn add_to_linker(&self, linker: &mut Linker<GuestState<Self::Ctx>>) -> Result<()> {
+        p1::add_to_linker_sync(linker, |state: &mut GuestState<WasiP1Ctx>| &mut state.ctx)?;
         Ok(())
     }
 
+    fn instrument(
+        &self,
+        linker: &mut Linker<GuestState<Self::Ctx>>,
+        store: &mut Store<GuestState<Self::Ctx>>,
+    ) -> Result<()> {
+        let hooks = Preview1Hooks {
+            tracker: Arc::clone(&self.taint_tracker),
+            files: Arc::clone(&self.file_collector),
+            env: Arc::clone(&self.env_collector),
+            env_count: self.env.len(),
+        };
+        instrument_preview1(linker, store, hooks)
+    }
+
     fn take_io(&self) -> anyhow::Result<WasiExecutionIO> {
         let duration_ms = self
             .start
@@ -253,6 +188,7 @@ mod tests {
     use std::fs;
 
     use super::*;
+    use crate::monitor::event::MonitorEventKind;
 
     #[test]
     fn records_wasi_capability_grants() {
@@ -286,4 +222,82 @@ mod tests {
                 && event.target.as_deref() == Some("network-monitor")
         }));
     }
+
+    #[test]
+    fn tracks_env_secret_into_stdout_write() {
+        use crate::sandbox::wasm_runner::WasmRunner;
+        use crate::taint::tracker::TaintEvidence;
+
+        let wasm =
+            fs::read("fixtures/tool_return_secret_tool/tool.wasm").expect("read fixture wasm");
+
+        let mut env = HashMap::new();
+        env.insert(
+            "DEMO_SECRET".to_string(),
+            "SEKRET_0123456789abcdef".to_string(),
+        );
+        env.insert("UNUSED".to_string(), "not-written-anywhere".to_string());
+
+        let runtime = WasiPreview1::new(env, None, 4096);
+        let exec = WasmRunner::default().run(&wasm, &runtime).unwrap();
+        assert_eq!(exec.exit_code, 0);
+
+        // environ_get hands the guest both, whether or not it uses them
+        let granted = runtime.env_collector().granted_keys();
+        assert!(granted.contains("DEMO_SECRET") && granted.contains("UNUSED"));
+        let writes = runtime.take_tainted_writes();
+        assert!(writes.iter().any(|w| {
+            w.fd == 1
+                && w.evidence == TaintEvidence::Copy
+                && w.data.value.contains("SEKRET_0123456789abcdef")
+                && w.data.sources[0].short_id() == "EnvVar: DEMO_SECRET"
+        }));
+        assert!(
+            writes
+                .iter()
+                .all(|w| w.data.sources[0].short_id() != "EnvVar: UNUSED")
+        );
+    }
+
+    #[test]
+    fn only_files_the_guest_read_become_sources() {
+        use crate::sandbox::wasm_runner::WasmRunner;
+
+        let wasm =
+            fs::read("fixtures/tool_return_file_exfil/tool.wasm").expect("read fixture wasm");
+        let data_dir = std::env::temp_dir().join(format!(
+            "mcp-sandboxscan-file-access-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("secret.txt"), "planted-secret-value\n").unwrap();
+        fs::write(data_dir.join("untouched.txt"), "never-opened\n").unwrap();
+
+        let mut env = HashMap::new();
+        env.insert("NEVER_QUERIED".to_string(), "granted-only".to_string());
+
+        let runtime = WasiPreview1::new(env, Some(data_dir.clone()), 4096);
+        let exec = WasmRunner::default().run(&wasm, &runtime).unwrap();
+        let collector = runtime.file_collector();
+        let sources = collector.as_taint_sources();
+        let events = collector.as_monitor_events();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(exec.exit_code, 0);
+        assert!(runtime.env_collector().granted_keys().is_empty());
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0].short_id().ends_with("secret.txt"));
+        assert_eq!(sources[0].content(), "planted-secret-value\n");
+        assert!(events.iter().any(|event| {
+            event.kind == MonitorEventKind::FileRead
+                && event.target.as_deref() == Some("/data/secret.txt")
+                && event.evidence["offset"] == 0
+                && event.evidence["bytes"] == 21
+        }));
+        assert!(
+            events
+                .iter()
+                .all(|event| event.target.as_deref() != Some("/data/untouched.txt"))
+        );
+    }
 }
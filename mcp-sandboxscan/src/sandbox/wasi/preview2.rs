@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+use wasmtime::Linker;
+use wasmtime::component::{Linker as ComponentLinker, ResourceTable};
+use wasmtime_wasi::p2::pipe::MemoryOutputPipe;
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
+
+use crate::collect::{EnvCollector, FileCollector, NetworkCollector};
+use crate::monitor::event::MonitorEvent;
+use crate::sandbox::limits::GuestState;
+use crate::sandbox::network_policy::NetworkPolicy;
+
+use super::guest::{GuestSetup, preopen_dirs};
+use super::instrument_p2::{Preview2Hooks, instrument_preview2};
+use super::{WasiExecutionIO, WasiRuntime};
+
+/// store data for a component: the WASI context plus its resource table
+pub struct WasiPreview2Ctx {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    hooks: Preview2Hooks,
+}
+
+impl WasiPreview2Ctx {
+    // split borrow for the hooked host calls
+    pub(crate) fn hooked(&mut self) -> (&mut WasiCtx, &mut ResourceTable, &Preview2Hooks) {
+        (&mut self.ctx, &mut self.table, &self.hooks)
+    }
+}
+
+impl WasiView for WasiPreview2Ctx {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView {
+            ctx: &mut self.ctx,
+            table: &mut self.table,
+        }
+    }
+}
+
+/// Preview2 (wasi:cli/run component) WASI runtime adapter.
+/// Grants and network policy match WasiPreview1; filesystem and environment
+/// host calls feed the same collectors, without byte-level taint tracking.
+pub struct WasiPreview2 {
+    pub env: HashMap<String, String>,
+    pub data_dir: Option<PathBuf>,
+    pub work_dir: Option<PathBuf>,
+    pub guest_root: Option<PathBuf>,
+    pub args: Vec<String>,
+    pub max_output_bytes: usize,
+    pub stdin_input: Option<Vec<u8>>,
+    pub network_policy: NetworkPolicy,
+
+    stdout: MemoryOutputPipe,
+    stderr: MemoryOutputPipe,
+    start: Mutex<Option<Instant>>,
+    monitor_events: Mutex<Vec<MonitorEvent>>,
+    network_collector: Arc<NetworkCollector>,
+    file_collector: Arc<FileCollector>,
+    env_collector: Arc<EnvCollector>,
+}
+
+impl WasiPreview2 {
+    pub fn new(
+        env: HashMap<String, String>,
+        data_dir: Option<PathBuf>,
+        max_output_bytes: usize,
+    ) -> Self {
+        Self::new_with_args(env, data_dir, None, None, Vec::new(), max_output_bytes)
+    }
+
+    pub fn new_with_args(
+        env: HashMap<String, String>,
+        data_dir: Option<PathBuf>,
+        work_dir: Option<PathBuf>,
+        guest_root: Option<PathBuf>,
+        args: Vec<String>,
+        max_output_bytes: usize,
+    ) -> Self {
+        Self {
+            env,
+            data_dir,
+            work_dir,
+            guest_root,
+            args,
+            max_output_bytes,
+            stdin_input: None,
+            network_policy: NetworkPolicy::deny_all(),
+            stdout: MemoryOutputPipe::new(max_output_bytes),
+            stderr: MemoryOutputPipe::new(max_output_bytes),
+            start: Mutex::new(None),
+            monitor_events: Mutex::new(Vec::new()),
+            network_collector: Arc::new(NetworkCollector::new()),
+            file_collector: Arc::new(FileCollector::new()),
+            env_collector: Arc::new(EnvCollector::new()),
+        }
+    }
+
+    pub fn take_monitor_events(&self) -> Vec<MonitorEvent> {
+        std::mem::take(&mut *self.monitor_events.lock().unwrap())
+    }
+
+    pub fn network_collector(&self) -> Arc<NetworkCollector> {
+        Arc::clone(&self.network_collector)
+    }
+
+    pub fn file_collector(&self) -> Arc<FileCollector> {
+        Arc::clone(&self.file_collector)
+    }
+
+    pub fn env_collector(&self) -> Arc<EnvCollector> {
+        Arc::clone(&self.env_collector)
+    }
+}
+
+impl WasiRuntime for WasiPreview2 {
+    type Ctx = WasiPreview2Ctx;
+
+    fn build_ctx(&self) -> Result<Self::Ctx> {
+        *self.start.lock().unwrap() = Some(Instant::now());
+        let mut monitor_events = self.monitor_events.lock().unwrap();
+        monitor_events.clear();
+        self.file_collector.reset();
+        self.env_collector.reset();
+
+        let mut builder = WasiCtxBuilder::new();
+        let preopens = preopen_dirs(&self.guest_root, &self.work_dir, &self.data_dir);
+        GuestSetup {
+            env: &self.env,
+            args: &self.args,
+            stdin_input: self.stdin_input.as_deref(),
+            stdout: &self.stdout,
+            stderr: &self.stderr,
+            max_output_bytes: self.max_output_bytes,
+            preopens: &preopens,
+            network_collector: &self.network_collector,
+            network_policy: &self.network_policy,
+        }
+        .apply(&mut builder, &mut monitor_events)?;
+
+        Ok(WasiPreview2Ctx {
+            ctx: builder.build(),
+            table: ResourceTable::new(),
+            hooks: Preview2Hooks {
+                files: Arc::clone(&self.file_collector),
+                env: Arc::clone(&self.env_collector),
+                preopens: preopens
+                    .iter()
+                    .map(|(dir, guest)| (dir.to_path_buf(), guest.to_string()))
+                    .collect(),
+            },
+        })
+    }
+
+    fn add_to_linker(&self, _linker: &mut Linker<GuestState<Self::Ctx>>) -> Result<()> {
+        bail!("preview2 runtime only runs components; use WasiPreview1 for core modules")
+    }
+
+    fn add_to_component_linker(
+        &self,
+        linker: &mut ComponentLinker<GuestState<Self::Ctx>>,
+    ) -> Result<()> {
+        wasmtime_wasi::p2::add_to_linker_sync(linker)
+            .map_err(|err| anyhow::anyhow!("failed to link WASI preview2: {err}"))?;
+        instrument_preview2(linker)
+    }
+
+    fn take_io(&self) -> Result<WasiExecutionIO> {
+        let duration_ms = self
+            .start
+            .lock()
+            .unwrap()
+            .take()
+            .map(|t| t.elapsed().as_millis())
+            .unwrap_or(0);
+
+        let mut stdout = self.stdout.contents().to_vec();
+        let mut stderr = self.stderr.contents().to_vec();
+
+        stdout.truncate(self.max_output_bytes);
+        stderr.truncate(self.max_output_bytes);
+
+        Ok(WasiExecutionIO {
+            stdout,
+            stderr,
+            duration_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::monitor::event::MonitorEventKind;
+    use crate::sandbox::wasm_runner::{WasmRunner, is_component};
+
+    // smallest wasi:cli/run component: run() returns ok or err without any imports
+    fn run_component(ok: bool) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(component
+                (core module $m
+                    (func (export "run") (result i32) i32.const {}))
+                (core instance $i (instantiate $m))
+                (func $run (result (result)) (canon lift (core func $i "run")))
+                (instance $cli (export "run" (func $run)))
+                (export "wasi:cli/run@0.2.0" (instance $cli)))"#,
+            if ok { 0 } else { 1 }
+        ))
+        .expect("valid component text")
+    }
+
+    #[test]
+    fn detects_components_by_header() {
+        let module = std::fs::read("fixtures/benign_tool/tool.wasm").expect("read fixture wasm");
+        assert!(!is_component(&module));
+        assert!(is_component(&run_component(true)));
+    }
+
+    #[test]
+    fn runs_cli_component_and_records_grants() {
+        let mut env = HashMap::new();
+        env.insert("DEMO_SECRET".to_string(), "secret-value".to_string());
+        let runtime = WasiPreview2::new(env, None, 1024);
+
+        let ok = WasmRunner::default()
+            .run_component(&run_component(true), &runtime)
+            .unwrap();
+        assert_eq!(ok.exit_code, 0);
+
+        let events = runtime.take_monitor_events();
+        assert!(events.iter().any(|event| {
+            event.kind == MonitorEventKind::CapabilityGranted
+                && event.target.as_deref() == Some("DEMO_SECRET")
+        }));
+        assert!(events.iter().any(|event| {
+            event.kind == MonitorEventKind::CapabilityGranted
+                && event.target.as_deref() == Some("network-monitor")
+        }));
+
+        let err = WasmRunner::default()
+            .run_component(&run_component(false), &runtime)
+            .unwrap();
+        assert_eq!(err.exit_code, 1);
+    }
+
+    const ERROR_CODES: &str = "\"access\" \"would-block\" \"already\" \"bad-descriptor\" \"busy\" \"deadlock\" \
+        \"quota\" \"exist\" \"file-too-large\" \"illegal-byte-sequence\" \"in-progress\" \
+        \"interrupted\" \"invalid\" \"io\" \"is-directory\" \"loop\" \"too-many-links\" \
+        \"message-size\" \"name-too-long\" \"no-device\" \"no-entry\" \"no-lock\" \
+        \"insufficient-memory\" \"insufficient-space\" \"not-directory\" \"not-empty\" \
+        \"not-recoverable\" \"unsupported\" \"no-tty\" \"no-such-device\" \"overflow\" \
+        \"not-permitted\" \"pipe\" \"read-only\" \"invalid-seek\" \"text-file-busy\" \
+        \"cross-device\"";
+
+    // reads the environment, then /data/secret.txt through the first preopen
+    const READS_ENV_AND_FILE: &str = r#"(component $c
+        (import "wasi:cli/environment@0.2.0" (instance $env
+            (export "get-environment" (func (result (list (tuple string string)))))))
+        (import "wasi:filesystem/types@0.2.0" (instance $types
+            (export "descriptor" (type $d (sub resource)))
+            (type $ec' (enum ERRORS))
+            (export "error-code" (type $ec (eq $ec')))
+            (type $pf' (flags "symlink-follow"))
+            (export "path-flags" (type $pf (eq $pf')))
+            (type $of' (flags "create" "directory" "exclusive" "truncate"))
+            (export "open-flags" (type $of (eq $of')))
+            (type $df' (flags "read" "write" "file-integrity-sync" "data-integrity-sync"
+                "requested-write-sync" "mutate-directory"))
+            (export "descriptor-flags" (type $df (eq $df')))
+            (export "[method]descriptor.open-at" (func (param "self" (borrow $d))
+                (param "path-flags" $pf) (param "path" string) (param "open-flags" $of)
+                (param "flags" $df) (result (result (own $d) (error $ec)))))
+            (export "[method]descriptor.read" (func (param "self" (borrow $d))
+                (param "length" u64) (param "offset" u64)
+                (result (result (tuple (list u8) bool) (error $ec)))))))
+        (alias export $types "descriptor" (type $descriptor))
+        (import "wasi:filesystem/preopens@0.2.0" (instance $preopens
+            (alias outer $c $descriptor (type $d'))
+            (export "descriptor" (type $d (eq $d')))
+            (export "get-directories" (func (result (list (tuple (own $d) string)))))))
+
+        (core module $libc
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 4096))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                (local $at i32)
+                global.get $next
+                local.set $at
+                ;; every block is rounded to 8 bytes, so every result is aligned
+                global.get $next
+                local.get 3
+                i32.const 7
+                i32.add
+                i32.const -8
+                i32.and
+                i32.add
+                global.set $next
+                local.get $at))
+        (core instance $libc (instantiate $libc))
+        (core func $get_env (canon lower (func $env "get-environment")
+            (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+        (core func $get_dirs (canon lower (func $preopens "get-directories")
+            (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+        (core func $open_at (canon lower (func $types "[method]descriptor.open-at")
+            (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+        (core func $read (canon lower (func $types "[method]descriptor.read")
+            (memory (core memory $libc "memory")) (realloc (core func $libc "realloc"))))
+
+        (core module $m
+            (import "libc" "memory" (memory 1))
+            (import "wasi" "get-environment" (func $get_env (param i32)))
+            (import "wasi" "get-directories" (func $get_dirs (param i32)))
+            (import "wasi" "open-at" (func $open_at (param i32 i32 i32 i32 i32 i32 i32)))
+            (import "wasi" "read" (func $read (param i32 i64 i64 i32)))
+            (data (i32.const 512) "secret.txt")
+            (func (export "run") (result i32)
+                (call $get_env (i32.const 256))
+                (call $get_dirs (i32.const 272))
+                ;; first preopen's handle, "secret.txt", no flags, read
+                (call $open_at (i32.load (i32.load (i32.const 272)))
+                    (i32.const 0) (i32.const 512) (i32.const 10)
+                    (i32.const 0) (i32.const 1) (i32.const 288))
+                (if (i32.load8_u (i32.const 288)) (then (return (i32.const 1))))
+                (call $read (i32.load (i32.const 292)) (i64.const 64) (i64.const 0) (i32.const 304))
+                (i32.load8_u (i32.const 304))))
+        (core instance $i (instantiate $m
+            (with "libc" (instance $libc))
+            (with "wasi" (instance
+                (export "get-environment" (func $get_env))
+                (export "get-directories" (func $get_dirs))
+                (export "open-at" (func $open_at))
+                (export "read" (func $read))))))
+        (func $run (result (result)) (canon lift (core func $i "run")))
+        (instance $cli (export "run" (func $run)))
+        (export "wasi:cli/run@0.2.0" (instance $cli)))"#;
+
+    #[test]
+    fn observes_env_and_file_reads_of_components() {
+        let data_dir =
+            std::env::temp_dir().join(format!("mcp-sandboxscan-p2-data-{}", std::process::id()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(data_dir.join("secret.txt"), "top-secret").unwrap();
+        std::fs::write(data_dir.join("untouched.txt"), "never read").unwrap();
+        let mut env = HashMap::new();
+        env.insert("DEMO_SECRET".to_string(), "secret-value".to_string());
+
+        let component = wat::parse_str(READS_ENV_AND_FILE.replace("ERRORS", ERROR_CODES))
+            .expect("valid component text");
+        let runtime = WasiPreview2::new(env, Some(data_dir.clone()), 1024);
+        let exec = WasmRunner::default()
+            .run_component(&component, &runtime)
+            .unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(exec.exit_code, 0);
+        assert!(
+            runtime
+                .env_collector()
+                .granted_keys()
+                .contains("DEMO_SECRET")
+        );
+        let sources = runtime.file_collector().as_taint_sources();
+        assert_eq!(sources.len(), 1);
+        assert!(matches!(
+            &sources[0],
+            crate::taint::source::TaintSource::FileRead { path, content }
+                if path.ends_with("secret.txt") && content == "top-secret"
+        ));
+    }
+}
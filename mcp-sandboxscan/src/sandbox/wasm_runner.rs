@@ -1,48 +1,144 @@
+use std::sync::mpsc;
+use std::thread;
+
 use anyhow::{Result, anyhow};
-use wasmtime::{Engine, Error, Instance, Linker, Module, Store};
+use wasmtime::component::{Component, Linker as ComponentLinker};
+use wasmtime::{Config, Engine, Error, Linker, Module, Store, Trap, WasmBacktrace};
 
 use wasmtime_wasi::I32Exit;
+use wasmtime_wasi::p2::bindings::sync::Command;
 
+use crate::sandbox::exec_evidence::{TerminationError, TerminationReason};
 use crate::sandbox::exec_result::WasmExecResult;
+use crate::sandbox::limits::{GuestLimiter, GuestState, LimitKind, SandboxLimits};
 use crate::sandbox::wasi::WasiRuntime;
 
 // 1) provide wastime enginer (for compile and initialization)
 // 2) provide run() -- run wasm bytes and collect result (stdout/stderr/exit_code/duration）
 pub struct WasmRunner {
     engine: Engine,
+    limits: SandboxLimits,
 }
 
 impl Default for WasmRunner {
     fn default() -> Self {
-        Self {
-            // default enginer configuration
-            engine: Engine::default(),
-        }
+        Self::new(SandboxLimits::none())
     }
 }
 
-/// output wastime execution error as exit code
-/// - if it is exit(n) from WASI, wasmtime will use I32Exit, extract n
-/// - otherwise, it will be trap / crash / violation, use -1
-fn error_to_exit_code(err: &Error) -> i32 {
+/// classify how a wasmtime execution error ended the guest
+/// - exit(n) from WASI surfaces as I32Exit, extract n
+/// - fuel / memory / table / instance caps become ResourceLimit, epoch interrupts Timeout
+/// - anything else is a trap (with wasmtime's trap code) or a host error
+fn error_termination(
+    err: &Error,
+    hit: Option<LimitKind>,
+    limits: &SandboxLimits,
+) -> TerminationReason {
     if let Some(exit) = err.downcast_ref::<I32Exit>() {
-        exit.0
-    } else {
-        -1 // real trap / crash / violation
+        return match exit.0 {
+            0 => TerminationReason::Normal,
+            code => TerminationReason::Exit { code },
+        };
+    }
+    if let Some(termination) = limit_termination(err, hit, limits) {
+        return termination;
     }
+    TerminationReason::Trap {
+        code: err.downcast_ref::<Trap>().map(|trap| format!("{trap:?}")),
+        message: format!("{err:#}"),
+        backtrace: err
+            .downcast_ref::<WasmBacktrace>()
+            .map(|backtrace| backtrace.to_string()),
+    }
+}
+
+/// map an execution error to the sandbox limit that caused it, if any
+fn limit_termination(
+    err: &Error,
+    hit: Option<LimitKind>,
+    limits: &SandboxLimits,
+) -> Option<TerminationReason> {
+    let message = format!("{err:#}");
+    let limit = match err.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => LimitKind::Fuel,
+        Some(Trap::Interrupt) => {
+            return Some(TerminationReason::Timeout {
+                after_ms: limits.wall_clock.unwrap_or_default().as_millis(),
+            });
+        }
+        _ => match hit {
+            Some(limit) => limit,
+            // instance / table counts are checked by wasmtime itself at instantiation
+            None if message.contains("instance count too high") => LimitKind::Instances,
+            None if message.contains("table count too high") => LimitKind::Tables,
+            None => return None,
+        },
+    };
+    Some(TerminationReason::ResourceLimit {
+        limit,
+        detail: message,
+    })
+}
+
+/// bumps the engine epoch once the wall-clock budget is spent; dropping it
+/// before then cancels the timer
+struct Watchdog {
+    _cancel: mpsc::Sender<()>,
 }
 
-/// use result of _start as exit code
-/// - normal => 0
-/// - error => corresponding exit code
-fn decode_exit(result: std::result::Result<(), Error>) -> i32 {
-    match result {
-        Ok(()) => 0,
-        Err(e) => error_to_exit_code(&e),
+impl Watchdog {
+    fn start(engine: &Engine, limits: &SandboxLimits) -> Option<Self> {
+        let timeout = limits.wall_clock?;
+        let engine = engine.clone();
+        let (cancel, cancelled) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = cancelled.recv_timeout(timeout) {
+                engine.increment_epoch();
+            }
+        });
+        Some(Self { _cancel: cancel })
     }
 }
 
+/// components share the `\0asm` magic with core modules but carry layer 1 in
+/// the upper half of the version field
+pub fn is_component(wasm_bytes: &[u8]) -> bool {
+    wasm_bytes.len() >= 8 && wasm_bytes[..4] == *b"\0asm" && wasm_bytes[6..8] == [1, 0]
+}
+
 impl WasmRunner {
+    pub fn new(limits: SandboxLimits) -> Self {
+        let mut config = Config::new();
+        config.consume_fuel(limits.fuel.is_some());
+        config.epoch_interruption(limits.wall_clock.is_some());
+        Self {
+            // only toggles features on top of the default configuration
+            engine: Engine::new(&config).expect("valid wasmtime config"),
+            limits,
+        }
+    }
+
+    /// store with the runtime's ctx, the memory/table/instance limiter, fuel and epoch deadline
+    fn new_store<C>(&self, ctx: C) -> Result<Store<GuestState<C>>> {
+        let state = GuestState {
+            ctx,
+            limiter: GuestLimiter::new(&self.limits),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limiter);
+        if let Some(fuel) = self.limits.fuel {
+            store
+                .set_fuel(fuel)
+                .map_err(|err| anyhow!("failed to set fuel: {err}"))?;
+        }
+        if self.limits.wall_clock.is_some() {
+            store.set_epoch_deadline(1);
+            store.epoch_deadline_trap();
+        }
+        Ok(store)
+    }
+
     /// run a wasm module, return collected result
     pub fn run<R: WasiRuntime>(&self, wasm_bytes: &[u8], runtime: &R) -> Result<WasmExecResult> {
         // 1) compile wasm bytes into Module (Module can be reused)
@@ -52,42 +148,208 @@ impl WasmRunner {
         // 2) build wasi context from runtime
         let ctx = runtime.build_ctx()?;
 
-        // 3) store will hold runtime status (ctx, memory, table, globals)
-        let mut store = Store::new(&self.engine, ctx);
+        // 3) store will hold runtime status (ctx, memory, table, globals) and limits
+        let mut store = self.new_store(ctx)?;
 
         // 4) Linker is used to import WASI/host functions, etc., into the linker and connect them to the module.
-        let mut linker: Linker<R::Ctx> = Linker::new(&self.engine);
+        let mut linker: Linker<GuestState<R::Ctx>> = Linker::new(&self.engine);
 
         runtime.add_to_linker(&mut linker)?;
+        runtime.instrument(&mut linker, &mut store)?;
 
-        let instance = linker
-            .instantiate(&mut store, &module)
-            .map_err(|err| anyhow!("failed to instantiate wasm: {err}"))?;
+        let watchdog = Watchdog::start(&self.engine, &self.limits);
+        let instance = match linker.instantiate(&mut store, &module) {
+            Ok(instance) => instance,
+            // a guest that is too large for the sandbox is a result, not a scan failure
+            Err(err)
+                if limit_termination(&err, store.data().limiter.hit(), &self.limits).is_some() =>
+            {
+                return self.finish(runtime, &store, Err(err));
+            }
+            Err(err) => return Err(anyhow!("failed to instantiate wasm: {err}")),
+        };
 
-        let exit_code = self.execute::<R>(&mut store, &instance)?;
-        // 7) take out collect IO and time counter from runtime
+        // 5) execute _start; normal return => Normal, error => exit(n), limit or trap
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|err| TerminationError {
+                termination: TerminationReason::MissingStart,
+                message: format!("missing _start: {err}"),
+            })?;
+        let outcome = start
+            .call(&mut store, ())
+            .map(|()| TerminationReason::Normal);
+        drop(watchdog);
+
+        self.finish(runtime, &store, outcome)
+    }
+
+    /// run a wasi:cli/run component, return collected result
+    pub fn run_component<R: WasiRuntime>(
+        &self,
+        wasm_bytes: &[u8],
+        runtime: &R,
+    ) -> Result<WasmExecResult> {
+        let component = Component::from_binary(&self.engine, wasm_bytes)
+            .map_err(|err| anyhow!("failed to compile component: {err}"))?;
+
+        let ctx = runtime.build_ctx()?;
+        let mut store = self.new_store(ctx)?;
+
+        let mut linker: ComponentLinker<GuestState<R::Ctx>> = ComponentLinker::new(&self.engine);
+        runtime.add_to_component_linker(&mut linker)?;
+
+        let watchdog = Watchdog::start(&self.engine, &self.limits);
+        let command = match Command::instantiate(&mut store, &component, &linker) {
+            Ok(command) => command,
+            Err(err)
+                if limit_termination(&err, store.data().limiter.hit(), &self.limits).is_some() =>
+            {
+                return self.finish(runtime, &store, Err(err));
+            }
+            Err(err) => return Err(anyhow!("failed to instantiate component: {err}")),
+        };
+
+        // run() -> result: err is a plain failure exit, traps decode like _start
+        let outcome = command
+            .wasi_cli_run()
+            .call_run(&mut store)
+            .map(|result| match result {
+                Ok(()) => TerminationReason::Normal,
+                Err(()) => TerminationReason::Exit { code: 1 },
+            });
+        drop(watchdog);
+
+        self.finish(runtime, &store, outcome)
+    }
+
+    /// take out collected IO and time counter from runtime and ensemble execution results
+    fn finish<R: WasiRuntime>(
+        &self,
+        runtime: &R,
+        store: &Store<GuestState<R::Ctx>>,
+        outcome: std::result::Result<TerminationReason, Error>,
+    ) -> Result<WasmExecResult> {
+        let termination = outcome.unwrap_or_else(|err| {
+            error_termination(&err, store.data().limiter.hit(), &self.limits)
+        });
+        // -1 keeps the legacy meaning "did not exit on its own"
+        let exit_code = termination.exit_code().unwrap_or(-1);
         let io = runtime.take_io()?;
 
-        // 8) ensemble execution results
         Ok(WasmExecResult {
             stdout: String::from_utf8_lossy(&io.stdout).into_owned(),
             stderr: String::from_utf8_lossy(&io.stderr).into_owned(),
             exit_code,
             duration_ms: io.duration_ms,
+            termination,
         })
     }
+}
 
-    /// execute _start in instance and return exit code
-    fn execute<R: WasiRuntime>(
-        &self,
-        store: &mut Store<R::Ctx>,
-        instance: &Instance,
-    ) -> Result<i32> {
-        let start = instance
-            .get_typed_func::<(), ()>(&mut *store, "_start")
-            .map_err(|err| anyhow!("missing _start: {err}"))?;
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::sandbox::exec_evidence::{ExecutionEvidence, termination_of};
+    use crate::sandbox::wasi::preview1::WasiPreview1;
+
+    fn run_wat(wat: &str, limits: SandboxLimits) -> WasmExecResult {
+        let wasm = wat::parse_str(wat).unwrap();
+        let runtime = WasiPreview1::new(HashMap::new(), None, 1024);
+        WasmRunner::new(limits).run(&wasm, &runtime).unwrap()
+    }
+
+    const SPIN: &str = r#"(module (func (export "_start") (loop (br 0))))"#;
+
+    fn limit_of(exec: &WasmExecResult) -> Option<LimitKind> {
+        match &exec.termination {
+            TerminationReason::ResourceLimit { limit, .. } => Some(*limit),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn fuel_stops_a_spinning_guest() {
+        let exec = run_wat(
+            SPIN,
+            SandboxLimits {
+                fuel: Some(100_000),
+                ..SandboxLimits::none()
+            },
+        );
+        assert_eq!(limit_of(&exec), Some(LimitKind::Fuel));
+        assert_eq!(ExecutionEvidence::from(exec).exit_code, None);
+    }
+
+    #[test]
+    fn wall_clock_stops_a_spinning_guest() {
+        let exec = run_wat(
+            SPIN,
+            SandboxLimits {
+                wall_clock: Some(Duration::from_millis(100)),
+                ..SandboxLimits::none()
+            },
+        );
+        assert_eq!(
+            exec.termination,
+            TerminationReason::Timeout { after_ms: 100 }
+        );
+    }
+
+    #[test]
+    fn memory_cap_stops_growth() {
+        let exec = run_wat(
+            r#"(module
+                (memory 1)
+                (func (export "_start") (drop (memory.grow (i32.const 32)))))"#,
+            SandboxLimits {
+                max_memory_bytes: Some(1 << 20),
+                ..SandboxLimits::none()
+            },
+        );
+        assert_eq!(limit_of(&exec), Some(LimitKind::Memory));
+
+        let exec = run_wat(
+            r#"(module (func (export "_start")))"#,
+            SandboxLimits::corpus_defaults(),
+        );
+        assert_eq!(exec.termination, TerminationReason::Normal);
+        assert_eq!(exec.exit_code, 0);
+    }
+
+    #[test]
+    fn classifies_exit_trap_and_missing_start() {
+        let exec = run_wat(
+            r#"(module
+                (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+                (memory (export "memory") 1)
+                (func (export "_start") (call $exit (i32.const 3))))"#,
+            SandboxLimits::none(),
+        );
+        assert_eq!(exec.termination, TerminationReason::Exit { code: 3 });
+        assert_eq!(exec.exit_code, 3);
+
+        let exec = run_wat(
+            r#"(module (func (export "_start") unreachable))"#,
+            SandboxLimits::none(),
+        );
+        let TerminationReason::Trap {
+            code, backtrace, ..
+        } = &exec.termination
+        else {
+            panic!("expected trap, got {:?}", exec.termination);
+        };
+        assert_eq!(code.as_deref(), Some("UnreachableCodeReached"));
+        assert!(backtrace.is_some());
+        assert_eq!(exec.exit_code, -1);
+        assert_eq!(ExecutionEvidence::from(exec).exit_code, None);
 
-        let result = start.call(&mut *store, ());
-        Ok(decode_exit(result))
+        let wasm = wat::parse_str(r#"(module (func (export "main")))"#).unwrap();
+        let runtime = WasiPreview1::new(HashMap::new(), None, 1024);
+        let err = WasmRunner::default().run(&wasm, &runtime).unwrap_err();
+        assert_eq!(termination_of(&err), Some(&TerminationReason::MissingStart));
     }
 }
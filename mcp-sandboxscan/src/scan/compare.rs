@@ -94,6 +94,9 @@ pub fn compare_from_report(
             matches!(
                 sink,
                 crate::scan::prompt_sink::PromptSink::McpToolResultText { .. }
+                    | crate::scan::prompt_sink::PromptSink::McpResourceText { .. }
+                    | crate::scan::prompt_sink::PromptSink::McpPromptMessage { .. }
+                    | crate::scan::prompt_sink::PromptSink::McpSamplingMessage { .. }
             )
         })
         .count();
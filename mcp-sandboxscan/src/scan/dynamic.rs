@@ -1,19 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 
-use crate::collect::observations_from_http_intents;
-use crate::monitor::event::{flow_events, sink_events, source_inventory_events};
+use crate::adapter::BuildArtifact;
+use crate::collect::{NetworkCollector, observations_from_http_intents};
+use crate::monitor::event::{
+    MonitorEvent, egress_sink_events, finding_events, flow_events, sink_events,
+    source_inventory_events,
+};
+use crate::sandbox::limits::{SandboxLimits, SandboxOptions};
 use crate::sandbox::wasi::preview1::WasiPreview1;
-use crate::sandbox::wasi_hooks::{collect_env_sources, collect_file_sources};
-use crate::sandbox::wasm_runner::WasmRunner;
+use crate::sandbox::wasi::preview2::WasiPreview2;
+use crate::sandbox::wasi_hooks::collect_env_sources;
+use crate::sandbox::wasm_runner::{WasmRunner, is_component};
+use crate::scan::egress_sink::extract_egress_sinks;
+use crate::scan::prompt_injection::detect_prompt_injection;
 use crate::scan::prompt_sink::extract_prompt_sinks;
+use crate::scan::rendered_url::extract_rendered_url_sinks;
 use crate::scan::report::{ScanReport, Summary};
 use crate::scan::tool_return_sink::extract_tool_return_sinks;
-use crate::taint::flow::detect_flows;
+use crate::taint::flow::{
+    detect_flows, detect_flows_with_taint, env_keys_read, lower_confidence_for_unread_env,
+};
 use crate::taint::source::TaintSource;
+use crate::taint::tracker::TaintedWrite;
 
 pub fn run_dynamic_scan(
     wasm_path: &Path,
@@ -21,31 +34,74 @@ pub fn run_dynamic_scan(
     env: &HashMap<String, String>,
     stdin_input: Option<Vec<u8>>,
     max_output_bytes: usize,
+) -> Result<ScanReport> {
+    run_dynamic_scan_with_limits(
+        wasm_path,
+        data_dir,
+        env,
+        stdin_input,
+        max_output_bytes,
+        &SandboxLimits::none(),
+        &SandboxOptions::default(),
+    )
+}
+
+pub fn run_dynamic_scan_with_limits(
+    wasm_path: &Path,
+    data_dir: Option<&Path>,
+    env: &HashMap<String, String>,
+    stdin_input: Option<Vec<u8>>,
+    max_output_bytes: usize,
+    limits: &SandboxLimits,
+    options: &SandboxOptions,
 ) -> Result<ScanReport> {
     let wasm_bytes = fs::read(wasm_path)
         .with_context(|| format!("failed to read wasm file {}", wasm_path.display()))?;
 
+    let runner = WasmRunner::new(limits.clone());
+
+    // javy, componentize-py and cargo component emit wasi:cli/run components
+    if is_component(&wasm_bytes) {
+        let mut runtime = WasiPreview2::new(
+            env.clone(),
+            data_dir.map(|p| p.to_path_buf()),
+            max_output_bytes,
+        );
+        runtime.stdin_input = stdin_input;
+        runtime.network_policy = options.network.clone();
+        let exec = runner.run_component(&wasm_bytes, &runtime)?;
+        return build_scan_report(exec, env, GuestObservations::preview2(&runtime));
+    }
+
     let mut runtime = WasiPreview1::new(
         env.clone(),
         data_dir.map(|p| p.to_path_buf()),
         max_output_bytes,
     );
     runtime.stdin_input = stdin_input;
+    runtime.network_policy = options.network.clone();
 
-    let runner = WasmRunner::default();
     let exec = runner.run(&wasm_bytes, &runtime)?;
 
-    build_scan_report(exec, env, data_dir, &runtime)
+    build_scan_report(exec, env, GuestObservations::preview1(&runtime))
 }
 
 pub fn run_python_dynamic_scan(
-    interpreter_wasm: &Path,
-    work_dir: &Path,
-    argv: &[String],
+    artifact: &BuildArtifact,
     data_dir: Option<&Path>,
     env: &HashMap<String, String>,
     max_output_bytes: usize,
+    limits: &SandboxLimits,
+    options: &SandboxOptions,
 ) -> Result<ScanReport> {
+    let BuildArtifact::PythonWasm {
+        interpreter_wasm,
+        work_dir,
+        argv,
+    } = artifact
+    else {
+        bail!("expected PythonWasm artifact for Python dynamic scan");
+    };
     let wasm_bytes = fs::read(interpreter_wasm).with_context(|| {
         format!(
             "failed to read Python interpreter wasm {}",
@@ -58,7 +114,7 @@ pub fn run_python_dynamic_scan(
         .map(|p| p.to_path_buf())
         .filter(|p| !p.as_os_str().is_empty());
 
-    let runtime = WasiPreview1::new_with_args(
+    let mut runtime = WasiPreview1::new_with_args(
         env.clone(),
         data_dir.map(|p| p.to_path_buf()),
         Some(work_dir.to_path_buf()),
@@ -66,47 +122,113 @@ pub fn run_python_dynamic_scan(
         argv.to_vec(),
         max_output_bytes,
     );
+    runtime.network_policy = options.network.clone();
 
-    let runner = WasmRunner::default();
+    let runner = WasmRunner::new(limits.clone());
     let exec = runner.run(&wasm_bytes, &runtime)?;
 
-    build_scan_report(exec, env, data_dir, &runtime)
+    build_scan_report(exec, env, GuestObservations::preview1(&runtime))
+}
+
+/// what the runtime saw the guest do besides writing stdio
+struct GuestObservations {
+    monitor_events: Vec<MonitorEvent>,
+    network_collector: Arc<NetworkCollector>,
+    file_sources: Vec<TaintSource>,
+    access_events: Vec<MonitorEvent>,
+    // None when the runtime cannot tell which env vars the guest read
+    env_read: Option<HashSet<String>>,
+    tainted_writes: Vec<TaintedWrite>,
+}
+
+impl GuestObservations {
+    fn preview1(runtime: &WasiPreview1) -> Self {
+        let file_collector = runtime.file_collector();
+        let env_collector = runtime.env_collector();
+        let mut access_events = env_collector.as_monitor_events();
+        access_events.extend(file_collector.as_monitor_events());
+        let tainted_writes = runtime.take_tainted_writes();
+        Self {
+            monitor_events: runtime.take_monitor_events(),
+            network_collector: runtime.network_collector(),
+            // only files under /data the guest actually read, not everything planted there
+            file_sources: file_collector.as_taint_sources(),
+            access_events,
+            env_read: Some(env_keys_read(&tainted_writes)),
+            tainted_writes,
+        }
+    }
+
+    // no byte-level taint tracking for components, only what the host calls saw,
+    // so there is no telling which of the granted env vars they used
+    fn preview2(runtime: &WasiPreview2) -> Self {
+        let file_collector = runtime.file_collector();
+        let env_collector = runtime.env_collector();
+        let mut access_events = env_collector.as_monitor_events();
+        access_events.extend(file_collector.as_monitor_events());
+        Self {
+            monitor_events: runtime.take_monitor_events(),
+            network_collector: runtime.network_collector(),
+            file_sources: file_collector.as_taint_sources(),
+            access_events,
+            env_read: None,
+            tainted_writes: vec![],
+        }
+    }
 }
 
 fn build_scan_report(
     exec: crate::sandbox::exec_result::WasmExecResult,
     env: &HashMap<String, String>,
-    data_dir: Option<&Path>,
-    runtime: &WasiPreview1,
+    observed: GuestObservations,
 ) -> Result<ScanReport> {
     let mut sinks = extract_prompt_sinks(&exec.stdout);
     sinks.extend(extract_tool_return_sinks(&exec.stdout));
 
     let mut sources: Vec<TaintSource> = vec![];
     sources.extend(collect_env_sources(env));
-    sources.extend(collect_file_sources(data_dir, 64 * 1024)?);
+    sources.extend(observed.file_sources);
 
-    let network_collector = runtime.network_collector();
+    let network_collector = observed.network_collector;
     for obs in observations_from_http_intents(&exec.stdout, &exec.stderr) {
         network_collector.record(obs);
     }
     sources.extend(network_collector.as_taint_sources());
+    let egress_sinks = extract_egress_sinks(&network_collector.observations());
 
-    let flows = detect_flows(&sources, &sinks);
+    // sources only the taint tracker saw (e.g. reads from fds outside /data)
+    let tainted_writes = observed.tainted_writes;
+    for write in &tainted_writes {
+        for src in &write.data.sources {
+            if !sources.iter().any(|s| s.short_id() == src.short_id()) {
+                sources.push(src.clone());
+            }
+        }
+    }
+
+    let mut flows = detect_flows_with_taint(&sources, &sinks, &tainted_writes);
+    flows.extend(detect_flows(&sources, &egress_sinks));
+    let rendered_url_sinks = extract_rendered_url_sinks(&sinks);
+    flows.extend(detect_flows(&sources, &rendered_url_sinks));
+    if let Some(env_read) = &observed.env_read {
+        lower_confidence_for_unread_env(&mut flows, env_read);
+    }
 
     let mut events = Vec::new();
-    events.extend(runtime.take_monitor_events());
+    events.extend(observed.monitor_events);
+    events.extend(observed.access_events);
     events.extend(network_collector.as_monitor_events());
-    events.extend(source_inventory_events(&sources));
+    events.extend(source_inventory_events(
+        &sources,
+        observed.env_read.as_ref(),
+    ));
     events.extend(sink_events(&sinks));
+    events.extend(egress_sink_events(&egress_sinks));
     events.extend(flow_events(&flows));
+    let findings = detect_prompt_injection(&sinks, &[]);
+    events.extend(finding_events(&findings));
 
-    let summary = Summary {
-        num_sources: sources.len(),
-        num_sinks: sinks.len(),
-        num_flows: flows.len(),
-        has_external_to_prompt_flow: !flows.is_empty(),
-    };
+    let summary = Summary::new(sources.len(), sinks.len() + egress_sinks.len(), &flows);
 
     Ok(ScanReport {
         exec: exec.into(),
@@ -114,15 +236,92 @@ fn build_scan_report(
         events,
         sources,
         sinks,
+        egress_sinks,
+        log_sinks: Vec::new(),
+        rendered_url_sinks,
         flows,
+        findings,
         summary,
+        fuzz: None,
     })
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::monitor::event::MonitorEventKind;
+
     #[test]
     fn smoke_test_flow_detection_logic() {
         assert!(true);
     }
+
+    #[test]
+    fn scans_components_with_preview2() {
+        let component = wat::parse_str(
+            r#"(component
+                (core module $m (func (export "run") (result i32) i32.const 0))
+                (core instance $i (instantiate $m))
+                (func $run (result (result)) (canon lift (core func $i "run")))
+                (instance $cli (export "run" (func $run)))
+                (export "wasi:cli/run@0.2.0" (instance $cli)))"#,
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "mcp-sandboxscan-component-{}.wasm",
+            std::process::id()
+        ));
+        fs::write(&path, component).unwrap();
+
+        let report = run_dynamic_scan(&path, None, &HashMap::new(), None, 1024);
+        fs::remove_file(&path).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.exec.exit_code, Some(0));
+        assert!(
+            report
+                .events
+                .iter()
+                .any(|event| event.target.as_deref() == Some("network-monitor"))
+        );
+    }
+
+    #[test]
+    fn granted_env_the_guest_never_uses_is_not_reported_read() {
+        let mut env = HashMap::new();
+        env.insert(
+            "DEMO_SECRET".to_string(),
+            "SEKRET_0123456789abcdef".to_string(),
+        );
+        env.insert("UNUSED".to_string(), "not-written-anywhere".to_string());
+
+        let report = run_dynamic_scan(
+            Path::new("fixtures/tool_return_secret_tool/tool.wasm"),
+            None,
+            &env,
+            None,
+            4096,
+        )
+        .unwrap();
+
+        // wasi-libc copies both into the guest, only one of them is used
+        let inventory = |key: &str| {
+            report
+                .events
+                .iter()
+                .find(|event| event.actor == "scanner" && event.target.as_deref() == Some(key))
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(inventory("DEMO_SECRET").kind, MonitorEventKind::EnvRead);
+        assert_eq!(inventory("DEMO_SECRET").evidence["access"], "read");
+        assert_eq!(
+            inventory("UNUSED").kind,
+            MonitorEventKind::CapabilityGranted
+        );
+        assert_eq!(inventory("UNUSED").evidence["access"], "granted");
+        assert!(report.events.iter().all(|event| {
+            !(event.kind == MonitorEventKind::EnvRead && event.target.as_deref() == Some("UNUSED"))
+        }));
+    }
 }
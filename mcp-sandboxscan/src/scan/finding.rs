@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/*
+what a scan turns up besides source-to-sink flows: text a server wrote to steer
+the model, or tools that changed under it, found by looking at what the server
+said rather than at where data went.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Finding {
+    // hidden instructions in the tool metadata tools/list hands the model
+    ToolPoisoning {
+        tool: String,
+        path: String,
+        signal: PoisoningSignal,
+        excerpt: String,
+    },
+    // text in a prompt sink written to steer the model, whatever its origin
+    PromptInjection {
+        sink_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<String>,
+        signal: InjectionSignal,
+        excerpt: String,
+    },
+    // tools/list answered differently than it did before
+    ToolDefinitionChanged {
+        tool: String,
+        change: ToolChange,
+        observed: ChangeOrigin,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        before: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        after: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PoisoningSignal {
+    // "ignore previous instructions" and the like
+    InstructionOverride,
+    // <IMPORTANT>, <system> and similar blocks addressed to the model
+    DirectiveBlock,
+    // asks to keep something from the user
+    Concealment,
+    // names credentials or other files a tool has no business reading
+    SensitivePath,
+    // Unicode tag characters, zero-width or bidi control characters
+    InvisibleUnicode,
+    // a long base64 run that decodes to text
+    EncodedBlob,
+}
+
+// variants sharing a name with PoisoningSignal mean the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InjectionSignal {
+    InstructionOverride,
+    // text that speaks to the assistant rather than the user
+    ModelAddress,
+    DirectiveBlock,
+    Concealment,
+    // asks the model to call a tool
+    ToolCoaxing,
+    // a markdown or HTML image whose URL query carries data
+    ImageExfil,
+    InvisibleUnicode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolChange {
+    Added,
+    Removed,
+    Description,
+    Schema,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeOrigin {
+    // a later tools/list to the same server process
+    Session,
+    // a fresh server process started after the scan
+    Restart,
+    // the manifest an earlier corpus scan recorded for the repo
+    Baseline,
+}
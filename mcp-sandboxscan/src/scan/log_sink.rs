@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// a line a native server wrote to its log stream. hosts show stderr to users and
+/// keep it in log files, so a secret here leaks, if less directly than into a prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogSink {
+    pub stream: String,
+    // 1-based line number within the captured output
+    pub line: usize,
+    pub text: String,
+}
+
+impl LogSink {
+    pub fn as_text(&self) -> &str {
+        &self.text
+    }
+}
+
+pub fn extract_log_sinks(stream: &str, output: &str) -> Vec<LogSink> {
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(idx, text)| LogSink {
+            stream: stream.to_string(),
+            line: idx + 1,
+            text: text.trim().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taint::flow::{FlowClass, detect_flows};
+    use crate::taint::source::TaintSource;
+
+    #[test]
+    fn secret_in_stderr_is_a_log_exposure_flow() {
+        let stderr = "server starting\n\nusing token ghp_SEKRET0123456789\n";
+        let sinks = extract_log_sinks("stderr", stderr);
+        assert_eq!(sinks.len(), 2);
+        assert_eq!(sinks[1].line, 3);
+
+        let sources = vec![
+            TaintSource::EnvVar {
+                key: "GITHUB_TOKEN".to_string(),
+                value: "ghp_SEKRET0123456789".to_string(),
+            },
+            TaintSource::HttpFetch {
+                url: "http://example.com".to_string(),
+                content: "server starting".to_string(),
+            },
+        ];
+        let flows = detect_flows(&sources, &sinks);
+        assert!(!flows.is_empty());
+        assert!(
+            flows
+                .iter()
+                .all(|flow| flow.source_id.contains("GITHUB_TOKEN"))
+        );
+        assert_eq!(flows[0].flow_class, FlowClass::LogExposure);
+        assert_eq!(flows[0].sink_type, "StderrLog");
+    }
+}
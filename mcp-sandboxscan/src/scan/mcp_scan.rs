@@ -1,37 +1,68 @@
 use crate::mcp::driver::McpDriverResult;
 use crate::mcp::events::monitor_events_from_transcript;
-use crate::monitor::event::{flow_events, sink_events, source_inventory_events};
-use crate::scan::mcp_sink::extract_mcp_tool_result_sinks;
+use crate::monitor::event::{
+    egress_sink_events, finding_events, flow_events, sink_events, source_inventory_events,
+};
+use crate::scan::egress_sink::EgressSink;
+use crate::scan::log_sink::extract_log_sinks;
+use crate::scan::mcp_sink::{
+    extract_mcp_sampling_sinks, extract_mcp_surface_sinks, extract_mcp_tool_result_sinks,
+};
+use crate::scan::prompt_injection::detect_prompt_injection;
+use crate::scan::rendered_url::extract_rendered_url_sinks;
 use crate::scan::report::{ScanReport, Summary};
+use crate::scan::rug_pull::{ToolManifest, detect_rug_pull};
+use crate::scan::tool_poisoning::detect_tool_poisoning;
 use crate::taint::flow::detect_flows;
 use crate::taint::source::TaintSource;
 
 pub fn scan_mcp_driver_result(
     driver_result: McpDriverResult,
     sources: Vec<TaintSource>,
+    egress_sinks: Vec<EgressSink>,
 ) -> ScanReport {
-    let sinks = extract_mcp_tool_result_sinks(&driver_result.tool_result_payload);
-    let flows = detect_flows(&sources, &sinks);
+    let mut sinks = extract_mcp_tool_result_sinks(&driver_result.tool_result_payload);
+    sinks.extend(extract_mcp_surface_sinks(&driver_result.transcript));
+    sinks.extend(extract_mcp_sampling_sinks(&driver_result.transcript));
+    let mut flows = detect_flows(&sources, &sinks);
+    flows.extend(detect_flows(&sources, &egress_sinks));
+    let log_sinks = extract_log_sinks("stderr", &driver_result.exec.stderr);
+    flows.extend(detect_flows(&sources, &log_sinks));
+    let rendered_url_sinks = extract_rendered_url_sinks(&sinks);
+    flows.extend(detect_flows(&sources, &rendered_url_sinks));
 
     let mut events = Vec::new();
     events.extend(monitor_events_from_transcript(&driver_result.transcript));
-    events.extend(source_inventory_events(&sources));
+    events.extend(source_inventory_events(&sources, None));
     events.extend(sink_events(&sinks));
+    events.extend(egress_sink_events(&egress_sinks));
+    // log lines are too many to list one event each; only their flows are reported
     events.extend(flow_events(&flows));
+    let mut findings = detect_tool_poisoning(&driver_result.transcript);
+    findings.extend(detect_rug_pull(&driver_result.transcript));
+    let tool_names: Vec<String> = ToolManifest::first_in(&driver_result.transcript)
+        .map(|manifest| manifest.tools.into_keys().collect())
+        .unwrap_or_default();
+    findings.extend(detect_prompt_injection(&sinks, &tool_names));
+    events.extend(finding_events(&findings));
 
-    let summary = Summary {
-        num_sources: sources.len(),
-        num_sinks: sinks.len(),
-        num_flows: flows.len(),
-        has_external_to_prompt_flow: !flows.is_empty(),
-    };
+    let summary = Summary::new(
+        sources.len(),
+        sinks.len() + egress_sinks.len() + log_sinks.len(),
+        &flows,
+    );
     ScanReport {
         exec: driver_result.exec,
         mcp_transcript: Some(driver_result.transcript),
         events,
         sources,
         sinks,
+        egress_sinks,
+        log_sinks,
+        rendered_url_sinks,
         flows,
+        findings,
         summary,
+        fuzz: None,
     }
 }
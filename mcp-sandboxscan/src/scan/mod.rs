@@ -1,8 +1,15 @@
 pub mod compare;
 pub mod dynamic;
+pub mod egress_sink;
+pub mod finding;
+pub mod log_sink;
 pub mod mcp_scan;
 pub mod mcp_sink;
 pub mod native_mcp;
+pub mod prompt_injection;
 pub mod prompt_sink;
+pub mod rendered_url;
 pub mod report;
+pub mod rug_pull;
+pub mod tool_poisoning;
 pub mod tool_return_sink;
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -7,13 +8,24 @@ use anyhow::{Context, Result, bail};
 use serde_json::Value;
 
 use crate::adapter::BuildArtifact;
-use crate::collect::NetworkCollector;
-use crate::mcp::driver::{McpCallPlan, McpDriver};
+use crate::collect::{HttpFixtures, InterceptCa, NetworkCollector};
+use crate::mcp::driver::{McpCallPlan, McpDriver, McpDriverResult, McpExplore};
 use crate::mcp::explore::ExplorationConfig;
+use crate::mcp::fuzz::{FuzzCoverage, fuzz_tools};
+use crate::mcp::http::HttpMcpDriver;
 use crate::mcp::native_stdio::{NativeStdioMcpDriver, StdioFraming};
+use crate::mcp::session::{McpLaunch, McpSession};
+use crate::mcp::surface::SurfaceConfig;
+use crate::monitor::event::finding_events;
+use crate::sandbox::limits::SandboxOptions;
+use crate::sandbox::native_isolation::{LoopbackRelay, NativeIsolation, SyscallMonitor};
+use crate::sandbox::native_trace::SyscallTracer;
+use crate::scan::egress_sink::extract_egress_sinks;
+use crate::scan::finding::ChangeOrigin;
 use crate::scan::mcp_scan::scan_mcp_driver_result;
 use crate::scan::report::ScanReport;
-use crate::subject::{Capability, SubjectManifest};
+use crate::scan::rug_pull::{ToolManifest, diff_manifests};
+use crate::subject::{Capability, SubjectManifest, Transport};
 use crate::taint::source::TaintSource;
 
 pub fn run_native_mcp_scan(
@@ -23,12 +35,25 @@ pub fn run_native_mcp_scan(
     data_dir: Option<&Path>,
     mcp_timeout: Option<Duration>,
     exploration: &ExplorationConfig,
+    sandbox: &SandboxOptions,
 ) -> Result<ScanReport> {
+    let network = &sandbox.network;
     let BuildArtifact::NativeCommand { command, args } = artifact else {
         bail!("expected NativeCommand artifact for native MCP scan");
     };
 
     let mcp = subject.mcp.as_ref().context("missing [mcp] spec")?;
+    let http = subject
+        .run
+        .as_ref()
+        .filter(|run| run.transport == Transport::Http);
+    if http.is_some() && sandbox.isolate_native {
+        // the isolated server gets its own network namespace, out of the scanner's reach
+        bail!(
+            "subject {} uses the http transport, which --isolate does not support",
+            subject.name
+        );
+    }
 
     let mut run_args = args.clone();
     let needs_data_dir_arg = subject
@@ -47,29 +72,101 @@ pub fn run_native_mcp_scan(
     }
 
     let network_collector = Arc::new(NetworkCollector::new());
+    network_collector.set_policy(network.clone());
+    if let Some(path) = &network.fixtures {
+        network_collector.set_fixtures(HttpFixtures::load(path)?);
+    }
+    let intercept_ca = if network.intercept_tls {
+        let ca = Arc::new(InterceptCa::generate()?);
+        network_collector.set_tls_intercept(Arc::clone(&ca));
+        Some(ca)
+    } else {
+        None
+    };
     let proxy_port = network_collector
         .start_egress_proxy()
         .context("failed to start egress network proxy")?;
 
-    let mut child_env = corpus_stub_env(env);
+    let dns = if network.capture_dns {
+        Some(start_dns_capture(
+            &network_collector,
+            sandbox.isolate_native,
+        )?)
+    } else {
+        None
+    };
+
+    let syscall_monitor = Arc::new(SyscallMonitor::default());
+    let isolation = sandbox.isolate_native.then(|| {
+        let mut isolation = NativeIsolation::new(Arc::clone(&syscall_monitor));
+        isolation.read_only.push(
+            std::fs::canonicalize(&subject.source_dir)
+                .unwrap_or_else(|_| subject.source_dir.clone()),
+        );
+        isolation.relays.push(LoopbackRelay::Tcp(SocketAddr::from((
+            [127, 0, 0, 1],
+            proxy_port,
+        ))));
+        if let Some(dns) = &dns {
+            isolation.relays.push(LoopbackRelay::Udp {
+                inner: dns.nameserver,
+                outer: dns.stub,
+            });
+            isolation.resolv_conf = Some(dns.resolv_conf.clone());
+        }
+        isolation
+    });
+
+    let mut child_env = corpus_stub_env(env, intercept_ca.as_deref().map(InterceptCa::cert_path));
     let exploration = prepare_exploration(subject, exploration, &mut child_env)?;
+    let tracer = sandbox.trace_native.then(|| {
+        let watch = data_dir
+            .map(|dir| std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()))
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .into_iter()
+            .chain(exploration.file_canary_path.clone())
+            .collect();
+        // the resolv.conf wrapper execs the server only after its own execs
+        let launcher_execs = match (&dns, &isolation) {
+            (Some(_), None) => RESOLV_CONF_LAUNCHER_EXECS,
+            _ => 0,
+        };
+        Arc::new(SyscallTracer::new(watch).skip_launcher_execs(launcher_execs))
+    });
     let proxy_url = format!("http://127.0.0.1:{proxy_port}");
     child_env.insert("HTTP_PROXY".to_string(), proxy_url.clone());
     child_env.insert("HTTPS_PROXY".to_string(), proxy_url);
     child_env.insert("NO_PROXY".to_string(), "127.0.0.1,localhost".to_string());
 
+    // http servers without a fixed port are told which one to use through PORT
+    let http = match http {
+        Some(run) => {
+            let port = match run.port {
+                Some(port) => port,
+                None => {
+                    let port = free_port()?;
+                    child_env.insert("PORT".to_string(), port.to_string());
+                    port
+                }
+            };
+            Some((port, run.path.clone().unwrap_or_else(|| "/mcp".to_string())))
+        }
+        None => None,
+    };
+
     let plan = McpCallPlan {
         tool_name: mcp.tool.clone(),
         arguments: mcp.arguments.clone(),
     };
 
+    // the stdio fallbacks only make sense for servers that pick a transport by argument
     let mut arg_variants = vec![run_args.clone()];
-    if !run_args.iter().any(|a| a == "stdio") {
+    if http.is_none() && !run_args.iter().any(|a| a == "stdio") {
         let mut with_stdio = run_args.clone();
         with_stdio.push("stdio".to_string());
         arg_variants.push(with_stdio);
     }
-    if !run_args.iter().any(|a| a == "--stdio") {
+    if http.is_none() && !run_args.iter().any(|a| a == "--stdio") {
         let mut with_flag = run_args.clone();
         with_flag.push("--stdio".to_string());
         arg_variants.push(with_flag);
@@ -77,6 +174,8 @@ pub fn run_native_mcp_scan(
 
     let mut last_err = None;
     let mut result = None;
+    let mut fuzz = None;
+    let mut restart_tools = None;
     let commands: Vec<String> = if command == "bun" {
         vec!["bun".to_string(), "node".to_string()]
     } else {
@@ -85,22 +184,47 @@ pub fn run_native_mcp_scan(
 
     'outer: for cmd in commands {
         for args in arg_variants.clone() {
-            let driver = NativeStdioMcpDriver {
-                command: cmd.clone(),
-                args,
-                current_dir: Some(subject.source_dir.clone()),
-                framing: StdioFraming::Newline,
-                env: child_env.clone(),
-                mcp_timeout,
+            // isolation binds resolv.conf itself
+            let (command, args) = match (&dns, &isolation) {
+                (Some(dns), None) => with_resolv_conf(&dns.resolv_conf, &cmd, args),
+                _ => (cmd.clone(), args),
             };
-            let scan_result = if exploration.enabled {
-                driver.call_tool_with_exploration(&plan, &exploration)
+            // resources and prompts are read in targeted mode only
+            let surface = exploration.enabled.then(SurfaceConfig::default);
+            let scan_result = if let Some((port, path)) = &http {
+                let driver = HttpMcpDriver {
+                    command,
+                    args,
+                    current_dir: Some(subject.source_dir.clone()),
+                    env: child_env.clone(),
+                    mcp_timeout,
+                    port: *port,
+                    path: path.clone(),
+                    tracer: tracer.clone(),
+                    surface,
+                    client: exploration.client.clone(),
+                };
+                scan_with_driver(&driver, &plan, &exploration)
             } else {
-                driver.call_tool(&plan)
+                let driver = NativeStdioMcpDriver {
+                    command,
+                    args,
+                    current_dir: Some(subject.source_dir.clone()),
+                    framing: StdioFraming::Newline,
+                    env: child_env.clone(),
+                    mcp_timeout,
+                    isolation: isolation.clone(),
+                    tracer: tracer.clone(),
+                    surface,
+                    client: exploration.client.clone(),
+                };
+                scan_with_driver(&driver, &plan, &exploration)
             };
             match scan_result {
-                Ok(r) => {
-                    result = Some(r);
+                Ok(attempt) => {
+                    result = Some(attempt.result);
+                    fuzz = attempt.fuzz;
+                    restart_tools = attempt.restart_tools;
                     break 'outer;
                 }
                 Err(err) => last_err = Some(err),
@@ -154,11 +278,71 @@ pub fn run_native_mcp_scan(
 
     sources.extend(network_collector.as_taint_sources());
 
-    let mut report = scan_mcp_driver_result(result, sources);
+    let egress_sinks = extract_egress_sinks(&network_collector.observations());
+    let mut report = scan_mcp_driver_result(result, sources, egress_sinks);
+    report.fuzz = fuzz;
+    let first_listing = report
+        .mcp_transcript
+        .as_ref()
+        .and_then(ToolManifest::first_in);
+    if let (Some(before), Some(after)) = (first_listing, restart_tools) {
+        let changes = diff_manifests(&before, &after, ChangeOrigin::Restart);
+        report.events.extend(finding_events(&changes));
+        report.findings.extend(changes);
+    }
     report.events.extend(network_collector.as_monitor_events());
+    report.events.extend(syscall_monitor.as_monitor_events());
+    if let Some(tracer) = &tracer {
+        tracer.finish();
+        report.events.extend(tracer.as_monitor_events());
+    }
     Ok(report)
 }
 
+struct DriverAttempt {
+    result: McpDriverResult,
+    fuzz: Option<FuzzCoverage>,
+    restart_tools: Option<ToolManifest>,
+}
+
+// fuzz, explore or make the planned call, whichever the config asks for; an
+// exploring scan that succeeds also lists the tools of a restarted server
+fn scan_with_driver(
+    driver: &(impl McpDriver + McpExplore + McpLaunch),
+    plan: &McpCallPlan,
+    exploration: &ExplorationConfig,
+) -> Result<DriverAttempt> {
+    let mut fuzz = None;
+    let result = if exploration.fuzz_cases > 0 {
+        let (result, coverage) = fuzz_tools(driver, plan, exploration)?;
+        fuzz = Some(coverage);
+        result
+    } else if exploration.enabled {
+        driver.call_tool_with_exploration(plan, exploration)?
+    } else {
+        driver.call_tool(plan)?
+    };
+    let restart_tools = if exploration.enabled {
+        list_tools_after_restart(driver)
+    } else {
+        None
+    };
+    Ok(DriverAttempt {
+        result,
+        fuzz,
+        restart_tools,
+    })
+}
+
+// a second server process, asked only for its tools; one that fails to start
+// again has nothing to compare
+fn list_tools_after_restart(driver: &impl McpLaunch) -> Option<ToolManifest> {
+    let mut session = McpSession::open(driver).ok()?;
+    let tools = session.list_tools().ok();
+    let _ = session.close();
+    tools.as_ref().and_then(ToolManifest::from_tools_list)
+}
+
 fn exploration_tool_input_sources(payload: &Value) -> Vec<TaintSource> {
     let Some(results) = payload
         .get("exploration_results")
@@ -251,10 +435,106 @@ fn prepare_exploration(
         env_canary,
         input_canary,
         file_canary_path: Some(canary_path.to_string_lossy().into_owned()),
+        fuzz_cases: exploration.fuzz_cases,
+        client: exploration.client.clone(),
     })
 }
 
-fn corpus_stub_env(base: &HashMap<String, String>) -> HashMap<String, String> {
+// stops the stub and removes resolv.conf when dropped, so a long corpus scan
+// does not run out of 127.53.0.x addresses
+struct DnsCapture {
+    collector: Arc<NetworkCollector>,
+    // where the stub resolver listens on the host
+    stub: SocketAddr,
+    // what resolv.conf names, always on port 53
+    nameserver: SocketAddr,
+    resolv_conf: PathBuf,
+}
+
+/// start the stub resolver and write a resolv.conf naming it. resolv.conf has no
+/// port field: an isolated server reaches the stub through a relay on port 53 of
+/// its own loopback, any other server needs the stub itself on port 53
+fn start_dns_capture(collector: &Arc<NetworkCollector>, isolated: bool) -> Result<DnsCapture> {
+    let (stub, nameserver) = if isolated {
+        let stub = collector
+            .start_dns_stub([SocketAddr::from(([127, 0, 0, 1], 0))])
+            .context("failed to bind a stub DNS resolver")?;
+        (stub, SocketAddr::from(([127, 53, 0, 1], 53)))
+    } else {
+        let candidates = (1..=254u8).map(|host| SocketAddr::from(([127, 53, 0, host], 53)));
+        let stub = collector.start_dns_stub(candidates).context(
+            "failed to bind a stub DNS resolver on 127.53.0.x:53 (needs root or CAP_NET_BIND_SERVICE)",
+        )?;
+        (stub, stub)
+    };
+
+    let dir = std::env::temp_dir().join("mcp-sandboxscan-dns");
+    // built before anything else can fail, so the stub never outlives an error
+    let capture = DnsCapture {
+        collector: Arc::clone(collector),
+        stub,
+        nameserver,
+        resolv_conf: dir.join(format!(
+            "{}-{}-{}.conf",
+            std::process::id(),
+            stub.ip(),
+            stub.port()
+        )),
+    };
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        &capture.resolv_conf,
+        format!(
+            "nameserver {}\noptions attempts:1 timeout:2\n",
+            nameserver.ip()
+        ),
+    )
+    .with_context(|| format!("failed to write {}", capture.resolv_conf.display()))?;
+    Ok(capture)
+}
+
+impl Drop for DnsCapture {
+    fn drop(&mut self) {
+        self.collector.stop_dns_stub();
+        let _ = std::fs::remove_file(&self.resolv_conf);
+    }
+}
+
+fn free_port() -> Result<u16> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).context("failed to pick a port for the MCP server")?;
+    Ok(listener.local_addr()?.port())
+}
+
+// unshare, the sh it runs and mount, all before the exec of `command`
+const RESOLV_CONF_LAUNCHER_EXECS: usize = 3;
+
+/// run `command` in a private mount namespace with `resolv_conf` bound over /etc/resolv.conf
+fn with_resolv_conf(resolv_conf: &Path, command: &str, args: Vec<String>) -> (String, Vec<String>) {
+    let mut wrapped: Vec<String> = [
+        "--user",
+        "--map-root-user",
+        "--mount",
+        "--",
+        "sh",
+        "-c",
+        r#"mount --bind "$0" /etc/resolv.conf && exec "$@""#,
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    wrapped.push(resolv_conf.to_string_lossy().into_owned());
+    wrapped.push(command.to_string());
+    wrapped.extend(args);
+    ("unshare".to_string(), wrapped)
+}
+
+/// `ca_cert` is the interception CA, trusted through the variables Node, OpenSSL
+/// (Python, Go, curl) and requests read
+fn corpus_stub_env(
+    base: &HashMap<String, String>,
+    ca_cert: Option<&Path>,
+) -> HashMap<String, String> {
     let mut out = base.clone();
     const STUBS: &[(&str, &str)] = &[
         ("GITHUB_PERSONAL_ACCESS_TOKEN", "ghp_corpus_scan_stub"),
@@ -268,5 +548,84 @@ fn corpus_stub_env(base: &HashMap<String, String>) -> HashMap<String, String> {
         out.entry(key.to_string())
             .or_insert_with(|| value.to_string());
     }
+    if let Some(path) = ca_cert {
+        let path = path.to_string_lossy().into_owned();
+        for key in ["NODE_EXTRA_CA_CERTS", "SSL_CERT_FILE", "REQUESTS_CA_BUNDLE"] {
+            out.insert(key.to_string(), path.clone());
+        }
+    }
     out
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    use super::*;
+    use crate::sandbox::native_trace::TracedSyscall;
+
+    #[test]
+    fn dns_capture_frees_its_stub_and_resolv_conf_when_dropped() {
+        let collector = Arc::new(NetworkCollector::new());
+        let dns = start_dns_capture(&collector, true).unwrap();
+        let (stub, resolv_conf) = (dns.stub, dns.resolv_conf.clone());
+        assert!(resolv_conf.exists());
+
+        drop(dns);
+        assert!(!resolv_conf.exists());
+        std::net::UdpSocket::bind(stub).expect("stub address is free again");
+    }
+
+    #[test]
+    fn traces_the_server_behind_the_resolv_conf_wrapper() {
+        let dir =
+            std::env::temp_dir().join(format!("mcp-sandboxscan-dns-trace-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let resolv_conf = dir.join("resolv.conf");
+        std::fs::write(&resolv_conf, "nameserver 127.53.0.9\n").unwrap();
+
+        let (command, args) = with_resolv_conf(
+            &resolv_conf,
+            "sh",
+            vec!["-c".to_string(), "cat /etc/resolv.conf".to_string()],
+        );
+        let tracer = Arc::new(
+            SyscallTracer::new(Vec::new()).skip_launcher_execs(RESOLV_CONF_LAUNCHER_EXECS),
+        );
+        let mut cmd = Command::new(command);
+        cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::null());
+        let mut child = match tracer.spawn_with(&mut cmd, |cmd| cmd.spawn()) {
+            Ok(child) => child,
+            // ptrace can be forbidden in containers
+            Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => return,
+            Err(err) => panic!("spawn traced: {err}"),
+        };
+        let mut out = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        let status = child.wait().unwrap();
+        tracer.finish();
+        let _ = std::fs::remove_dir_all(&dir);
+        // hosts without unprivileged user namespaces cannot bind resolv.conf
+        if !status.success() {
+            return;
+        }
+        assert_eq!(out, "nameserver 127.53.0.9\n");
+
+        // unshare, sh and mount are the launcher; only what the server runs is a spawn
+        let spawned: Vec<_> = tracer
+            .syscalls()
+            .into_iter()
+            .filter_map(|syscall| match syscall {
+                TracedSyscall::Exec { argv, .. } => argv.first().cloned(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(spawned, ["cat"]);
+    }
+}
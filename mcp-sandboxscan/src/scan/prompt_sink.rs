@@ -4,10 +4,34 @@ use serde_json::Value;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum PromptSink {
-    StdoutPrompt { line: String },
-    JsonPrompt { key: String, value: String },
-    ToolReturnLeaf { path: String, value: String },
-    McpToolResultText { path: String, value: String },
+    StdoutPrompt {
+        line: String,
+    },
+    JsonPrompt {
+        key: String,
+        value: String,
+    },
+    ToolReturnLeaf {
+        path: String,
+        value: String,
+    },
+    McpToolResultText {
+        path: String,
+        value: String,
+    },
+    McpResourceText {
+        uri: String,
+        value: String,
+    },
+    McpPromptMessage {
+        prompt: String,
+        path: String,
+        value: String,
+    },
+    McpSamplingMessage {
+        path: String,
+        value: String,
+    },
 }
 
 impl PromptSink {
@@ -18,6 +42,22 @@ impl PromptSink {
             PromptSink::JsonPrompt { value, .. } => value,
             PromptSink::ToolReturnLeaf { value, .. } => value,
             PromptSink::McpToolResultText { value, .. } => value,
+            PromptSink::McpResourceText { value, .. } => value,
+            PromptSink::McpPromptMessage { value, .. } => value,
+            PromptSink::McpSamplingMessage { value, .. } => value,
+        }
+    }
+
+    // where in the output the text sits: a JSON path, a key or a resource URI
+    pub fn location(&self) -> Option<&str> {
+        match self {
+            PromptSink::StdoutPrompt { .. } => None,
+            PromptSink::JsonPrompt { key, .. } => Some(key),
+            PromptSink::McpResourceText { uri, .. } => Some(uri),
+            PromptSink::ToolReturnLeaf { path, .. }
+            | PromptSink::McpToolResultText { path, .. }
+            | PromptSink::McpPromptMessage { path, .. }
+            | PromptSink::McpSamplingMessage { path, .. } => Some(path),
         }
     }
 }
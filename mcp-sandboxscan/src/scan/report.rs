@@ -2,14 +2,19 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::mcp::fuzz::FuzzCoverage;
 use crate::mcp::transcript::McpTranscript;
 use crate::monitor::event::MonitorEvent;
 use crate::sandbox::exec_evidence::ExecutionEvidence;
+use crate::scan::egress_sink::EgressSink;
+use crate::scan::finding::Finding;
+use crate::scan::log_sink::LogSink;
 use crate::scan::prompt_sink::PromptSink;
-use crate::taint::flow::FlowMatch;
+use crate::scan::rendered_url::RenderedUrlSink;
+use crate::taint::flow::{FlowClass, FlowMatch};
 use crate::taint::source::TaintSource;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanReport {
     pub exec: ExecutionEvidence,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -18,14 +23,52 @@ pub struct ScanReport {
     pub events: Vec<MonitorEvent>,
     pub sources: Vec<TaintSource>,
     pub sinks: Vec<PromptSink>,
+    #[serde(default)]
+    pub egress_sinks: Vec<EgressSink>,
+    #[serde(default)]
+    pub log_sinks: Vec<LogSink>,
+    // URLs in prompt sinks that a chat client would render
+    #[serde(default)]
+    pub rendered_url_sinks: Vec<RenderedUrlSink>,
     pub flows: Vec<FlowMatch>,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
     pub summary: Summary,
+    // responses seen per tool when arguments were fuzzed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzz: Option<FuzzCoverage>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Summary {
     pub num_sources: usize,
     pub num_sinks: usize,
     pub num_flows: usize,
     pub has_external_to_prompt_flow: bool,
+    #[serde(default)]
+    pub has_exfiltration_flow: bool,
+    #[serde(default)]
+    pub has_log_exposure_flow: bool,
+    #[serde(default)]
+    pub has_link_exposure_flow: bool,
+}
+
+impl Summary {
+    pub fn new(num_sources: usize, num_sinks: usize, flows: &[FlowMatch]) -> Self {
+        let has_class = |class| flows.iter().any(|flow| flow.flow_class == class);
+        Summary {
+            num_sources,
+            num_sinks,
+            num_flows: flows.len(),
+            has_external_to_prompt_flow: has_class(FlowClass::ExternalToPrompt),
+            has_exfiltration_flow: has_class(FlowClass::Exfiltration),
+            has_log_exposure_flow: has_class(FlowClass::LogExposure),
+            has_link_exposure_flow: has_class(FlowClass::LinkExposure),
+        }
+    }
+
+    /// any source reached a prompt or the network; log or link exposure alone does not count
+    pub fn has_flow(&self) -> bool {
+        self.has_external_to_prompt_flow || self.has_exfiltration_flow
+    }
 }
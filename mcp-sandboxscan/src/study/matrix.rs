@@ -4,8 +4,9 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::attack::{AttackScenario, ScenarioKind, Verdict, run_attack};
+use crate::pipeline::ScanLimits;
 use crate::pipeline::case_study::{default_env_for_subject, resolve_data_dir};
-use crate::pipeline::scan_subject;
 use crate::subject::{Language, SubjectManifest};
 
 use super::portability::WasmPortabilityStatus;
@@ -27,6 +28,9 @@ pub struct StudyCaseResult {
     pub num_sinks: usize,
     pub num_flows: usize,
     pub has_external_to_prompt_flow: bool,
+    // declared scenario and the oracle's verdict against it
+    pub scenario: Option<ScenarioKind>,
+    pub verdict: Verdict,
     pub error: Option<String>,
 }
 
@@ -56,25 +60,30 @@ fn scan_subject_for_matrix(
     max_output_bytes: usize,
 ) -> StudyCaseResult {
     match load_subject(subject_path).and_then(|subject| {
+        let scenario = AttackScenario::for_subject(&subject);
         let scan_env = default_env_for_subject(&subject, env);
         let effective_data_dir = resolve_data_dir(manifest_dir, &subject, data_dir)?;
-        let result = scan_subject(
+        let run = run_attack(
             &subject,
+            &scenario,
             &scan_env,
             effective_data_dir.as_deref(),
             max_output_bytes,
+            ScanLimits::default(),
         )?;
-        Ok((subject, result))
+        Ok((subject, run))
     }) {
-        Ok((subject, result)) => StudyCaseResult {
+        Ok((subject, run)) => StudyCaseResult {
             subject_name: subject.name,
             subject_path: subject_path.to_path_buf(),
             language: subject.language,
-            wasm_status: result.adaptation_status.into(),
-            num_sources: result.report.summary.num_sources,
-            num_sinks: result.report.summary.num_sinks,
-            num_flows: result.report.summary.num_flows,
-            has_external_to_prompt_flow: result.report.summary.has_external_to_prompt_flow,
+            wasm_status: run.result.adaptation_status.into(),
+            num_sources: run.result.report.summary.num_sources,
+            num_sinks: run.result.report.summary.num_sinks,
+            num_flows: run.result.report.summary.num_flows,
+            has_external_to_prompt_flow: run.result.report.summary.has_external_to_prompt_flow,
+            scenario: Some(run.scenario.kind),
+            verdict: run.verdict,
             error: None,
         },
         Err(err) => StudyCaseResult {
@@ -91,6 +100,10 @@ fn scan_subject_for_matrix(
             num_sinks: 0,
             num_flows: 0,
             has_external_to_prompt_flow: false,
+            scenario: load_subject(subject_path)
+                .ok()
+                .map(|subject| AttackScenario::for_subject(&subject).kind),
+            verdict: Verdict::Error,
             error: Some(err.to_string()),
         },
     }
@@ -125,6 +138,9 @@ mod tests {
         assert_eq!(matrix.summary.detected_cases, 1);
         assert!(matrix.cases[0].has_external_to_prompt_flow);
         assert!(matrix.cases[0].num_flows > 0);
+        assert_eq!(matrix.cases[0].scenario, Some(ScenarioKind::EnvLeak));
+        assert_eq!(matrix.cases[0].verdict, Verdict::Detected);
+        assert_eq!(matrix.summary.confusion.tp, 1);
     }
 
     fn env_with_demo_secret() -> HashMap<String, String> {
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::attack::verdict::{Confusion, Label, update_confusion};
+
 use super::matrix::StudyCaseResult;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -10,6 +12,8 @@ pub struct StudySummary {
     pub detected_cases: usize,
     pub clean_cases: usize,
     pub total_flows: usize,
+    // oracle verdicts against each subject's expected label
+    pub confusion: Confusion,
 }
 
 impl StudySummary {
@@ -27,6 +31,16 @@ impl StudySummary {
             .count();
         let total_flows = cases.iter().map(|case| case.num_flows).sum();
 
+        let mut confusion = Confusion::default();
+        for case in cases {
+            match (case.error.is_none(), case.scenario) {
+                (true, Some(scenario)) => {
+                    update_confusion(&mut confusion, Label::for_scenario(scenario), case.verdict)
+                }
+                _ => confusion.errors += 1,
+            }
+        }
+
         Self {
             total_cases,
             scanned_cases,
@@ -34,6 +48,7 @@ impl StudySummary {
             detected_cases,
             clean_cases,
             total_flows,
+            confusion,
         }
     }
 }
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/*
+optional [expect] table: what a subject is supposed to do, so scoring does not
+depend on the subject name
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScenarioKind {
+    Benign,
+    EnvLeak,
+    FileExfil,
+    C2Beacon,
+    PromptInjection,
+}
+
+/// file written under the scenario data dir (mounted as /data for WASI subjects)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlantedFile {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectSpec {
+    pub label: ScenarioKind,
+    // substrings of FlowMatch.source_id that count as a hit
+    #[serde(default)]
+    pub sources: Vec<String>,
+    // FlowMatch.sink_type values that count as a hit
+    #[serde(default)]
+    pub sinks: Vec<String>,
+    // seeded into the scan env unless the caller already set the key
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub files: Vec<PlantedFile>,
+}
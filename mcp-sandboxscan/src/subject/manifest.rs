@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 // load from parent folder
 use super::capability::Capability;
+use super::expect::ExpectSpec;
 use super::language::Language;
+use crate::sandbox::network_policy::NetworkPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubjectManifest {
@@ -19,6 +21,11 @@ pub struct SubjectManifest {
     #[serde(default)]
     pub capabilities: Vec<Capability>,
     pub mcp: Option<McpSpec>,
+    // declared ground truth for bench / study scoring
+    pub expect: Option<ExpectSpec>,
+    // outbound connections the subject may make; deny-all when absent
+    #[serde(default)]
+    pub network: NetworkPolicy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,12 +47,26 @@ pub struct RunSpec {
     pub command: String,
     #[serde(default)]
     pub args: Vec<String>,
+    // how the server speaks MCP; http servers listen on 127.0.0.1:port at path
+    #[serde(default)]
+    pub transport: Transport,
+    pub port: Option<u16>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transport {
+    #[default]
+    Stdio,
+    Http,
 }
 
 // test code
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::subject::ScenarioKind;
     #[test]
     fn parse_rust_env_leak_manifest() {
         let raw = std::fs::read_to_string("case_studies/rust-env-leak/subject.toml")
@@ -57,4 +78,31 @@ mod tests {
         assert!(manifest.capabilities.contains(&Capability::Stdio));
         assert!(manifest.build.is_some());
     }
+
+    #[test]
+    fn parse_expect_table() {
+        let raw = std::fs::read_to_string("case_studies/rust-file-exfil/subject.toml")
+            .expect("read subject.toml");
+        let manifest: SubjectManifest = toml::from_str(&raw).expect("parse subject.toml");
+        let expect = manifest.expect.expect("rust-file-exfil declares [expect]");
+        assert_eq!(expect.label, ScenarioKind::FileExfil);
+        assert_eq!(expect.env["FILE_TO_READ"], "secret.txt");
+        assert_eq!(expect.files[0].path, "secret.txt");
+    }
+
+    #[test]
+    fn parse_http_run_spec() {
+        let run: RunSpec = toml::from_str(
+            r#"
+            command = "python3"
+            args = ["server.py"]
+            transport = "http"
+            port = 8931
+            "#,
+        )
+        .expect("parse [run]");
+        assert_eq!(run.transport, Transport::Http);
+        assert_eq!(run.port, Some(8931));
+        assert_eq!(run.path, None);
+    }
 }
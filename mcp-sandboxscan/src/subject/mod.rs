@@ -1,7 +1,9 @@
 pub mod capability;
+pub mod expect;
 pub mod language;
 pub mod manifest;
 
 pub use capability::Capability;
+pub use expect::{ExpectSpec, PlantedFile, ScenarioKind};
 pub use language::Language;
-pub use manifest::{BuildSpec, McpSpec, RunSpec, SubjectManifest};
+pub use manifest::{BuildSpec, McpSpec, RunSpec, SubjectManifest, Transport};
@@ -1,6 +1,12 @@
 // extract snippet from every source and match substrings in sink
+use std::collections::HashSet;
+
+use crate::scan::egress_sink::EgressSink;
+use crate::scan::log_sink::LogSink;
 use crate::scan::prompt_sink::PromptSink;
+use crate::scan::rendered_url::RenderedUrlSink;
 use crate::taint::source::TaintSource;
+use crate::taint::tracker::{TaintEvidence, TaintedWrite};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,7 +14,141 @@ pub struct FlowMatch {
     pub source_id: String,
     pub sink_type: String,
     pub snippet: String,
-    pub confidence: String, // MVP: "high"/"medium"
+    pub confidence: String, // "proven" (host-call taint), "high"/"medium" (substring), "low" (env never read)
+    #[serde(default)]
+    pub flow_class: FlowClass,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlowClass {
+    // external data reaching text the model will read
+    #[default]
+    ExternalToPrompt,
+    // local secrets leaving the host over the network
+    Exfiltration,
+    // local secrets in the server's own logs; lower severity, not a verdict on its own
+    LogExposure,
+    // local secrets in a link that only leaves if the user clicks it; same footing as logs
+    LinkExposure,
+}
+
+/// anything a source can flow into
+pub trait FlowSink {
+    fn sink_type(&self) -> String;
+    fn as_text(&self) -> &str;
+
+    fn flow_class(&self) -> FlowClass {
+        FlowClass::ExternalToPrompt
+    }
+
+    fn accepts(&self, _source: &TaintSource) -> bool {
+        true
+    }
+
+    fn contains(&self, snippet: &str) -> bool {
+        self.as_text().contains(snippet)
+    }
+}
+
+impl FlowSink for PromptSink {
+    fn sink_type(&self) -> String {
+        match self {
+            PromptSink::StdoutPrompt { .. } => "StdoutPrompt".to_string(),
+            PromptSink::JsonPrompt { .. } => "JsonPrompt".to_string(),
+            PromptSink::ToolReturnLeaf { .. } => "ToolReturnLeaf".to_string(),
+            PromptSink::McpToolResultText { .. } => "McpToolResultText".to_string(),
+            PromptSink::McpResourceText { .. } => "McpResourceText".to_string(),
+            PromptSink::McpPromptMessage { .. } => "McpPromptMessage".to_string(),
+            PromptSink::McpSamplingMessage { .. } => "McpSamplingMessage".to_string(),
+        }
+    }
+
+    fn as_text(&self) -> &str {
+        PromptSink::as_text(self)
+    }
+}
+
+impl FlowSink for EgressSink {
+    fn sink_type(&self) -> String {
+        match self {
+            EgressSink::UrlQuery { .. } => "EgressUrlQuery".to_string(),
+            EgressSink::HttpHeader { .. } => "EgressHttpHeader".to_string(),
+            EgressSink::HttpBody { .. } => "EgressHttpBody".to_string(),
+            EgressSink::DnsLabel { .. } => "EgressDnsLabel".to_string(),
+        }
+    }
+
+    fn as_text(&self) -> &str {
+        EgressSink::as_text(self)
+    }
+
+    // resolvers and proxies are free to change the case of a hostname
+    fn contains(&self, snippet: &str) -> bool {
+        match self {
+            EgressSink::DnsLabel { value, .. } => value
+                .to_ascii_lowercase()
+                .contains(&snippet.to_ascii_lowercase()),
+            _ => self.as_text().contains(snippet),
+        }
+    }
+
+    fn flow_class(&self) -> FlowClass {
+        FlowClass::Exfiltration
+    }
+
+    // only host-local data counts as exfiltrated; echoing a fetched URL back out does not
+    fn accepts(&self, source: &TaintSource) -> bool {
+        source.is_host_local()
+    }
+}
+
+impl FlowSink for LogSink {
+    fn sink_type(&self) -> String {
+        let mut stream = self.stream.chars();
+        match stream.next() {
+            Some(first) => format!("{}{}Log", first.to_ascii_uppercase(), stream.as_str()),
+            None => "Log".to_string(),
+        }
+    }
+
+    fn as_text(&self) -> &str {
+        LogSink::as_text(self)
+    }
+
+    fn flow_class(&self) -> FlowClass {
+        FlowClass::LogExposure
+    }
+
+    // a server logging what it fetched or was sent is routine
+    fn accepts(&self, source: &TaintSource) -> bool {
+        source.is_host_local()
+    }
+}
+
+impl FlowSink for RenderedUrlSink {
+    fn sink_type(&self) -> String {
+        "RenderedUrlExfil".to_string()
+    }
+
+    fn as_text(&self) -> &str {
+        RenderedUrlSink::as_text(self)
+    }
+
+    // the client fetches an image by itself, so the data leaves as surely as over the
+    // network; a link needs the user to click it
+    fn flow_class(&self) -> FlowClass {
+        if self.markup.is_image() {
+            FlowClass::Exfiltration
+        } else {
+            FlowClass::LinkExposure
+        }
+    }
+
+    // a tool linking to the page it fetched, or echoing its input into a link, is routine
+    fn accepts(&self, source: &TaintSource) -> bool {
+        source.is_host_local()
+    }
 }
 
 fn make_snipppets(s: &str) -> Vec<String> {
@@ -46,36 +186,34 @@ fn make_snipppets(s: &str) -> Vec<String> {
     out.into_iter().filter(|x| !x.trim().is_empty()).collect()
 }
 
-pub fn detect_flows(sources: &[TaintSource], sinks: &[PromptSink]) -> Vec<FlowMatch> {
+pub fn detect_flows<S: FlowSink>(sources: &[TaintSource], sinks: &[S]) -> Vec<FlowMatch> {
     let mut flows = vec![];
     let min_len = 4;
     for src in sources {
         let content = src.content();
         let snippets = make_snipppets(content);
 
-        for sink in sinks {
-            let sink_text = sink.as_text();
-
+        for sink in sinks.iter().filter(|sink| sink.accepts(src)) {
             for snip in &snippets {
                 // any matched snippet -> flow
-                if snip.len() >= min_len && sink_text.contains(snip.as_str()) {
+                if snip.len() >= min_len && sink.contains(snip) {
                     flows.push(FlowMatch {
                         source_id: src.short_id(),
-                        sink_type: match sink {
-                            PromptSink::StdoutPrompt { .. } => "StdoutPrompt".to_string(),
-                            PromptSink::JsonPrompt { .. } => "JsonPrompt".to_string(),
-                            PromptSink::ToolReturnLeaf { .. } => "ToolReturnLeaf".to_string(),
-                            PromptSink::McpToolResultText { .. } => "McpToolResultText".to_string(),
-                        },
+                        sink_type: sink.sink_type(),
                         snippet: snip.clone(),
                         confidence: "high".to_string(), // MVP
+                        flow_class: sink.flow_class(),
                     });
                     break;
                 }
             }
         }
     }
-    // deduplicate flows by source_id + sink_type
+    dedup_flows(flows)
+}
+
+// deduplicate flows by source_id + sink_type, keeping the first (strongest) match
+fn dedup_flows(mut flows: Vec<FlowMatch>) -> Vec<FlowMatch> {
     flows.sort_by(|a, b| {
         (a.source_id.clone(), a.sink_type.clone()).cmp(&(b.source_id.clone(), b.sink_type.clone()))
     });
@@ -84,6 +222,72 @@ pub fn detect_flows(sources: &[TaintSource], sinks: &[PromptSink]) -> Vec<FlowMa
     flows
 }
 
+/// flows backed by WASI host-call taint: a labelled fragment written to stdout that
+/// shows up in a sink extracted from stdout
+pub fn detect_tainted_flows(writes: &[TaintedWrite], sinks: &[PromptSink]) -> Vec<FlowMatch> {
+    let mut flows = vec![];
+    for write in writes.iter().filter(|w| w.fd == 1) {
+        let fragment = write.data.value.trim();
+        if fragment.is_empty() {
+            continue;
+        }
+        for sink in sinks {
+            if !sink.as_text().contains(fragment) {
+                continue;
+            }
+            for src in &write.data.sources {
+                flows.push(FlowMatch {
+                    source_id: src.short_id(),
+                    sink_type: sink.sink_type(),
+                    snippet: fragment.to_string(),
+                    confidence: match write.evidence {
+                        TaintEvidence::Range => "proven".to_string(),
+                        TaintEvidence::Copy => "high".to_string(),
+                    },
+                    flow_class: sink.flow_class(),
+                });
+            }
+        }
+    }
+    dedup_flows(flows)
+}
+
+/// taint-backed flows first, then substring matches for whatever they did not cover
+pub fn detect_flows_with_taint(
+    sources: &[TaintSource],
+    sinks: &[PromptSink],
+    writes: &[TaintedWrite],
+) -> Vec<FlowMatch> {
+    let mut flows = detect_tainted_flows(writes, sinks);
+    flows.extend(detect_flows(sources, sinks));
+    dedup_flows(flows)
+}
+
+/// env vars whose bytes the tracker saw leave the guest through a host call;
+/// the closest thing to a getenv the runtime can observe
+pub fn env_keys_read(writes: &[TaintedWrite]) -> HashSet<String> {
+    writes
+        .iter()
+        .flat_map(|write| &write.data.sources)
+        .filter_map(|source| match source {
+            TaintSource::EnvVar { key, .. } => Some(key.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// flows from env vars the guest was granted but never used can only be
+/// coincidental substring matches
+pub fn lower_confidence_for_unread_env(flows: &mut [FlowMatch], env_read: &HashSet<String>) {
+    for flow in flows.iter_mut() {
+        if let Some(key) = flow.source_id.strip_prefix("EnvVar: ")
+            && !env_read.contains(key)
+        {
+            flow.confidence = "low".to_string();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +310,80 @@ mod tests {
         assert!(flows[0].snippet.contains("SECRET"));
     }
 
+    #[test]
+    fn tainted_write_outranks_substring_match() {
+        use crate::taint::taint_string::TaintedString;
+
+        let source = TaintSource::EnvVar {
+            key: "API_KEY".to_string(),
+            value: "SECRET_TOKEN".to_string(),
+        };
+        let sinks = vec![PromptSink::StdoutPrompt {
+            line: "PROMPT: use SECRET_TOKEN to authenticate".to_string(),
+        }];
+        let writes = vec![TaintedWrite {
+            fd: 1,
+            offset: 13,
+            evidence: TaintEvidence::Range,
+            data: TaintedString {
+                value: "SECRET_TOKEN".to_string(),
+                sources: vec![source.clone()],
+            },
+        }];
+
+        let flows = detect_flows_with_taint(&[source], &sinks, &writes);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].confidence, "proven");
+        assert_eq!(flows[0].source_id, "EnvVar: API_KEY");
+    }
+
+    #[test]
+    fn env_to_network_is_an_exfiltration_flow() {
+        let sources = vec![
+            TaintSource::EnvVar {
+                key: "DEMO_SECRET".to_string(),
+                value: "SEKRET_0123".to_string(),
+            },
+            TaintSource::HttpFetch {
+                url: "http://api.example.com/".to_string(),
+                content: "/beacon".to_string(),
+            },
+        ];
+        let sinks = vec![EgressSink::UrlQuery {
+            url: "http://c2.evil.example/beacon?k=SEKRET_0123".to_string(),
+            value: "/beacon?k=SEKRET_0123".to_string(),
+        }];
+
+        let flows = detect_flows(&sources, &sinks);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].source_id, "EnvVar: DEMO_SECRET");
+        assert_eq!(flows[0].sink_type, "EgressUrlQuery");
+        assert_eq!(flows[0].flow_class, FlowClass::Exfiltration);
+    }
+
+    #[test]
+    fn secret_in_dns_labels_matches_whatever_the_case() {
+        let sources = vec![TaintSource::EnvVar {
+            key: "DEMO_SECRET".to_string(),
+            value: "SEKRET01".to_string(),
+        }];
+        let sinks = vec![EgressSink::DnsLabel {
+            host: "sekret01.c2.evil.example".to_string(),
+            value: "sekret01".to_string(),
+        }];
+
+        let flows = detect_flows(&sources, &sinks);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].sink_type, "EgressDnsLabel");
+
+        // everywhere else the case of the bytes is what was sent
+        let sinks = vec![EgressSink::HttpBody {
+            url: "http://c2.evil.example/".to_string(),
+            value: "sekret01".to_string(),
+        }];
+        assert!(detect_flows(&sources, &sinks).is_empty());
+    }
+
     #[test]
     fn no_false_positive_when_not_present() {
         let sources = vec![TaintSource::EnvVar {
@@ -120,4 +398,28 @@ mod tests {
         let flows = detect_flows(&sources, &sinks);
         assert!(flows.is_empty());
     }
+
+    #[test]
+    fn unread_env_flow_gets_low_confidence() {
+        let sources = vec![
+            TaintSource::EnvVar {
+                key: "API_KEY".to_string(),
+                value: "SECRET_TOKEN".to_string(),
+            },
+            TaintSource::EnvVar {
+                key: "READ_KEY".to_string(),
+                value: "OTHER_TOKEN".to_string(),
+            },
+        ];
+        let sinks = vec![PromptSink::StdoutPrompt {
+            line: "PROMPT: SECRET_TOKEN OTHER_TOKEN".to_string(),
+        }];
+
+        let mut flows = detect_flows(&sources, &sinks);
+        lower_confidence_for_unread_env(&mut flows, &HashSet::from(["READ_KEY".to_string()]));
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].source_id, "EnvVar: API_KEY");
+        assert_eq!(flows[0].confidence, "low");
+        assert_eq!(flows[1].confidence, "high");
+    }
 }
@@ -2,3 +2,4 @@ pub mod flow;
 pub mod flow_config;
 pub mod source;
 pub mod taint_string;
+pub mod tracker;
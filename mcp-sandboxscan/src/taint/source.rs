@@ -29,6 +29,14 @@ pub enum TaintSource {
 }
 
 impl TaintSource {
+    /// data that lives on the host, as opposed to what the server fetched or was sent
+    pub fn is_host_local(&self) -> bool {
+        matches!(
+            self,
+            TaintSource::EnvVar { .. } | TaintSource::FileRead { .. }
+        )
+    }
+
     pub fn content(&self) -> &str {
         match self {
             TaintSource::FileRead { content, .. } => content,
@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::taint::source::TaintSource;
+use crate::taint::taint_string::TaintedString;
+
+/*
+byte-level taint for WASI guests: host calls that hand data to the guest
+(environ_get, fd_read) label the guest memory they filled, and fd_write checks
+the bytes it is asked to emit against those labels
+*/
+
+// shortest copied run that counts as the same bytes leaving through fd_write
+const MIN_COPY_RUN: usize = 8;
+// runs shorter than MIN_COPY_RUN only count when they are the whole source value
+const MIN_WHOLE_VALUE: usize = 4;
+// cap on bytes remembered per source (matches collect_file_sources)
+const MAX_SOURCE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaintEvidence {
+    // written straight out of a labelled guest memory range
+    Range,
+    // the guest copied labelled bytes elsewhere before writing them
+    Copy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaintedWrite {
+    pub fd: u32,
+    // byte offset of the fragment within everything written to fd
+    pub offset: u64,
+    pub evidence: TaintEvidence,
+    pub data: TaintedString,
+}
+
+struct TrackedSource {
+    source: TaintSource,
+    bytes: Vec<u8>,
+    // copy detection is only enabled for discrete secrets (env values, planted files)
+    match_copies: bool,
+    windows: Option<HashSet<u64>>,
+}
+
+impl TrackedSource {
+    fn windows(&mut self) -> &HashSet<u64> {
+        let bytes = &self.bytes;
+        self.windows
+            .get_or_insert_with(|| bytes.windows(MIN_COPY_RUN).map(window_key).collect())
+    }
+}
+
+struct TaintedRange {
+    start: u32,
+    bytes: Vec<u8>,
+    source: usize,
+}
+
+impl TaintedRange {
+    // u64 so a range ending at the top of the 4 GiB address space cannot wrap
+    fn end(&self) -> u64 {
+        self.start as u64 + self.bytes.len() as u64
+    }
+}
+
+#[derive(Default)]
+struct TrackerState {
+    sources: Vec<TrackedSource>,
+    by_id: HashMap<String, usize>,
+    ranges: Vec<TaintedRange>,
+    writes: Vec<TaintedWrite>,
+    fd_offsets: HashMap<u32, u64>,
+}
+
+#[derive(Default)]
+pub struct TaintTracker {
+    state: Mutex<TrackerState>,
+}
+
+impl TaintTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = TrackerState::default();
+    }
+
+    /// guest memory at `start` now holds the value of an env var
+    pub fn taint_env(&self, start: u32, key: &str, value: &[u8]) {
+        let source = TaintSource::EnvVar {
+            key: key.to_string(),
+            value: String::from_utf8_lossy(value).into_owned(),
+        };
+        let mut state = self.state.lock().unwrap();
+        let index = state.source_index(source, true);
+        state.label(start, value, index);
+    }
+
+    /// guest memory at `start` now holds bytes read from `path`; `match_copies`
+    /// is set for planted files whose contents are secrets in their own right
+    pub fn taint_read(&self, start: u32, path: &str, bytes: &[u8], match_copies: bool) {
+        let mut state = self.state.lock().unwrap();
+        let index = state.source_index(
+            TaintSource::FileRead {
+                path: path.to_string(),
+                content: String::new(),
+            },
+            match_copies,
+        );
+        let tracked = &mut state.sources[index];
+        let room = MAX_SOURCE_BYTES.saturating_sub(tracked.bytes.len());
+        tracked
+            .bytes
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+        tracked.windows = None;
+        state.label(start, bytes, index);
+    }
+
+    /// bytes about to leave through fd_write; `chunks` are (guest address, bytes) per iovec
+    pub fn observe_write(&self, fd: u32, chunks: &[(u32, Vec<u8>)]) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let mut offset = state.fd_offsets.get(&fd).copied().unwrap_or(0);
+
+        for (addr, bytes) in chunks {
+            let mut covered = vec![false; bytes.len()];
+            let mut found = vec![];
+
+            // direct: the iovec points into a labelled range that still holds the labelled bytes
+            for range in &state.ranges {
+                let lo = (*addr).max(range.start) as u64;
+                let hi = (*addr as u64 + bytes.len() as u64).min(range.end());
+                if lo >= hi {
+                    continue;
+                }
+                let in_write = (lo - *addr as u64) as usize..(hi - *addr as u64) as usize;
+                let in_range =
+                    (lo - range.start as u64) as usize..(hi - range.start as u64) as usize;
+                if bytes[in_write.clone()] != range.bytes[in_range] {
+                    continue;
+                }
+                covered[in_write.clone()].iter_mut().for_each(|c| *c = true);
+                found.push((range.source, in_write, TaintEvidence::Range));
+            }
+
+            // copied: labelled bytes reappear elsewhere in the written buffer
+            for index in 0..state.sources.len() {
+                if !state.sources[index].match_copies {
+                    continue;
+                }
+                for run in copied_runs(bytes, &mut state.sources[index]) {
+                    if covered[run.clone()].iter().all(|c| *c) {
+                        continue;
+                    }
+                    found.push((index, run, TaintEvidence::Copy));
+                }
+            }
+
+            for (index, run, evidence) in found {
+                let value = String::from_utf8_lossy(&bytes[run.clone()]).into_owned();
+                let source = state.source_snapshot(index);
+                state.writes.push(TaintedWrite {
+                    fd,
+                    offset: offset + run.start as u64,
+                    evidence,
+                    data: TaintedString {
+                        value,
+                        sources: vec![source],
+                    },
+                });
+            }
+            offset += bytes.len() as u64;
+        }
+
+        state.fd_offsets.insert(fd, offset);
+    }
+
+    pub fn take_writes(&self) -> Vec<TaintedWrite> {
+        std::mem::take(&mut self.state.lock().unwrap().writes)
+    }
+}
+
+impl TrackerState {
+    fn source_index(&mut self, source: TaintSource, match_copies: bool) -> usize {
+        let id = source.short_id();
+        if let Some(index) = self.by_id.get(&id) {
+            return *index;
+        }
+        let bytes = source.content().as_bytes().to_vec();
+        self.sources.push(TrackedSource {
+            source,
+            bytes,
+            match_copies,
+            windows: None,
+        });
+        self.by_id.insert(id, self.sources.len() - 1);
+        self.sources.len() - 1
+    }
+
+    fn source_snapshot(&self, index: usize) -> TaintSource {
+        let tracked = &self.sources[index];
+        match &tracked.source {
+            TaintSource::FileRead { path, .. } => TaintSource::FileRead {
+                path: path.clone(),
+                content: String::from_utf8_lossy(&tracked.bytes).into_owned(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// label [start, start + bytes.len()), dropping whatever labels the host call overwrote
+    fn label(&mut self, start: u32, bytes: &[u8], source: usize) {
+        if bytes.is_empty() {
+            return;
+        }
+        let end = start as u64 + bytes.len() as u64;
+        let mut kept = Vec::with_capacity(self.ranges.len() + 1);
+        for range in self.ranges.drain(..) {
+            if range.end() <= start as u64 || range.start as u64 >= end {
+                kept.push(range);
+                continue;
+            }
+            if range.start < start {
+                kept.push(TaintedRange {
+                    start: range.start,
+                    bytes: range.bytes[..(start - range.start) as usize].to_vec(),
+                    source: range.source,
+                });
+            }
+            // end is below range.end() here, so it still fits in a u32
+            if range.end() > end {
+                kept.push(TaintedRange {
+                    start: end as u32,
+                    bytes: range.bytes[(end - range.start as u64) as usize..].to_vec(),
+                    source: range.source,
+                });
+            }
+        }
+        kept.push(TaintedRange {
+            start,
+            bytes: bytes.to_vec(),
+            source,
+        });
+        self.ranges = kept;
+    }
+}
+
+fn window_key(window: &[u8]) -> u64 {
+    let mut key = [0u8; 8];
+    key.copy_from_slice(&window[..MIN_COPY_RUN]);
+    u64::from_le_bytes(key)
+}
+
+fn copied_runs(haystack: &[u8], tracked: &mut TrackedSource) -> Vec<std::ops::Range<usize>> {
+    let value = tracked.bytes.trim_ascii();
+    if value.len() < MIN_WHOLE_VALUE {
+        return vec![];
+    }
+    if value.len() < MIN_COPY_RUN {
+        return haystack
+            .windows(value.len())
+            .enumerate()
+            .filter(|(_, w)| *w == value)
+            .map(|(i, _)| i..i + value.len())
+            .collect();
+    }
+
+    let windows = tracked.windows();
+    let mut runs = vec![];
+    let mut i = 0;
+    while i + MIN_COPY_RUN <= haystack.len() {
+        if !windows.contains(&window_key(&haystack[i..])) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + MIN_COPY_RUN < haystack.len() && windows.contains(&window_key(&haystack[i + 1..]))
+        {
+            i += 1;
+        }
+        runs.push(start..i + MIN_COPY_RUN);
+        i += MIN_COPY_RUN;
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_write_is_proven_even_when_sliced() {
+        let tracker = TaintTracker::new();
+        tracker.taint_read(0x100, "fd:3", b"top-secret-0123456789abcdef", false);
+
+        // guest writes a slice straight out of the read buffer
+        tracker.observe_write(1, &[(0x104, b"secret".to_vec())]);
+
+        let writes = tracker.take_writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].evidence, TaintEvidence::Range);
+        assert_eq!(writes[0].data.value, "secret");
+        assert_eq!(writes[0].data.sources[0].short_id(), "FileRead: fd:3");
+    }
+
+    #[test]
+    fn copied_env_value_is_found_inside_reformatted_output() {
+        let tracker = TaintTracker::new();
+        tracker.taint_env(0x200, "DEMO_SECRET", b"SEKRET_0123456789abcdef");
+
+        tracker.observe_write(1, &[(0x900, b"hello\n".to_vec())]);
+        tracker.observe_write(1, &[(0x900, b"{\"leak\":\"SEKRET_0123\"}".to_vec())]);
+
+        let writes = tracker.take_writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].evidence, TaintEvidence::Copy);
+        assert_eq!(writes[0].offset, 6 + 9);
+        assert_eq!(writes[0].data.value, "SEKRET_0123");
+    }
+
+    #[test]
+    fn overwritten_range_loses_its_label() {
+        let tracker = TaintTracker::new();
+        tracker.taint_read(0x100, "fd:3", b"top-secret", false);
+        tracker.taint_read(0x100, "fd:0", b"harmless!!", false);
+        tracker.observe_write(1, &[(0x100, b"harmless!!".to_vec())]);
+
+        let writes = tracker.take_writes();
+        assert!(
+            writes
+                .iter()
+                .all(|w| w.data.sources[0].short_id() == "FileRead: fd:0")
+        );
+    }
+
+    #[test]
+    fn ranges_at_the_top_of_memory_do_not_wrap() {
+        let tracker = TaintTracker::new();
+        let top = u32::MAX - 9;
+        tracker.taint_read(top, "fd:3", b"top-secret", false);
+        // overlaps the tail of the first range and would end past u32::MAX
+        tracker.taint_read(u32::MAX - 3, "fd:0", b"tail", false);
+        tracker.observe_write(1, &[(top, b"top-secnot".to_vec())]);
+
+        let writes = tracker.take_writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].evidence, TaintEvidence::Range);
+        assert_eq!(writes[0].data.value, "top-se");
+    }
+}